@@ -1,7 +1,7 @@
 use anyhow::Result;
 use bitcoin::network::constants::Network;
-use bitcoin::secp256k1::Secp256k1;
-use bitcoin::util::bip32::ExtendedPrivKey;
+use bitcoin::secp256k1::{key::ONE_KEY, Secp256k1};
+use bitcoin::util::bip32::{ChainCode, ExtendedPrivKey};
 use clap::arg_enum;
 // use secp256k1::Secp256k1;
 use std::convert::TryFrom;
@@ -133,6 +133,25 @@ impl HDPrivKey {
     pub fn to_base58(&self) -> String {
         format!("{}", self.ext_key)
     }
+
+    // SecretKey can't hold zeroes, so we overwrite it with the valid key 1 instead.
+    fn wipe(&mut self) {
+        // Volatile writes so the compiler can't elide them in drop
+        unsafe {
+            std::ptr::write_volatile(&mut self.ext_key.private_key.key, ONE_KEY);
+            std::ptr::write_volatile(
+                &mut self.ext_key.chain_code,
+                ChainCode::from(&[0u8; 32][..]),
+            );
+        }
+        std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+impl Drop for HDPrivKey {
+    fn drop(&mut self) {
+        self.wipe();
+    }
 }
 
 #[cfg(test)]
@@ -158,6 +177,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn drop_clears_key_material() {
+        let master_seed = hex::decode("04c3fca05109eb0d188971e66ba949a4a4547b6c0eceddcb3e796e6ddb7d489826901932dbab5d6aa71421de1d119b4d472a92702e2642b2d9259d4766d84284").unwrap();
+        // boxed, so the allocator sees the key's memory when it is freed
+        let key = Box::new(HDPrivKey::new(Seed::from_bytes(master_seed.clone())).unwrap());
+        let private_key = key.ext_key.private_key.key[..].to_vec();
+        assert!(!crate::wipe::freed_unwiped(&private_key, move || drop(key)));
+
+        let key = Box::new(HDPrivKey::new(Seed::from_bytes(master_seed)).unwrap());
+        let chain_code = key.ext_key.chain_code.as_bytes().to_vec();
+        assert!(!crate::wipe::freed_unwiped(&chain_code, move || drop(key)));
+    }
+
     #[test]
     fn test_account1() {
         // Generated with https://iancoleman.io/bip39/
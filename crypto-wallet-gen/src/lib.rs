@@ -1,10 +1,17 @@
 mod bip32;
 mod mnemonics;
+mod random;
 mod seed;
 mod wallets;
+#[cfg(any(test, feature = "wipe-check"))]
+pub mod wipe;
 
 pub use bip32::{Bip44DerivationPath, CoinType, HDPrivKey};
-pub use mnemonics::{bip39::Bip39Mnemonic, scrypt::ScryptMnemonic, Mnemonic, MnemonicFactory};
+pub use mnemonics::{
+    bip39::Bip39Mnemonic,
+    scrypt::{Params as ScryptParams, ScryptMnemonic},
+    Mnemonic, MnemonicFactory,
+};
+pub use random::{secure_rng, CompositeRng};
 pub use seed::Seed;
-pub use wallets::bitcoin::BitcoinWallet;
-mod random;
+pub use wallets::{bitcoin::BitcoinWallet, Wallet};
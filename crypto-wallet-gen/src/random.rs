@@ -1,12 +1,20 @@
 use anyhow::Result;
-use rand::RngCore;
+use rand::{CryptoRng, RngCore, SeedableRng};
 use zeroize::Zeroize;
 
+/// CompositeRng XORs the output of two random generators, so the result is at
+/// least as unpredictable as the better of the two.
 pub struct CompositeRng<Rng1: RngCore, Rng2: RngCore> {
     rng1: Rng1,
     rng2: Rng2,
 }
 
+impl<Rng1: RngCore, Rng2: RngCore> CompositeRng<Rng1, Rng2> {
+    pub fn new(rng1: Rng1, rng2: Rng2) -> Self {
+        Self { rng1, rng2 }
+    }
+}
+
 impl<Rng1: RngCore, Rng2: RngCore> RngCore for CompositeRng<Rng1, Rng2> {
     fn next_u32(&mut self) -> u32 {
         self.rng1.next_u32() ^ self.rng2.next_u32()
@@ -36,13 +44,16 @@ impl<Rng1: RngCore, Rng2: RngCore> RngCore for CompositeRng<Rng1, Rng2> {
     }
 }
 
+// Mixing in at least one cryptographically secure generator makes the composite secure as well.
+impl<Rng1: RngCore + CryptoRng, Rng2: RngCore> CryptoRng for CompositeRng<Rng1, Rng2> {}
+
 #[macro_export]
 macro_rules! composite_rng {
     ($rng1:expr, $rng2:expr) => {
-        $crate::random::CompositeRng::new($rng1, $rng2)
+        $crate::CompositeRng::new($rng1, $rng2)
     };
     ($rng1:expr, $rng2:expr, $($tail:expr),+) => {
-        $crate::random::CompositeRng::new($rng1, composite_rng!($rng2, $($tail),+))
+        $crate::CompositeRng::new($rng1, composite_rng!($rng2, $($tail),+))
     };
 }
 
@@ -110,3 +121,80 @@ impl<R: rand_core_5::RngCore> RngCore for RandCore5Wrapper<R> {
             .map_err(|err| rand::Error::new(err.take_inner()))
     }
 }
+
+/// Random generator mixing the OS generator, a ChaCha20 generator seeded from the OS
+/// and - if the platform timer supports it - CPU jitter entropy.
+pub fn secure_rng() -> Result<impl RngCore + CryptoRng> {
+    let chacha = rand_chacha::ChaCha20Rng::from_rng(rand::rngs::OsRng)?;
+    let jitter = RngOrZeroes(jitter_rng().map(RandCore5Wrapper));
+    Ok(composite_rng!(rand::rngs::OsRng, chacha, jitter))
+}
+
+// rand_jitter only offers a default timer with its broken "std" feature, so we supply our own.
+// Returns None if the timer doesn't pass the jitter quality tests on this platform.
+fn jitter_rng() -> Option<rand_jitter::JitterRng<fn() -> u64>> {
+    fn get_nstime() -> u64 {
+        let dur = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        // The jitter is in the lower bits, so mixing seconds and nanoseconds is fine.
+        dur.as_secs() << 30 | dur.subsec_nanos() as u64
+    }
+
+    let mut rng = rand_jitter::JitterRng::new_with_timer(get_nstime as fn() -> u64);
+    let rounds = rng.test_timer().ok()?;
+    rng.set_rounds(rounds);
+    Some(rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct ConstRng(u8);
+    impl RngCore for ConstRng {
+        fn next_u32(&mut self) -> u32 {
+            u32::from_le_bytes([self.0; 4])
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            u64::from_le_bytes([self.0; 8])
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            dest.iter_mut().for_each(|b| *b = self.0);
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn composite_xors_outputs() {
+        let mut rng = composite_rng!(ConstRng(0b1010), ConstRng(0b0110), ConstRng(0b0001));
+        let mut dest = [0u8; 4];
+        rng.fill_bytes(&mut dest);
+        assert_eq!([0b1101; 4], dest);
+        assert_eq!(u32::from_le_bytes([0b1101; 4]), rng.next_u32());
+    }
+
+    #[test]
+    fn missing_rng_is_neutral() {
+        let mut rng = composite_rng!(ConstRng(0xAB), RngOrZeroes::<ConstRng>(None));
+        let mut dest = [0u8; 8];
+        rng.try_fill_bytes(&mut dest).unwrap();
+        assert_eq!([0xAB; 8], dest);
+    }
+
+    #[test]
+    fn secure_rng_produces_distinct_output() {
+        let mut rng = secure_rng().unwrap();
+        let mut first = [0u8; 32];
+        let mut second = [0u8; 32];
+        rng.fill_bytes(&mut first);
+        rng.fill_bytes(&mut second);
+        assert_ne!(first, second);
+    }
+}
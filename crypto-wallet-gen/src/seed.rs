@@ -1,6 +1,8 @@
 #[cfg(test)]
 use anyhow::Result;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
+#[derive(Zeroize, ZeroizeOnDrop)]
 pub struct Seed {
     seed: Vec<u8>,
 }
@@ -14,8 +16,9 @@ impl Seed {
         &self.seed
     }
 
-    pub fn into_bytes(self) -> Vec<u8> {
-        self.seed
+    /// Hands the bytes over to the caller, who is responsible for wiping them.
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        std::mem::take(&mut self.seed)
     }
 
     #[cfg(test)]
@@ -24,3 +27,21 @@ impl Seed {
         Ok(Self { seed })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drop_clears_seed() {
+        let bytes: [u8; 32] = rand::random();
+        let seed = Seed::from_bytes(bytes.to_vec());
+        assert!(!crate::wipe::freed_unwiped(&bytes, move || drop(seed)));
+    }
+
+    #[test]
+    fn into_bytes_keeps_content() {
+        let seed = Seed::from_hex("00112233").unwrap();
+        assert_eq!(vec![0x00, 0x11, 0x22, 0x33], seed.into_bytes());
+    }
+}
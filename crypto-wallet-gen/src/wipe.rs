@@ -0,0 +1,74 @@
+// Test allocator that notices secrets being freed without having been wiped.
+// Tests only hold a copy of the first bytes of a secret on the stack and check
+// every heap block freed while the secret's owner is dropped.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Mutex;
+
+const PATTERN_LEN: usize = 16;
+
+static PATTERN: [AtomicU8; PATTERN_LEN] = [const { AtomicU8::new(0) }; PATTERN_LEN];
+static ARMED: AtomicBool = AtomicBool::new(false);
+static FOUND: AtomicBool = AtomicBool::new(false);
+static CHECK: Mutex<()> = Mutex::new(());
+
+struct WipeCheck;
+
+unsafe impl GlobalAlloc for WipeCheck {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if ARMED.load(Ordering::SeqCst) && layout.size() >= PATTERN_LEN {
+            let pattern: [u8; PATTERN_LEN] =
+                std::array::from_fn(|i| PATTERN[i].load(Ordering::SeqCst));
+            let block = std::slice::from_raw_parts(ptr, layout.size());
+            if block.windows(PATTERN_LEN).any(|w| w == pattern) {
+                FOUND.store(true, Ordering::SeqCst);
+            }
+        }
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: WipeCheck = WipeCheck;
+
+/// whether a heap block still holding the start of `secret` was freed while running `f`
+pub fn freed_unwiped(secret: &[u8], f: impl FnOnce()) -> bool {
+    assert!(
+        secret.len() >= PATTERN_LEN,
+        "secret is too short to look for"
+    );
+    let _check = CHECK.lock().unwrap_or_else(|e| e.into_inner());
+
+    for (p, b) in PATTERN.iter().zip(secret) {
+        p.store(*b, Ordering::SeqCst);
+    }
+    FOUND.store(false, Ordering::SeqCst);
+    ARMED.store(true, Ordering::SeqCst);
+    f();
+    ARMED.store(false, Ordering::SeqCst);
+
+    FOUND.load(Ordering::SeqCst)
+}
+
+#[test]
+fn test_freed_unwiped() {
+    let secret: [u8; 32] = rand::random();
+    let leaked = secret.to_vec();
+    assert!(freed_unwiped(&secret, move || drop(leaked)));
+
+    let wiped = zeroize::Zeroizing::new(secret.to_vec());
+    assert!(!freed_unwiped(&secret, move || drop(wiped)));
+}
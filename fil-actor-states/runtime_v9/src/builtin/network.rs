@@ -1,8 +1,13 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::fmt;
+use std::str::FromStr;
+
+use fvm_shared::address::Network;
 use fvm_shared::clock::EPOCH_DURATION_SECONDS;
 pub use fvm_shared::BLOCKS_PER_EPOCH as EXPECTED_LEADERS_PER_EPOCH;
+use serde::{Deserialize, Serialize};
 
 pub const SECONDS_IN_HOUR: i64 = 3600;
 pub const SECONDS_IN_DAY: i64 = 86400;
@@ -10,3 +15,64 @@ pub const SECONDS_IN_YEAR: i64 = 31556925;
 pub const EPOCHS_IN_HOUR: i64 = SECONDS_IN_HOUR / EPOCH_DURATION_SECONDS;
 pub const EPOCHS_IN_DAY: i64 = SECONDS_IN_DAY / EPOCH_DURATION_SECONDS;
 pub const EPOCHS_IN_YEAR: i64 = SECONDS_IN_YEAR / EPOCH_DURATION_SECONDS;
+
+/// Unix time of the mainnet genesis block, 2020-08-24 22:00:00 UTC
+pub const MAINNET_GENESIS_TIMESTAMP: u64 = 1598306400;
+/// Unix time of the calibnet genesis block after its 2022 reset, 2022-11-01 18:13:00 UTC
+pub const CALIBNET_GENESIS_TIMESTAMP: u64 = 1667326380;
+
+/// Networks the builtin actors are deployed on, each with its own policy.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Default, Clone, Copy)]
+pub enum ChainType {
+    #[default]
+    Mainnet,
+    Calibnet,
+    Butterfly,
+    /// Local 2k networks, with 2KiB and 8MiB sectors
+    Devnet,
+}
+
+impl ChainType {
+    /// Address prefix network: f for mainnet, t for the test networks.
+    pub fn network(&self) -> Network {
+        match self {
+            ChainType::Mainnet => Network::Mainnet,
+            ChainType::Calibnet | ChainType::Butterfly | ChainType::Devnet => Network::Testnet,
+        }
+    }
+
+    /// Unix time of epoch 0, None for networks that are reset or run locally
+    /// with a new genesis each time.
+    pub fn genesis_timestamp(&self) -> Option<u64> {
+        match self {
+            ChainType::Mainnet => Some(MAINNET_GENESIS_TIMESTAMP),
+            ChainType::Calibnet => Some(CALIBNET_GENESIS_TIMESTAMP),
+            ChainType::Butterfly | ChainType::Devnet => None,
+        }
+    }
+}
+
+impl FromStr for ChainType {
+    type Err = &'static str;
+
+    fn from_str(chain: &str) -> Result<Self, Self::Err> {
+        match chain.to_lowercase().as_str() {
+            "mainnet" => Ok(ChainType::Mainnet),
+            "calibnet" | "calibrationnet" => Ok(ChainType::Calibnet),
+            "butterfly" | "butterflynet" => Ok(ChainType::Butterfly),
+            "devnet" | "2k" => Ok(ChainType::Devnet),
+            _ => Err("Chain type not supported"),
+        }
+    }
+}
+
+impl fmt::Display for ChainType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainType::Mainnet => write!(f, "mainnet"),
+            ChainType::Calibnet => write!(f, "calibnet"),
+            ChainType::Butterfly => write!(f, "butterfly"),
+            ChainType::Devnet => write!(f, "devnet"),
+        }
+    }
+}
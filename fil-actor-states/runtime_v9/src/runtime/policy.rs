@@ -5,6 +5,8 @@ use fvm_shared::sector::{RegisteredPoStProof, RegisteredSealProof, StoragePower}
 use num_traits::FromPrimitive;
 use serde::{Deserialize, Serialize};
 
+use crate::network::ChainType;
+
 // A trait for runtime policy configuration
 pub trait RuntimePolicy {
     fn policy(&self) -> &Policy;
@@ -84,7 +86,7 @@ pub struct Policy {
     pub pre_commit_challenge_delay: ChainEpoch,
 
     /// Lookback from the deadline's challenge window opening from which to sample chain randomness for the challenge seed.
-
+    ///
     /// This lookback exists so that deadline windows can be non-overlapping (which make the programming simpler)
     /// but without making the miner wait for chain stability before being able to start on PoSt computation.
     /// The challenge is available this many epochs before the window is actually open to receiving a PoSt.
@@ -136,6 +138,7 @@ pub struct Policy {
 
     // --- verifreg policy
     /// Minimum verified deal size
+    #[serde(with = "storage_power_str")]
     pub minimum_verified_allocation_size: StoragePower,
     /// Minimum term for a verified data allocation (epochs)
     pub minimum_verified_allocation_term: i64,
@@ -165,6 +168,7 @@ pub struct Policy {
 
     // --- power ---
     /// Minimum miner consensus power
+    #[serde(with = "storage_power_str")]
     pub minimum_consensus_power: StoragePower,
 }
 
@@ -246,6 +250,76 @@ impl Policy {
             ..Policy::mainnet()
         }
     }
+
+    pub fn butterfly() -> Self {
+        Policy {
+            valid_post_proof_type: HashSet::<RegisteredPoStProof>::from([
+                RegisteredPoStProof::StackedDRGWindow512MiBV1,
+                RegisteredPoStProof::StackedDRGWindow32GiBV1,
+                RegisteredPoStProof::StackedDRGWindow64GiBV1,
+            ]),
+            valid_pre_commit_proof_type: HashSet::<RegisteredSealProof>::from([
+                RegisteredSealProof::StackedDRG512MiBV1P1,
+                RegisteredSealProof::StackedDRG32GiBV1P1,
+                RegisteredSealProof::StackedDRG64GiBV1P1,
+            ]),
+            minimum_consensus_power: StoragePower::from(
+                policy_constants::BUTTERFLY_MINIMUM_CONSENSUS_POWER,
+            ),
+            ..Policy::mainnet()
+        }
+    }
+
+    /// 2k networks, with small sectors, small deals and a short pre-commit delay
+    pub fn devnet() -> Self {
+        Policy {
+            pre_commit_challenge_delay: policy_constants::DEVNET_PRE_COMMIT_CHALLENGE_DELAY,
+            valid_post_proof_type: HashSet::<RegisteredPoStProof>::from([
+                RegisteredPoStProof::StackedDRGWindow2KiBV1,
+                RegisteredPoStProof::StackedDRGWindow8MiBV1,
+            ]),
+            valid_pre_commit_proof_type: HashSet::<RegisteredSealProof>::from([
+                RegisteredSealProof::StackedDRG2KiBV1P1,
+                RegisteredSealProof::StackedDRG8MiBV1P1,
+            ]),
+            minimum_verified_allocation_size: StoragePower::from_i32(
+                policy_constants::DEVNET_MINIMUM_VERIFIED_ALLOCATION_SIZE,
+            )
+            .unwrap(),
+            minimum_consensus_power: StoragePower::from(
+                policy_constants::DEVNET_MINIMUM_CONSENSUS_POWER,
+            ),
+            ..Policy::mainnet()
+        }
+    }
+
+    /// The policy the actors of `chain` are built with.
+    pub fn for_network(chain: ChainType) -> Self {
+        match chain {
+            ChainType::Mainnet => Policy::mainnet(),
+            ChainType::Calibnet => Policy::calibnet(),
+            ChainType::Butterfly => Policy::butterfly(),
+            ChainType::Devnet => Policy::devnet(),
+        }
+    }
+}
+
+/// Storage power as a decimal string, readable in TOML and JSON and without
+/// the 64 bit limit of their integers.
+mod storage_power_str {
+    use std::str::FromStr;
+
+    use fvm_shared::sector::StoragePower;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(power: &StoragePower, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(power)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<StoragePower, D::Error> {
+        let s = String::deserialize(d)?;
+        StoragePower::from_str(&s).map_err(de::Error::custom)
+    }
 }
 
 pub mod policy_constants {
@@ -327,7 +401,7 @@ pub mod policy_constants {
     pub const PRE_COMMIT_CHALLENGE_DELAY: ChainEpoch = 150;
 
     /// Lookback from the deadline's challenge window opening from which to sample chain randomness for the challenge seed.
-
+    ///
     /// This lookback exists so that deadline windows can be non-overlapping (which make the programming simpler)
     /// but without making the miner wait for chain stability before being able to start on PoSt computation.
     /// The challenge is available this many epochs before the window is actually open to receiving a PoSt.
@@ -392,4 +466,81 @@ pub mod policy_constants {
 
     pub const CALIBNET_MINIMUM_CONSENSUS_POWER: i64 = 32 << 30;
     pub const MAINNET_MINIMUM_CONSENSUS_POWER: i64 = 10 << 40;
+    pub const BUTTERFLY_MINIMUM_CONSENSUS_POWER: i64 = 2 << 30;
+    pub const DEVNET_MINIMUM_CONSENSUS_POWER: i64 = 2048;
+
+    pub const DEVNET_MINIMUM_VERIFIED_ALLOCATION_SIZE: i32 = 256;
+    pub const DEVNET_PRE_COMMIT_CHALLENGE_DELAY: ChainEpoch = 10;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_network() {
+        assert_eq!(Policy::for_network(ChainType::Mainnet), Policy::mainnet());
+        assert_eq!(Policy::for_network(ChainType::Calibnet), Policy::calibnet());
+
+        let butterfly = Policy::for_network(ChainType::Butterfly);
+        assert_eq!(
+            butterfly.minimum_consensus_power,
+            StoragePower::from(2i64 << 30)
+        );
+        assert!(butterfly
+            .valid_pre_commit_proof_type
+            .contains(&RegisteredSealProof::StackedDRG512MiBV1P1));
+
+        let devnet = Policy::for_network(ChainType::Devnet);
+        assert_eq!(devnet.minimum_consensus_power, StoragePower::from(2048));
+        assert_eq!(devnet.pre_commit_challenge_delay, 10);
+        assert!(devnet
+            .valid_post_proof_type
+            .contains(&RegisteredPoStProof::StackedDRGWindow2KiBV1));
+        assert!(!devnet
+            .valid_pre_commit_proof_type
+            .contains(&RegisteredSealProof::StackedDRG32GiBV1P1));
+        assert_eq!(
+            devnet.wpost_proving_period,
+            Policy::mainnet().wpost_proving_period
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        for chain in [
+            ChainType::Mainnet,
+            ChainType::Calibnet,
+            ChainType::Butterfly,
+            ChainType::Devnet,
+        ] {
+            let policy = Policy::for_network(chain);
+            let json = serde_json::to_string(&policy).unwrap();
+            assert_eq!(serde_json::from_str::<Policy>(&json).unwrap(), policy);
+            let toml = toml::to_string(&policy).unwrap();
+            assert_eq!(toml::from_str::<Policy>(&toml).unwrap(), policy);
+        }
+
+        let json = serde_json::to_value(Policy::mainnet()).unwrap();
+        assert_eq!(json["minimum_consensus_power"], "10995116277760");
+        assert_eq!(json["wpost_proving_period"], 2880);
+    }
+
+    #[test]
+    fn test_custom_policy() {
+        // a test network with a one hour proving period, described in TOML
+        let mut toml = toml::Value::try_from(Policy::devnet()).unwrap();
+        let table = toml.as_table_mut().unwrap();
+        table.insert("wpost_proving_period".into(), 120.into());
+        table.insert("wpost_challenge_window".into(), 10.into());
+        let policy: Policy = toml::from_str(&toml::to_string(&toml).unwrap()).unwrap();
+        assert_eq!(policy.wpost_proving_period, 120);
+        assert_eq!(policy.wpost_challenge_window, 10);
+        assert_eq!(policy.minimum_consensus_power, StoragePower::from(2048));
+
+        let invalid = toml::to_string(&toml)
+            .unwrap()
+            .replace("\"2048\"", "\"2k\"");
+        assert!(toml::from_str::<Policy>(&invalid).is_err());
+    }
 }
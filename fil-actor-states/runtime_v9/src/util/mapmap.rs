@@ -4,9 +4,9 @@ use fvm_ipld_blockstore::Blockstore;
 use fvm_ipld_hamt::{BytesKey, Error};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use serde::__private::PhantomData;
 use std::collections::btree_map::Entry::{Occupied, Vacant};
 use std::collections::BTreeMap;
+use std::marker::PhantomData;
 
 // MapMap stores multiple values per key in a Hamt of Hamts
 // Every element stored has a primary and secondary key
@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Builtin actor types, policies and state decoders for the wallet.
+//!
+//! The vendored runtimes are versioned crates whose layout follows the
+//! builtin-actors repository. This crate names the parts the wallet relies on
+//! so callers don't depend on a particular runtime version.
+
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::CborStore;
+use serde::de::DeserializeOwned;
+
+pub use fil_actors_runtime_v9 as v9;
+
+pub use v9::network::ChainType;
+pub use v9::runtime::builtins::Type as BuiltinActor;
+pub use v9::runtime::{policy_constants, Policy, RuntimePolicy};
+pub use v9::{actor_error, ActorContext, ActorDowncast, ActorError, AsActorError};
+pub use v9::{network, singletons};
+pub use v9::{BatchReturn, BatchReturnGen, FailCode, MessageAccumulator};
+
+/// HAMTs, AMTs and their keys, as the actors lay them out in state
+pub mod collections {
+    pub use fil_actors_runtime_v9::runtime::EMPTY_ARR_CID;
+    pub use fil_actors_runtime_v9::{
+        make_empty_map, make_map_with_root, make_map_with_root_and_bitwidth, parse_uint_key,
+        u64_key, Array, Keyer, Map, MapMap, Multimap, Set, SetMultimap, HAMT_BIT_WIDTH,
+    };
+}
+
+/// State of an actor stored at `head`, decoded as `S`
+pub fn decode_state<S, BS>(store: &BS, head: &Cid) -> anyhow::Result<S>
+where
+    S: DeserializeOwned,
+    BS: Blockstore,
+{
+    store
+        .get_cbor(head)?
+        .ok_or_else(|| anyhow::anyhow!("state {} not found", head))
+}
+
+/// Value at `key` in the HAMT at `root`, with the default bit width
+pub fn map_get<V, BS>(
+    store: &BS,
+    root: &Cid,
+    key: &dyn collections::Keyer,
+) -> anyhow::Result<Option<V>>
+where
+    V: DeserializeOwned + serde::Serialize + Clone,
+    BS: Blockstore,
+{
+    let map: collections::Map<BS, V> = collections::make_map_with_root(root, store)?;
+    Ok(map.get(&key.key())?.cloned())
+}
+
+#[cfg(test)]
+mod test {
+    use cid::multihash::Code;
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::tuple::*;
+    use fvm_shared::{address::Address, error::ExitCode};
+
+    use super::{collections::Keyer, *};
+
+    #[derive(Debug, PartialEq, Serialize_tuple, Deserialize_tuple)]
+    struct State {
+        owner: Address,
+        approvals: u64,
+    }
+
+    #[test]
+    fn test_decode_state() {
+        let store = MemoryBlockstore::new();
+        let state = State {
+            owner: Address::new_id(1000),
+            approvals: 2,
+        };
+        let head = store.put_cbor(&state, Code::Blake2b256).unwrap();
+        assert_eq!(decode_state::<State, _>(&store, &head).unwrap(), state);
+        assert!(decode_state::<State, _>(&store, &collections::EMPTY_ARR_CID).is_err());
+
+        let mut map = collections::make_empty_map(&store, collections::HAMT_BIT_WIDTH);
+        map.set(Address::new_id(1000).key(), 7u64).unwrap();
+        let root = map.flush().unwrap();
+        assert_eq!(
+            map_get::<u64, _>(&store, &root, &Address::new_id(1000)).unwrap(),
+            Some(7)
+        );
+        assert_eq!(map_get::<u64, _>(&store, &root, &1001u64).unwrap(), None);
+    }
+
+    #[test]
+    fn test_facade() {
+        assert_eq!(singletons::INIT_ACTOR_ADDR, Address::new_id(1));
+        assert_eq!(BuiltinActor::Multisig.name(), "multisig");
+        assert_eq!(network::EPOCHS_IN_DAY, 2880);
+
+        let mainnet = Policy::mainnet();
+        assert_eq!(
+            mainnet.max_control_addresses,
+            policy_constants::MAX_CONTROL_ADDRESSES
+        );
+        assert_ne!(
+            Policy::calibnet().minimum_consensus_power,
+            mainnet.minimum_consensus_power
+        );
+
+        let error = actor_error!(illegal_argument; "bad threshold {}", 3);
+        assert_eq!(error.exit_code(), ExitCode::USR_ILLEGAL_ARGUMENT);
+        assert_eq!(error.msg(), "bad threshold 3");
+
+        let mut batch = BatchReturnGen::new(2);
+        batch.add_success();
+        batch.add_fail(ExitCode::USR_NOT_FOUND);
+        let batch = batch.gen();
+        assert_eq!(batch.success_count, 1);
+        assert!(!batch.all_ok());
+    }
+}
@@ -0,0 +1,123 @@
+use fil_actor_states::ChainType;
+use fvm_shared::sector::StoragePower;
+
+// Limits and addresses of the builtin actors the wallet checks messages
+// against, taken from the runtime through fil_actor_states
+
+pub use fil_actor_states::{
+    network::{
+        EPOCHS_IN_DAY, EPOCHS_IN_HOUR, EPOCHS_IN_YEAR, SECONDS_IN_DAY, SECONDS_IN_HOUR,
+        SECONDS_IN_YEAR,
+    },
+    policy_constants,
+    singletons::{
+        BURNT_FUNDS_ACTOR_ID, CRON_ACTOR_ID, DATACAP_TOKEN_ACTOR_ADDR, DATACAP_TOKEN_ACTOR_ID,
+        INIT_ACTOR_ADDR, INIT_ACTOR_ID, REWARD_ACTOR_ID, STORAGE_MARKET_ACTOR_ADDR,
+        STORAGE_MARKET_ACTOR_ID, STORAGE_POWER_ACTOR_ADDR, STORAGE_POWER_ACTOR_ID, SYSTEM_ACTOR_ID,
+        VERIFIED_REGISTRY_ACTOR_ADDR, VERIFIED_REGISTRY_ACTOR_ID,
+    },
+};
+
+/// Subset of the runtime policy the wallet validates params with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Policy {
+    /// Maximum number of control addresses a miner may register.
+    pub max_control_addresses: usize,
+    /// Maximum length allowed for any on-chain peer ID.
+    pub max_peer_id_length: usize,
+    /// Maximum amount of data that can be stored in multiaddrs.
+    pub max_multiaddr_data: usize,
+    /// Minimum verified deal size
+    pub minimum_verified_allocation_size: StoragePower,
+    /// Minimum term for a verified data allocation (epochs)
+    pub minimum_verified_allocation_term: i64,
+    /// Maximum term for a verified data allocaion (epochs)
+    pub maximum_verified_allocation_term: i64,
+    /// Maximum time a verified allocation can be active without being claimed (epochs).
+    pub maximum_verified_allocation_expiration: i64,
+    /// The number of blocks between payouts for deals
+    pub deal_updates_interval: i64,
+}
+
+impl Policy {
+    pub fn mainnet() -> Self {
+        Policy::from(&fil_actor_states::Policy::mainnet())
+    }
+
+    pub fn for_network(chain: ChainType) -> Self {
+        Policy::from(&fil_actor_states::Policy::for_network(chain))
+    }
+
+    /// Policy of a network not built in, from the JSON of the full runtime
+    /// policy
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let policy: fil_actor_states::Policy = serde_json::from_str(json)
+            .map_err(|e| anyhow::anyhow!("invalid actor policy: {}", e))?;
+        Ok(Policy::from(&policy))
+    }
+}
+
+impl From<&fil_actor_states::Policy> for Policy {
+    fn from(policy: &fil_actor_states::Policy) -> Self {
+        Policy {
+            max_control_addresses: policy.max_control_addresses,
+            max_peer_id_length: policy.max_peer_id_length,
+            max_multiaddr_data: policy.max_multiaddr_data,
+            minimum_verified_allocation_size: policy.minimum_verified_allocation_size.clone(),
+            minimum_verified_allocation_term: policy.minimum_verified_allocation_term,
+            maximum_verified_allocation_term: policy.maximum_verified_allocation_term,
+            maximum_verified_allocation_expiration: policy.maximum_verified_allocation_expiration,
+            deal_updates_interval: policy.deal_updates_interval,
+        }
+    }
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy::mainnet()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use fvm_shared::{address::Address, bigint::BigInt};
+
+    use super::*;
+
+    #[test]
+    fn test_mainnet_policy() {
+        let policy = Policy::mainnet();
+        assert_eq!(
+            policy.max_control_addresses,
+            policy_constants::MAX_CONTROL_ADDRESSES
+        );
+        assert_eq!(policy.minimum_verified_allocation_term, 180 * EPOCHS_IN_DAY);
+        assert_eq!(policy.deal_updates_interval, EPOCHS_IN_DAY);
+        assert_eq!(INIT_ACTOR_ADDR, Address::new_id(1));
+        assert_eq!(Policy::for_network(ChainType::Mainnet), policy);
+    }
+
+    #[test]
+    fn test_policy_from_json() {
+        let mut runtime = fil_actor_states::Policy::mainnet();
+        runtime.max_control_addresses = 3;
+        let policy = Policy::from_json(&serde_json::to_string(&runtime).unwrap()).unwrap();
+        assert_eq!(policy.max_control_addresses, 3);
+        assert_eq!(
+            policy.minimum_verified_allocation_size,
+            Policy::mainnet().minimum_verified_allocation_size
+        );
+        // every field of the runtime policy is needed
+        assert!(Policy::from_json(r#"{"max_control_addresses": 3}"#).is_err());
+    }
+
+    #[test]
+    fn test_devnet_policy() {
+        let policy = Policy::for_network(ChainType::Devnet);
+        assert_eq!(policy.minimum_verified_allocation_size, BigInt::from(256));
+        assert_eq!(
+            policy.max_control_addresses,
+            Policy::mainnet().max_control_addresses
+        );
+    }
+}
@@ -0,0 +1,457 @@
+use std::{
+    collections::HashSet,
+    io::{self, BufRead, BufReader, Read, Take, Write},
+};
+
+use cid::{
+    multihash::{Code, MultihashDigest},
+    Cid,
+};
+use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
+use fvm_ipld_encoding::{to_vec, Cbor, DAG_CBOR};
+use serde::{Deserialize, Serialize};
+
+use crate::message::SignedMessage;
+
+// Content addressed archives, CARv1 and CARv2, to move state and signed
+// messages around as files. Blocks are checked against their CID on the way in.
+
+/// Larger headers or blocks are refused rather than allocated
+pub const MAX_BLOCK_SIZE: usize = 1 << 24;
+
+/// CARv2 files start with this, a CARv1 header of version 2 and no roots
+const V2_PRAGMA: [u8; 11] = [
+    0x0a, 0xa1, 0x67, 0x76, 0x65, 0x72, 0x73, 0x69, 0x6f, 0x6e, 0x02,
+];
+/// Characteristics, data offset, data size and index offset
+const V2_HEADER_LEN: u64 = 40;
+
+const IDENTITY: u64 = 0;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CarHeader {
+    #[serde(default)]
+    pub roots: Vec<Cid>,
+    pub version: u64,
+}
+
+/// Reads blocks one at a time, checking each against its CID
+pub struct CarReader<R> {
+    reader: Take<BufReader<R>>,
+    header: CarHeader,
+    /// version of the file, the blocks are always in a CARv1 payload
+    version: u64,
+}
+
+impl<R: Read> CarReader<R> {
+    pub fn new(reader: R) -> anyhow::Result<Self> {
+        let mut reader = BufReader::new(reader).take(u64::MAX);
+        let header = read_header(&mut reader)?;
+        match header.version {
+            1 => Ok(Self {
+                reader,
+                header,
+                version: 1,
+            }),
+            2 => {
+                let mut v2 = [0; V2_HEADER_LEN as usize];
+                reader
+                    .read_exact(&mut v2)
+                    .map_err(|_| anyhow::anyhow!("truncated CARv2 header"))?;
+                let field = |i: usize| u64::from_le_bytes(v2[i..i + 8].try_into().unwrap());
+                let (data_offset, data_size) = (field(16), field(24));
+                let skip = data_offset
+                    .checked_sub(V2_PRAGMA.len() as u64 + V2_HEADER_LEN)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("CARv2 data offset {} is too small", data_offset)
+                    })?;
+                let mut inner = reader.into_inner();
+                io::copy(&mut (&mut inner).take(skip), &mut io::sink())?;
+                let mut reader = inner.take(data_size);
+                let header = read_header(&mut reader)?;
+                if header.version != 1 {
+                    return Err(anyhow::anyhow!(
+                        "CARv2 payload of version {}, expected 1",
+                        header.version
+                    ));
+                }
+                Ok(Self {
+                    reader,
+                    header,
+                    version: 2,
+                })
+            }
+            version => Err(anyhow::anyhow!("unsupported CAR version {}", version)),
+        }
+    }
+
+    pub fn roots(&self) -> &[Cid] {
+        &self.header.roots
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Next block, None at the end of the data
+    pub fn next_block(&mut self) -> anyhow::Result<Option<(Cid, Vec<u8>)>> {
+        let section = match read_section(&mut self.reader)? {
+            Some(section) => section,
+            None => return Ok(None),
+        };
+        let mut cursor = io::Cursor::new(&section);
+        let cid =
+            Cid::read_bytes(&mut cursor).map_err(|e| anyhow::anyhow!("invalid CID: {}", e))?;
+        let data = section[cursor.position() as usize..].to_vec();
+        verify_block(&cid, &data)?;
+        Ok(Some((cid, data)))
+    }
+}
+
+impl<R: Read> Iterator for CarReader<R> {
+    type Item = anyhow::Result<(Cid, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block().transpose()
+    }
+}
+
+fn read_header<R: BufRead>(reader: &mut R) -> anyhow::Result<CarHeader> {
+    let header =
+        read_section(reader)?.ok_or_else(|| anyhow::anyhow!("empty file, not a CAR file"))?;
+    fvm_ipld_encoding::from_slice(&header).map_err(|e| anyhow::anyhow!("invalid CAR header: {}", e))
+}
+
+/// A varint length followed by as many bytes, None at a clean end
+fn read_section<R: BufRead>(reader: &mut R) -> anyhow::Result<Option<Vec<u8>>> {
+    if reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+    let mut len: u64 = 0;
+    for i in 0.. {
+        if i == 9 {
+            return Err(anyhow::anyhow!("invalid section length"));
+        }
+        let mut byte = [0];
+        reader
+            .read_exact(&mut byte)
+            .map_err(|_| anyhow::anyhow!("truncated section length"))?;
+        len |= ((byte[0] & 0x7f) as u64) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+    if len as usize > MAX_BLOCK_SIZE {
+        return Err(anyhow::anyhow!("section of {} bytes is too large", len));
+    }
+    let mut section = vec![0; len as usize];
+    reader
+        .read_exact(&mut section)
+        .map_err(|_| anyhow::anyhow!("truncated section of {} bytes", len))?;
+    Ok(Some(section))
+}
+
+/// Fail unless `data` hashes to the multihash of `cid`
+pub fn verify_block(cid: &Cid, data: &[u8]) -> anyhow::Result<()> {
+    let code = Code::try_from(cid.hash().code())
+        .map_err(|_| anyhow::anyhow!("unsupported hash function in {}", cid))?;
+    if code.digest(data) != *cid.hash() {
+        return Err(anyhow::anyhow!("block doesn't match its CID {}", cid));
+    }
+    Ok(())
+}
+
+/// Put every block of a CAR file in `store`, returns the roots
+pub fn load_car<BS: Blockstore, R: Read>(store: &BS, reader: R) -> anyhow::Result<Vec<Cid>> {
+    let mut car = CarReader::new(reader)?;
+    while let Some((cid, data)) = car.next_block()? {
+        store.put_keyed(&cid, &data)?;
+    }
+    Ok(car.header.roots)
+}
+
+/// Writes a CARv1, blocks in the order they are given
+pub struct CarWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> CarWriter<W> {
+    pub fn new(mut writer: W, roots: &[Cid]) -> anyhow::Result<Self> {
+        let header = to_vec(&CarHeader {
+            roots: roots.to_vec(),
+            version: 1,
+        })?;
+        write_section(&mut writer, &[&header])?;
+        Ok(Self { writer })
+    }
+
+    pub fn write_block(&mut self, cid: &Cid, data: &[u8]) -> anyhow::Result<()> {
+        write_section(&mut self.writer, &[&cid.to_bytes(), data])
+    }
+
+    pub fn finish(mut self) -> anyhow::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+fn write_section<W: Write>(writer: &mut W, parts: &[&[u8]]) -> anyhow::Result<()> {
+    let mut len = parts.iter().map(|p| p.len()).sum::<usize>() as u64;
+    while len >= 0x80 {
+        writer.write_all(&[len as u8 | 0x80])?;
+        len >>= 7;
+    }
+    writer.write_all(&[len as u8])?;
+    for part in parts {
+        writer.write_all(part)?;
+    }
+    Ok(())
+}
+
+/// Export `roots` and everything they link to as a CARv1. Blocks come depth
+/// first, links in the order they appear, each block once, so the same DAG
+/// always gives the same file. Inlined identity CIDs are not written.
+pub fn write_car<BS: Blockstore, W: Write>(
+    store: &BS,
+    roots: &[Cid],
+    writer: W,
+) -> anyhow::Result<W> {
+    let mut car = CarWriter::new(writer, roots)?;
+    let mut seen = HashSet::new();
+    let mut stack: Vec<Cid> = roots.iter().rev().copied().collect();
+    while let Some(cid) = stack.pop() {
+        if cid.hash().code() == IDENTITY || !seen.insert(cid) {
+            continue;
+        }
+        let data = store
+            .get(&cid)?
+            .ok_or_else(|| anyhow::anyhow!("block {} not found", cid))?;
+        if cid.codec() == DAG_CBOR {
+            stack.extend(links(&data)?.into_iter().rev());
+        }
+        car.write_block(&cid, &data)?;
+    }
+    car.finish()
+}
+
+/// Same blocks as `write_car`, in a CARv2 without index
+pub fn write_car_v2<BS: Blockstore, W: Write>(
+    store: &BS,
+    roots: &[Cid],
+    mut writer: W,
+) -> anyhow::Result<W> {
+    let data = write_car(store, roots, vec![])?;
+    writer.write_all(&V2_PRAGMA)?;
+    writer.write_all(&[0; 16])?;
+    writer.write_all(&(V2_PRAGMA.len() as u64 + V2_HEADER_LEN).to_le_bytes())?;
+    writer.write_all(&(data.len() as u64).to_le_bytes())?;
+    writer.write_all(&0u64.to_le_bytes())?;
+    writer.write_all(&data)?;
+    writer.flush()?;
+    Ok(writer)
+}
+
+/// CIDs a DAG-CBOR block links to, in order
+pub fn links(data: &[u8]) -> anyhow::Result<Vec<Cid>> {
+    let invalid = || anyhow::anyhow!("invalid DAG-CBOR block");
+    let mut links = vec![];
+    let mut pos = 0;
+    // items left to read, nested ones are added as their containers open
+    let mut remaining: u64 = 1;
+    let head = |pos: &mut usize| -> anyhow::Result<(u8, u64)> {
+        let first = *data.get(*pos).ok_or_else(invalid)?;
+        *pos += 1;
+        let (major, info) = (first >> 5, first & 0x1f);
+        let size = match info {
+            0..=23 => return Ok((major, info as u64)),
+            24 => 1,
+            25 => 2,
+            26 => 4,
+            27 => 8,
+            _ => return Err(invalid()),
+        };
+        let bytes = data
+            .get(*pos..(*pos).saturating_add(size))
+            .ok_or_else(invalid)?;
+        *pos += size;
+        Ok((major, bytes.iter().fold(0, |n, b| n << 8 | *b as u64)))
+    };
+    while remaining > 0 {
+        remaining -= 1;
+        let (major, value) = head(&mut pos)?;
+        match major {
+            0 | 1 | 7 => {}
+            2 | 3 => {
+                pos = pos
+                    .checked_add(value as usize)
+                    .filter(|end| *end <= data.len())
+                    .ok_or_else(invalid)?;
+            }
+            // every item takes at least a byte
+            4 | 5 if value > (data.len() - pos) as u64 => return Err(invalid()),
+            4 => remaining += value,
+            5 => remaining += value * 2,
+            6 if value == 42 => {
+                let (major, len) = head(&mut pos)?;
+                let end = pos
+                    .checked_add(len as usize)
+                    .filter(|end| *end <= data.len())
+                    .ok_or_else(invalid)?;
+                let bytes = &data[pos..end];
+                pos = end;
+                // the multibase identity prefix
+                if major != 2 || bytes.first() != Some(&0) {
+                    return Err(invalid());
+                }
+                links.push(Cid::try_from(&bytes[1..]).map_err(|_| invalid())?);
+            }
+            6 => remaining += 1,
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(links)
+}
+
+/// Signed messages as a CAR file, one root per message in order
+pub fn write_signed_messages<W: Write>(messages: &[SignedMessage], writer: W) -> anyhow::Result<W> {
+    let mut roots = vec![];
+    let mut blocks = vec![];
+    for message in messages {
+        let data = message.marshal_cbor()?;
+        let cid = Cbor::cid(message)?;
+        roots.push(cid);
+        blocks.push((cid, data));
+    }
+    let mut car = CarWriter::new(writer, &roots)?;
+    let mut seen = HashSet::new();
+    for (cid, data) in &blocks {
+        if seen.insert(*cid) {
+            car.write_block(cid, data)?;
+        }
+    }
+    car.finish()
+}
+
+/// The messages of `write_signed_messages`, in the order of the roots
+pub fn read_signed_messages<R: Read>(reader: R) -> anyhow::Result<Vec<SignedMessage>> {
+    let store = MemoryBlockstore::new();
+    let roots = load_car(&store, reader)?;
+    roots
+        .iter()
+        .map(|root| {
+            let data = store
+                .get(root)?
+                .ok_or_else(|| anyhow::anyhow!("message {} not in the file", root))?;
+            Ok(fvm_ipld_encoding::from_slice(&data)?)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use fvm_ipld_encoding::CborStore;
+    use fvm_shared::address::Address;
+
+    use super::*;
+    use crate::{models::account::FlairAccount, types::WalletType, MessageBuilder};
+    use cid::multihash::Multihash;
+
+    fn put<S: Serialize>(store: &MemoryBlockstore, obj: &S) -> Cid {
+        store.put_cbor(obj, Code::Blake2b256).unwrap()
+    }
+
+    /// root -> (a -> leaf, b -> leaf), and an inline identity link
+    fn dag(store: &MemoryBlockstore) -> (Cid, Vec<Cid>) {
+        let leaf = Cid::new_v1(0x55, Code::Sha2_256.digest(b"leaf"));
+        store.put_keyed(&leaf, b"leaf").unwrap();
+        let a = put(store, &("a", vec![leaf]));
+        let b = put(store, &("b", leaf, 7u64));
+        let inline = Cid::new_v1(0x55, Multihash::wrap(IDENTITY, b"inline").unwrap());
+        let root = put(store, &(a, b, inline, "root".to_string()));
+        (root, vec![root, a, leaf, b])
+    }
+
+    #[test]
+    fn test_links() {
+        let store = MemoryBlockstore::new();
+        let (root, order) = dag(&store);
+        let data = store.get(&root).unwrap().unwrap();
+        let found = links(&data).unwrap();
+        assert_eq!(found[..2], [order[1], order[3]]);
+        assert_eq!(found[2].hash().code(), IDENTITY);
+        assert!(links(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let store = MemoryBlockstore::new();
+        let (root, order) = dag(&store);
+
+        let v1 = write_car(&store, &[root], vec![]).unwrap();
+        // deterministic, depth first
+        assert_eq!(write_car(&store, &[root], vec![]).unwrap(), v1);
+        let mut car = CarReader::new(&v1[..]).unwrap();
+        assert_eq!(car.version(), 1);
+        assert_eq!(car.roots(), [root]);
+        let cids: Vec<Cid> = car.by_ref().map(|b| b.unwrap().0).collect();
+        assert_eq!(cids, order);
+
+        let v2 = write_car_v2(&store, &[root], vec![]).unwrap();
+        assert_eq!(&v2[..11], &V2_PRAGMA);
+        let car = CarReader::new(&v2[..]).unwrap();
+        assert_eq!(car.version(), 2);
+        let copy = MemoryBlockstore::new();
+        assert_eq!(load_car(&copy, &v2[..]).unwrap(), vec![root]);
+        for cid in &order {
+            assert_eq!(copy.get(cid).unwrap(), store.get(cid).unwrap());
+        }
+
+        // a missing block fails the export
+        let empty = MemoryBlockstore::new();
+        assert!(write_car(&empty, &[root], vec![]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        let store = MemoryBlockstore::new();
+        let (root, _) = dag(&store);
+        let v1 = write_car(&store, &[root], vec![]).unwrap();
+
+        // a flipped byte in the last block
+        let mut corrupt = v1.clone();
+        *corrupt.last_mut().unwrap() ^= 1;
+        let result: anyhow::Result<Vec<_>> = CarReader::new(&corrupt[..]).unwrap().collect();
+        assert!(result.unwrap_err().to_string().contains("doesn't match"));
+
+        let truncated = &v1[..v1.len() - 3];
+        let result: anyhow::Result<Vec<_>> = CarReader::new(truncated).unwrap().collect();
+        assert!(result.unwrap_err().to_string().contains("truncated"));
+
+        assert!(CarReader::new(&b""[..]).is_err());
+        assert!(CarReader::new(&b"\x05hello"[..]).is_err());
+        let version3 = to_vec(&CarHeader {
+            roots: vec![],
+            version: 3,
+        })
+        .unwrap();
+        let mut file = vec![version3.len() as u8];
+        file.extend(version3);
+        assert!(CarReader::new(&file[..]).is_err());
+    }
+
+    #[test]
+    fn test_signed_messages() {
+        let account = FlairAccount::generate(WalletType::Bls).unwrap();
+        let from: Address = account.get_address().try_into().unwrap();
+        let messages: Vec<SignedMessage> = (0..3)
+            .map(|nonce| {
+                MessageBuilder::new(from, Address::new_id(1000))
+                    .nonce(nonce)
+                    .sign(&account)
+                    .unwrap()
+            })
+            .collect();
+        let file = write_signed_messages(&messages, vec![]).unwrap();
+        assert_eq!(read_signed_messages(&file[..]).unwrap(), messages);
+    }
+}
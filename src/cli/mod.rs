@@ -0,0 +1,1990 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use bip39::{Language, MnemonicType};
+use fil_wallet::{
+    actors::Policy,
+    create_multisig_message, datacap,
+    decode::{message_from_cbor, message_from_json, parse_actor_type, preview_message, ActorType},
+    evm::{self, abi},
+    invoke::{Invoke, MethodRef},
+    market, miner, multisig, multisig_approve_params, multisig_send_propose_params,
+    offline::{summarize, MultisigContext, TransactionFile, UnsignedTransaction},
+    parse_duration, parse_epoch, parse_fil,
+    signer::{remote::SignatureJson, verify, MsgMeta, Signer},
+    verifreg, ChainType, FlairAccount, FlairAddress, Keystore, MessageBuilder, MessageJson,
+    SecretPhrase, SeedDerivation, SignedMessageJson, WalletType,
+};
+use fvm_shared::{
+    address::Address,
+    bigint::BigInt,
+    crypto::signature::{Signature, SignatureType},
+    econ::TokenAmount,
+    message::Message,
+    MethodNum,
+};
+use serde_json::{json, Value};
+use structopt::StructOpt;
+use zeroize::Zeroizing;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "fil-wallet", about = "Filecoin wallet")]
+pub struct Cli {
+    /// network addresses and actor policies are for: mainnet, calibnet, butterfly, devnet
+    #[structopt(long, global = true, default_value = "mainnet")]
+    pub network: ChainType,
+
+    /// print JSON instead of text
+    #[structopt(long, global = true)]
+    pub json: bool,
+
+    /// keystore directory, ~/.fil-wallet/keystore by default
+    #[structopt(long, global = true, env = "FIL_WALLET_PATH", parse(from_os_str))]
+    pub keystore: Option<PathBuf>,
+
+    #[structopt(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum Command {
+    /// Generate a new key in the keystore
+    New {
+        /// secp256k1 or bls
+        #[structopt(long = "type", default_value = "secp256k1")]
+        wallet_type: WalletType,
+    },
+    /// List the addresses of the keystore
+    List,
+    /// Import a key exported by Lotus (hex encoded KeyInfo)
+    Import {
+        /// read the key from a file instead of prompting for it
+        #[structopt(long, parse(from_os_str))]
+        file: Option<PathBuf>,
+    },
+    /// Export a key in the Lotus format
+    Export { address: String },
+    /// Derive a key from a secret phrase
+    Derive {
+        /// secp256k1 or bls
+        #[structopt(long = "type", default_value = "secp256k1")]
+        wallet_type: WalletType,
+        /// generate a new secret phrase instead of prompting for one
+        #[structopt(long)]
+        generate: bool,
+        /// words of the generated phrase: 12, 15, 18, 21 or 24
+        #[structopt(long, default_value = "24")]
+        words: usize,
+        /// derive the seed with scrypt instead of PBKDF2
+        #[structopt(long)]
+        scrypt: bool,
+        /// store the key in the keystore
+        #[structopt(long)]
+        save: bool,
+    },
+    /// Sign hex encoded data, prints the signature in the Lotus format
+    Sign {
+        address: String,
+        /// hex encoded data, or a CID with --cid
+        data: String,
+        /// sign the bytes of a CID
+        #[structopt(long)]
+        cid: bool,
+    },
+    /// Verify a signature made by `sign`
+    Verify {
+        address: String,
+        /// hex encoded data
+        data: String,
+        /// hex encoded signature, type byte first
+        signature: String,
+    },
+    /// Build messages
+    Msg(MsgCommand),
+    /// Multisig wallets
+    Msig(MsigCommand),
+    /// Storage miner owner and worker operations
+    Miner(MinerCommand),
+    /// Storage market escrow
+    Market(MarketCommand),
+    /// DataCap grants and transfers
+    Filplus(FilplusCommand),
+    /// Solidity contracts on the FEVM
+    Evm(EvmCommand),
+    /// Address utilities
+    Address(AddressCommand),
+    /// Air-gapped signing with transaction files
+    Offline(OfflineCommand),
+    /// Send FIL through a Lotus node
+    #[cfg(feature = "rpc")]
+    Send {
+        #[structopt(long)]
+        from: String,
+        #[structopt(long)]
+        to: String,
+        /// amount, in FIL unless a unit is given
+        #[structopt(long)]
+        value: String,
+        /// highest fee to pay, in FIL unless a unit is given
+        #[structopt(long)]
+        max_fee: Option<String>,
+        /// wait for the receipt with this many confirmations
+        #[structopt(long)]
+        wait: Option<u64>,
+        #[structopt(flatten)]
+        api: ApiOpts,
+    },
+    /// Replace a stuck message with a higher premium
+    #[cfg(feature = "rpc")]
+    Replace {
+        /// CID of the pending message
+        cid: String,
+        /// send a zero value message to ourselves instead
+        #[structopt(long)]
+        cancel: bool,
+        /// premium increase, in percent
+        #[structopt(long, default_value = "25")]
+        bump: u64,
+        /// premium to use if higher, in attoFIL
+        #[structopt(long)]
+        gas_premium: Option<String>,
+        /// highest fee to pay, in FIL unless a unit is given
+        #[structopt(long)]
+        max_fee: Option<String>,
+        #[structopt(flatten)]
+        api: ApiOpts,
+    },
+    /// Serve the keystore as a Lotus remote wallet (`lotus daemon --wallet-api`)
+    #[cfg(feature = "server")]
+    Serve {
+        /// listen address
+        #[structopt(long, default_value = "127.0.0.1:1777")]
+        listen: String,
+        /// token allowed to list keys and sign
+        #[structopt(long, env = "FIL_WALLET_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+        /// token also allowed to import, export and delete keys
+        #[structopt(long, env = "FIL_WALLET_ADMIN_TOKEN", hide_env_values = true)]
+        admin_token: Option<String>,
+        /// signing policy (JSON)
+        #[structopt(long, parse(from_os_str))]
+        policy: Option<PathBuf>,
+        /// append audit records to this file
+        #[structopt(long, parse(from_os_str))]
+        audit_log: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum MsgCommand {
+    /// Build a message, signed with --sign
+    Build {
+        #[structopt(long)]
+        to: String,
+        /// amount, in FIL unless a unit is given
+        #[structopt(long, default_value = "0")]
+        value: String,
+        /// method number, or name for builtin and FRC-42 methods
+        #[structopt(long, default_value = "0")]
+        method: MethodRef,
+        /// type of the receiver, to resolve builtin method names
+        #[structopt(long, parse(try_from_str = parse_actor_type))]
+        actor: Option<ActorType>,
+        /// base64 encoded params
+        #[structopt(long)]
+        params: Option<String>,
+        #[structopt(flatten)]
+        opts: MessageOpts,
+    },
+    /// Show what a message does: method name and decoded params
+    Decode {
+        /// Lotus JSON, or CBOR in hex or base64, signed or not
+        message: String,
+        /// type of the receiver, e.g. multisig or storageminer, found from
+        /// the address for singleton actors
+        #[structopt(long, parse(try_from_str = parse_actor_type))]
+        actor: Option<ActorType>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum MsigCommand {
+    /// Create a multisig wallet through the init actor
+    Create {
+        /// signer address, repeat for every signer
+        #[structopt(long = "signer", required = true)]
+        signers: Vec<String>,
+        /// approvals needed, all signers by default
+        #[structopt(long)]
+        threshold: Option<u64>,
+        /// epochs over which the initial balance unlocks, or a duration like "180 days"
+        #[structopt(long, default_value = "0")]
+        unlock_duration: String,
+        /// epoch the unlocking starts at, or a UTC date like "2024-03-01 12:00"
+        #[structopt(long, default_value = "0")]
+        start_epoch: String,
+        /// initial balance, in FIL unless a unit is given
+        #[structopt(long, default_value = "0")]
+        value: String,
+        /// multisig actor code CID (`lotus state actor-cids`), asked from the node when omitted
+        #[structopt(long)]
+        code: Option<String>,
+        #[structopt(flatten)]
+        opts: MessageOpts,
+        #[cfg(feature = "rpc")]
+        #[structopt(flatten)]
+        api: ApiOpts,
+    },
+    /// Propose a transfer from a multisig wallet
+    Propose {
+        #[structopt(long)]
+        msig: String,
+        #[structopt(long)]
+        to: String,
+        /// amount, in FIL unless a unit is given
+        #[structopt(long)]
+        value: String,
+        #[structopt(flatten)]
+        opts: MessageOpts,
+    },
+    /// Approve a pending multisig transaction
+    Approve {
+        #[structopt(long)]
+        msig: String,
+        #[structopt(long)]
+        txn_id: i64,
+        #[structopt(flatten)]
+        opts: MessageOpts,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum MinerCommand {
+    /// Withdraw available balance to the beneficiary
+    Withdraw {
+        /// amount, in FIL unless a unit is given
+        #[structopt(long)]
+        amount: String,
+        #[structopt(flatten)]
+        target: MinerTarget,
+        #[structopt(flatten)]
+        opts: MessageOpts,
+    },
+    /// Start a worker key change and set the control addresses
+    ChangeWorker {
+        #[structopt(long)]
+        worker: String,
+        #[structopt(long = "control")]
+        control: Vec<String>,
+        /// actor policy (JSON of the runtime Policy), the network's by default
+        #[structopt(long, parse(from_os_str))]
+        policy: Option<PathBuf>,
+        #[structopt(flatten)]
+        target: MinerTarget,
+        #[structopt(flatten)]
+        opts: MessageOpts,
+    },
+    /// Apply a worker key change once its delay passed
+    ConfirmWorker {
+        #[structopt(flatten)]
+        target: MinerTarget,
+        #[structopt(flatten)]
+        opts: MessageOpts,
+    },
+    /// Propose a new owner, or confirm when sent by the new owner
+    ChangeOwner {
+        #[structopt(long)]
+        new_owner: String,
+        #[structopt(flatten)]
+        target: MinerTarget,
+        #[structopt(flatten)]
+        opts: MessageOpts,
+    },
+    /// Propose a beneficiary, or confirm when sent by the beneficiary
+    ChangeBeneficiary {
+        #[structopt(long)]
+        beneficiary: String,
+        /// amount it may withdraw, in FIL unless a unit is given
+        #[structopt(long)]
+        quota: String,
+        /// epoch the quota expires at, or a UTC date like "2024-03-01 12:00"
+        #[structopt(long)]
+        expiration: String,
+        #[structopt(flatten)]
+        target: MinerTarget,
+        #[structopt(flatten)]
+        opts: MessageOpts,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum MarketCommand {
+    /// Deposit to the escrow of a client or a provider
+    AddBalance {
+        /// escrow to credit, the sender's by default
+        #[structopt(long)]
+        address: Option<String>,
+        /// amount, in FIL unless a unit is given
+        #[structopt(long)]
+        amount: String,
+        #[structopt(flatten)]
+        opts: MessageOpts,
+    },
+    /// Withdraw unlocked escrow
+    Withdraw {
+        /// escrow to withdraw from, the sender's by default
+        #[structopt(long)]
+        address: Option<String>,
+        /// amount, in FIL unless a unit is given
+        #[structopt(long)]
+        amount: String,
+        #[structopt(flatten)]
+        opts: MessageOpts,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum FilplusCommand {
+    /// Grant DataCap to a client, sent by a notary
+    GrantDatacap {
+        #[structopt(long)]
+        client: String,
+        /// allowance in bytes
+        #[structopt(long)]
+        allowance: String,
+        /// actor policy (JSON of the runtime Policy), the network's by default
+        #[structopt(long, parse(from_os_str))]
+        policy: Option<PathBuf>,
+        #[structopt(flatten)]
+        opts: MessageOpts,
+    },
+    /// Transfer DataCap
+    Transfer {
+        #[structopt(long)]
+        to: String,
+        /// amount in bytes
+        #[structopt(long)]
+        amount: String,
+        #[structopt(flatten)]
+        opts: MessageOpts,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum EvmCommand {
+    /// Call a contract function
+    Invoke {
+        /// f0 or f2 address of the contract
+        #[structopt(long)]
+        contract: String,
+        /// function signature, e.g. "transfer(address,uint256)"
+        #[structopt(long)]
+        sig: String,
+        /// arguments, arrays and tuples in JSON
+        args: Vec<String>,
+        /// amount, in FIL unless a unit is given
+        #[structopt(long, default_value = "0")]
+        value: String,
+        #[structopt(flatten)]
+        opts: MessageOpts,
+    },
+    /// Deploy a contract from hex encoded initcode
+    Deploy {
+        #[structopt(long)]
+        initcode: String,
+        /// amount, in FIL unless a unit is given
+        #[structopt(long, default_value = "0")]
+        value: String,
+        #[structopt(flatten)]
+        opts: MessageOpts,
+    },
+    /// Decode the return of a contract call, base64 as in receipts
+    DecodeReturn {
+        /// function signature with its return types, e.g. "balanceOf(address)(uint256)"
+        #[structopt(long)]
+        sig: String,
+        data: String,
+        /// the call reverted
+        #[structopt(long)]
+        reverted: bool,
+    },
+}
+
+/// miner to call, through a proposal to the multisig owning it with --msig
+#[derive(Debug, StructOpt)]
+pub struct MinerTarget {
+    #[structopt(long)]
+    miner: String,
+    #[structopt(long)]
+    msig: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum AddressCommand {
+    /// Show an address for the selected network
+    Convert { address: String },
+}
+
+/// sender, nonce and gas of a message, amounts in attoFIL
+#[derive(Debug, StructOpt)]
+pub struct MessageOpts {
+    #[structopt(long)]
+    from: String,
+    #[structopt(long, default_value = "0")]
+    nonce: u64,
+    #[structopt(long, default_value = "0")]
+    gas_limit: i64,
+    #[structopt(long, default_value = "0")]
+    gas_fee_cap: String,
+    #[structopt(long, default_value = "0")]
+    gas_premium: String,
+    /// sign with the key of the sender
+    #[structopt(long)]
+    sign: bool,
+    /// print an unsigned transaction file for an offline signer
+    #[structopt(long, conflicts_with = "sign")]
+    offline: bool,
+}
+
+/// Lotus node to talk to
+#[cfg(feature = "rpc")]
+#[derive(Debug, StructOpt)]
+pub struct ApiOpts {
+    #[structopt(
+        long,
+        env = "FIL_WALLET_API",
+        default_value = "http://127.0.0.1:1234/rpc/v0"
+    )]
+    api: String,
+    /// token from `lotus auth create-token --perm write`
+    #[structopt(long, env = "FIL_WALLET_API_TOKEN", hide_env_values = true)]
+    api_token: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum OfflineCommand {
+    /// Describe a transaction file, checking signed ones
+    Show {
+        /// JSON or QR frames, one per line; - for stdin
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+    },
+    /// Sign an unsigned transaction file with the key of its sender
+    Sign {
+        /// JSON or QR frames, one per line; - for stdin
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+        /// don't ask for confirmation
+        #[structopt(long)]
+        yes: bool,
+        /// print the signed transaction as QR frames
+        #[structopt(long)]
+        qr: bool,
+        #[structopt(long, default_value = "200")]
+        fragment_len: usize,
+    },
+    /// Print a transaction file as QR frames, one QR code each
+    Qr {
+        /// JSON or QR frames, one per line; - for stdin
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+        #[structopt(long, default_value = "200")]
+        fragment_len: usize,
+    },
+}
+
+/// User input: secrets are read from the terminal without echo
+pub trait Prompt {
+    fn secret(&mut self, prompt: &str) -> anyhow::Result<Zeroizing<String>>;
+    fn confirm(&mut self, prompt: &str) -> anyhow::Result<bool>;
+}
+
+pub struct Terminal;
+
+impl Prompt for Terminal {
+    fn secret(&mut self, prompt: &str) -> anyhow::Result<Zeroizing<String>> {
+        Ok(Zeroizing::new(rpassword::prompt_password(prompt)?))
+    }
+
+    fn confirm(&mut self, prompt: &str) -> anyhow::Result<bool> {
+        eprint!("{} [y/N] ", prompt);
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+}
+
+/// Result of a command, printed as text or JSON
+pub struct Output {
+    text: String,
+    json: Value,
+}
+
+impl Output {
+    fn new(text: impl ToString, json: Value) -> Self {
+        Self {
+            text: text.to_string(),
+            json,
+        }
+    }
+
+    pub fn write(&self, json: bool, out: &mut dyn Write) -> anyhow::Result<()> {
+        if json {
+            writeln!(out, "{}", serde_json::to_string_pretty(&self.json)?)?;
+        } else if !self.text.is_empty() {
+            writeln!(out, "{}", self.text)?;
+        }
+        Ok(())
+    }
+}
+
+fn default_keystore() -> anyhow::Result<PathBuf> {
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .ok_or_else(|| anyhow::anyhow!("no home directory, use --keystore"))?;
+    Ok(Path::new(&home).join(".fil-wallet").join("keystore"))
+}
+
+fn parse_address(address: &str) -> anyhow::Result<Address> {
+    Address::from_str(address.trim())
+        .map_err(|e| anyhow::anyhow!("invalid address {}: {}", address, e))
+}
+
+fn read_transaction(file: &Path) -> anyhow::Result<TransactionFile> {
+    let content = if file == Path::new("-") {
+        let mut content = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut content)?;
+        content
+    } else {
+        std::fs::read_to_string(file)?
+    };
+    content.parse()
+}
+
+fn parse_atto(amount: &str) -> anyhow::Result<TokenAmount> {
+    let atto = BigInt::from_str(amount.trim())
+        .map_err(|_| anyhow::anyhow!("invalid attoFIL amount: {}", amount))?;
+    Ok(TokenAmount::from_atto(atto))
+}
+
+impl Cli {
+    fn keystore(&self) -> anyhow::Result<Keystore> {
+        match &self.keystore {
+            Some(path) => Keystore::open(path),
+            None => Keystore::open(default_keystore()?),
+        }
+    }
+
+    fn address(&self, address: impl Into<FlairAddress>) -> String {
+        let mut address = address.into();
+        address.set_network(self.network.network());
+        address.to_string()
+    }
+
+    fn account(&self, address: &str) -> anyhow::Result<FlairAccount> {
+        let address: FlairAddress = parse_address(address)?.into();
+        self.keystore()?
+            .load()?
+            .get(&address)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("{} is not in the keystore", self.address(address)))
+    }
+
+    fn account_output(&self, account: &FlairAccount) -> Output {
+        let address = self.address(account.get_address());
+        Output::new(
+            &address,
+            json!({"address": address, "type": account.get_type().to_string()}),
+        )
+    }
+
+    pub fn run(&self, prompt: &mut dyn Prompt) -> anyhow::Result<Output> {
+        match &self.command {
+            Command::New { wallet_type } => {
+                let account = FlairAccount::generate(*wallet_type)?;
+                self.keystore()?.put(&account)?;
+                Ok(self.account_output(&account))
+            }
+            Command::List => {
+                let wallet = self.keystore()?.load()?;
+                let addresses: Vec<String> = wallet
+                    .list()?
+                    .into_iter()
+                    .map(|a| self.address(a))
+                    .collect();
+                Ok(Output::new(addresses.join("\n"), json!(addresses)))
+            }
+            Command::Import { file } => {
+                let key = match file {
+                    Some(file) => Zeroizing::new(std::fs::read_to_string(file)?),
+                    None => prompt.secret("Private key: ")?,
+                };
+                let account = FlairAccount::import(&key)?;
+                self.keystore()?.put(&account)?;
+                Ok(self.account_output(&account))
+            }
+            Command::Export { address } => {
+                let key = Zeroizing::new(self.account(address)?.export()?);
+                Ok(Output::new(key.as_str(), json!(key.as_str())))
+            }
+            Command::Derive {
+                wallet_type,
+                generate,
+                words,
+                scrypt,
+                save,
+            } => {
+                let phrase = if *generate {
+                    let length = MnemonicType::for_word_count(*words)?;
+                    SecretPhrase::generate_secure(Language::English, length)?
+                } else {
+                    SecretPhrase::generate_from_phrase(&prompt.secret("Secret phrase: ")?)?
+                };
+                let phrase = if *scrypt {
+                    phrase.with_derivation(SeedDerivation::scrypt_default())
+                } else {
+                    phrase
+                };
+
+                let passphrase = prompt.secret("Passphrase (empty for none): ")?;
+                if *generate
+                    && !passphrase.is_empty()
+                    && prompt.secret("Repeat passphrase: ")? != passphrase
+                {
+                    return Err(anyhow::anyhow!("passphrases don't match"));
+                }
+
+                let account = phrase.derive_account_with_progress(
+                    *wallet_type,
+                    Some(&passphrase),
+                    &mut |done, total| {
+                        if done == total || done % (total / 20).max(1) == 0 {
+                            eprint!("\rderiving seed: {}%", done * 100 / total);
+                            if done == total {
+                                eprintln!();
+                            }
+                        }
+                    },
+                )?;
+                if *save {
+                    self.keystore()?.put(&account)?;
+                }
+
+                let mut output = self.account_output(&account);
+                if *generate {
+                    output.text = format!("{}\n{}", phrase.phrase(), output.text);
+                    output.json["phrase"] = json!(phrase.phrase());
+                }
+                Ok(output)
+            }
+            Command::Sign { address, data, cid } => {
+                let account = self.account(address)?;
+                let data = if *cid {
+                    cid::Cid::from_str(data.trim())?.to_bytes()
+                } else {
+                    hex::decode(data.trim())?
+                };
+                let signature = account.sign_bytes(&data, &MsgMeta::unknown())?;
+                let mut bytes = vec![signature.signature_type() as u8];
+                bytes.extend(signature.bytes());
+                Ok(Output::new(
+                    hex::encode(bytes),
+                    json!(SignatureJson::from(&signature)),
+                ))
+            }
+            Command::Verify {
+                address,
+                data,
+                signature,
+            } => {
+                let address = parse_address(address)?;
+                let data = hex::decode(data.trim())?;
+                let bytes = hex::decode(signature.trim())?;
+                let signature = match bytes.split_first() {
+                    Some((t, sig)) if *t == SignatureType::Secp256k1 as u8 => {
+                        Signature::new_secp256k1(sig.to_vec())
+                    }
+                    Some((t, sig)) if *t == SignatureType::BLS as u8 => {
+                        Signature::new_bls(sig.to_vec())
+                    }
+                    _ => return Err(anyhow::anyhow!("unknown signature type")),
+                };
+                verify(&address, &data, &signature)?;
+                Ok(Output::new("valid", json!({"valid": true})))
+            }
+            Command::Msg(MsgCommand::Build {
+                to,
+                value,
+                method,
+                actor,
+                params,
+                opts,
+            }) => {
+                let params = match params {
+                    Some(params) => base64::decode(params.trim())?,
+                    None => vec![],
+                };
+                let mut invoke = Invoke::new(parse_address(to)?, method.clone());
+                if let Some(actor) = actor {
+                    invoke = invoke.actor(*actor);
+                }
+                self.message(
+                    opts,
+                    parse_address(to)?,
+                    parse_fil(value)?,
+                    invoke.method_num()?,
+                    params,
+                    None,
+                )
+            }
+            Command::Msg(MsgCommand::Decode { message, actor }) => {
+                let message = message.trim();
+                let mut message = if message.starts_with('{') {
+                    message_from_json(message)?
+                } else {
+                    let bytes = hex::decode(message)
+                        .or_else(|_| base64::decode(message))
+                        .map_err(|_| anyhow::anyhow!("expected JSON, hex or base64"))?;
+                    message_from_cbor(&bytes)?
+                };
+                // CBOR doesn't carry the network
+                message.from.set_network(self.network.network());
+                message.to.set_network(self.network.network());
+                let preview = preview_message(&message, *actor)?;
+                Ok(Output::new(
+                    preview.to_string(),
+                    serde_json::to_value(&preview)?,
+                ))
+            }
+            Command::Msig(MsigCommand::Create {
+                signers,
+                threshold,
+                unlock_duration,
+                start_epoch,
+                value,
+                code,
+                opts,
+                #[cfg(feature = "rpc")]
+                api,
+            }) => {
+                let signers = signers
+                    .iter()
+                    .map(|s| parse_address(s))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                let threshold = threshold.unwrap_or(signers.len() as u64);
+                if threshold == 0 || threshold > signers.len() as u64 {
+                    return Err(anyhow::anyhow!(
+                        "threshold must be between 1 and {}",
+                        signers.len()
+                    ));
+                }
+                let unlock_duration = parse_duration(unlock_duration)?;
+                let start_epoch = parse_epoch(self.network, start_epoch)?;
+                let value = parse_fil(value)?;
+
+                let code = match code {
+                    Some(code) => cid::Cid::from_str(code.trim())
+                        .map_err(|e| anyhow::anyhow!("invalid code CID {}: {}", code, e))?,
+                    #[cfg(feature = "rpc")]
+                    None => self.node_manifest(api)?.code(ActorType::Multisig)?,
+                    #[cfg(not(feature = "rpc"))]
+                    None => return Err(anyhow::anyhow!("--code is needed without a node")),
+                };
+                let message = create_multisig_message(
+                    parse_address(&opts.from)?,
+                    code,
+                    signers.clone(),
+                    threshold,
+                    unlock_duration,
+                    start_epoch,
+                    value,
+                )?;
+                let context = MultisigContext::Create {
+                    signers: signers.iter().map(|s| s.to_string()).collect(),
+                    threshold,
+                };
+                self.message(
+                    opts,
+                    message.to,
+                    message.value,
+                    message.method_num,
+                    message.params.to_vec(),
+                    Some(context),
+                )
+            }
+            Command::Msig(MsigCommand::Propose {
+                msig,
+                to,
+                value,
+                opts,
+            }) => {
+                parse_address(to)?;
+                let value = parse_fil(value)?;
+                let params =
+                    multisig_send_propose_params(to.trim().to_string(), value.atto().to_string());
+                let context = MultisigContext::Propose {
+                    msig: msig.trim().to_string(),
+                    to: to.trim().to_string(),
+                    value: value.atto().to_string(),
+                };
+                self.message(
+                    opts,
+                    parse_address(msig)?,
+                    TokenAmount::from_atto(0),
+                    multisig::METHOD_PROPOSE,
+                    base64::decode(params)?,
+                    Some(context),
+                )
+            }
+            Command::Msig(MsigCommand::Approve { msig, txn_id, opts }) => {
+                let params = multisig_approve_params(*txn_id);
+                let context = MultisigContext::Approve {
+                    msig: msig.trim().to_string(),
+                    txn_id: *txn_id,
+                };
+                self.message(
+                    opts,
+                    parse_address(msig)?,
+                    TokenAmount::from_atto(0),
+                    multisig::METHOD_APPROVE,
+                    base64::decode(params)?,
+                    Some(context),
+                )
+            }
+            Command::Miner(command) => {
+                let (call, target, opts) = match command {
+                    MinerCommand::Withdraw {
+                        amount,
+                        target,
+                        opts,
+                    } => (miner::withdraw_balance(parse_fil(amount)?)?, target, opts),
+                    MinerCommand::ChangeWorker {
+                        worker,
+                        control,
+                        policy,
+                        target,
+                        opts,
+                    } => {
+                        let control = control
+                            .iter()
+                            .map(|c| parse_address(c))
+                            .collect::<anyhow::Result<_>>()?;
+                        let call = miner::change_worker_address(
+                            &self.actor_policy(policy.as_deref())?,
+                            parse_address(worker)?,
+                            control,
+                        )?;
+                        (call, target, opts)
+                    }
+                    MinerCommand::ConfirmWorker { target, opts } => {
+                        (miner::confirm_update_worker_key(), target, opts)
+                    }
+                    MinerCommand::ChangeOwner {
+                        new_owner,
+                        target,
+                        opts,
+                    } => (
+                        miner::change_owner_address(parse_address(new_owner)?)?,
+                        target,
+                        opts,
+                    ),
+                    MinerCommand::ChangeBeneficiary {
+                        beneficiary,
+                        quota,
+                        expiration,
+                        target,
+                        opts,
+                    } => (
+                        miner::change_beneficiary(
+                            parse_address(beneficiary)?,
+                            parse_fil(quota)?,
+                            parse_epoch(self.network, expiration)?,
+                        )?,
+                        target,
+                        opts,
+                    ),
+                };
+                let miner = parse_address(&target.miner)?;
+                let zero = TokenAmount::from_atto(0);
+                match &target.msig {
+                    Some(msig) => {
+                        let msig = parse_address(msig)?;
+                        let proposal = call.propose(parse_address(&opts.from)?, msig, miner)?;
+                        let params = proposal.params.bytes().to_vec();
+                        self.message(opts, msig, zero, multisig::METHOD_PROPOSE, params, None)
+                    }
+                    None => {
+                        let params = call.params.bytes().to_vec();
+                        self.message(opts, miner, zero, call.method, params, None)
+                    }
+                }
+            }
+            Command::Market(command) => {
+                let (address, amount, opts) = match command {
+                    MarketCommand::AddBalance {
+                        address,
+                        amount,
+                        opts,
+                    }
+                    | MarketCommand::Withdraw {
+                        address,
+                        amount,
+                        opts,
+                    } => (address, amount, opts),
+                };
+                let from = parse_address(&opts.from)?;
+                let address = match address {
+                    Some(address) => parse_address(address)?,
+                    None => from,
+                };
+                let message = match command {
+                    MarketCommand::AddBalance { .. } => {
+                        market::add_balance_message(from, address, parse_fil(amount)?)?
+                    }
+                    MarketCommand::Withdraw { .. } => {
+                        market::withdraw_balance_message(from, address, parse_fil(amount)?)?
+                    }
+                };
+                self.message(
+                    opts,
+                    message.to,
+                    message.value,
+                    message.method_num,
+                    message.params.bytes().to_vec(),
+                    None,
+                )
+            }
+            Command::Filplus(command) => {
+                let (message, opts) = match command {
+                    FilplusCommand::GrantDatacap {
+                        client,
+                        allowance,
+                        policy,
+                        opts,
+                    } => {
+                        let allowance = BigInt::from_str(allowance.trim())
+                            .map_err(|_| anyhow::anyhow!("invalid allowance: {}", allowance))?;
+                        let message = verifreg::add_verified_client_message(
+                            &self.actor_policy(policy.as_deref())?,
+                            parse_address(&opts.from)?,
+                            parse_address(client)?,
+                            allowance,
+                        )?;
+                        (message, opts)
+                    }
+                    FilplusCommand::Transfer { to, amount, opts } => {
+                        let amount = u64::from_str(amount.trim())
+                            .map_err(|_| anyhow::anyhow!("invalid amount: {}", amount))?;
+                        let message = datacap::transfer_message(
+                            parse_address(&opts.from)?,
+                            parse_address(to)?,
+                            datacap::bytes_to_datacap(amount),
+                            Default::default(),
+                        )?;
+                        (message, opts)
+                    }
+                };
+                self.message(
+                    opts,
+                    message.to,
+                    message.value,
+                    message.method_num,
+                    message.params.bytes().to_vec(),
+                    None,
+                )
+            }
+            Command::Evm(EvmCommand::Invoke {
+                contract,
+                sig,
+                args,
+                value,
+                opts,
+            }) => {
+                let function: abi::Function = sig.parse()?;
+                let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                let calldata = function.encode_input_str(&args)?;
+                self.message(
+                    opts,
+                    parse_address(contract)?,
+                    parse_fil(value)?,
+                    evm::METHOD_INVOKE_CONTRACT,
+                    evm::invoke_contract_params(&calldata)?.bytes().to_vec(),
+                    None,
+                )
+            }
+            Command::Evm(EvmCommand::Deploy {
+                initcode,
+                value,
+                opts,
+            }) => {
+                let initcode = hex::decode(initcode.trim().trim_start_matches("0x"))
+                    .map_err(|_| anyhow::anyhow!("initcode is not hex"))?;
+                let message = evm::create_external_message(
+                    parse_address(&opts.from)?,
+                    &initcode,
+                    parse_fil(value)?,
+                )?;
+                self.message(
+                    opts,
+                    message.to,
+                    message.value,
+                    message.method_num,
+                    message.params.bytes().to_vec(),
+                    None,
+                )
+            }
+            Command::Evm(EvmCommand::DecodeReturn {
+                sig,
+                data,
+                reverted,
+            }) => {
+                let function: abi::Function = sig.parse()?;
+                let data = base64::decode(data.trim())
+                    .map_err(|_| anyhow::anyhow!("return data is not base64"))?;
+                let data = evm::decode_return(&data)?;
+                if *reverted {
+                    let reason = evm::revert_reason(&data);
+                    return Ok(Output::new(&reason, json!({ "reverted": reason })));
+                }
+                let values = function.decode_output(&data)?;
+                let text = values
+                    .iter()
+                    .map(|v| v.as_str().map_or(v.to_string(), str::to_string))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Ok(Output::new(text, json!(values)))
+            }
+            Command::Address(AddressCommand::Convert { address }) => {
+                let parsed = parse_address(address)?;
+                let converted = self.address(parsed);
+                Ok(Output::new(
+                    &converted,
+                    json!({
+                        "address": converted,
+                        "protocol": parsed.protocol() as u8,
+                        "payload": hex::encode(parsed.payload_bytes()),
+                        "bytes": hex::encode(parsed.to_bytes()),
+                    }),
+                ))
+            }
+            Command::Offline(OfflineCommand::Show { file }) => match read_transaction(file)? {
+                TransactionFile::Unsigned(unsigned) => {
+                    unsigned.message()?;
+                    Ok(Output::new(
+                        format!("unsigned: {}", unsigned.summary),
+                        serde_json::to_value(&unsigned)?,
+                    ))
+                }
+                TransactionFile::Signed(signed) => {
+                    let cid = signed.signed_message()?.cid()?;
+                    Ok(Output::new(
+                        format!("signed: {}\nCID: {}", signed.summary, cid),
+                        serde_json::to_value(&signed)?,
+                    ))
+                }
+            },
+            Command::Offline(OfflineCommand::Sign {
+                file,
+                yes,
+                qr,
+                fragment_len,
+            }) => {
+                let unsigned = match read_transaction(file)? {
+                    TransactionFile::Unsigned(unsigned) => unsigned,
+                    TransactionFile::Signed(_) => {
+                        return Err(anyhow::anyhow!("the transaction is already signed"))
+                    }
+                };
+                let message = unsigned.message()?;
+                let account = self.account(&message.from.to_string())?;
+                eprintln!("{:?}: {}", unsigned.network, unsigned.summary);
+                if !yes && !prompt.confirm("Sign this transaction?")? {
+                    return Err(anyhow::anyhow!("not signed"));
+                }
+
+                let signed = unsigned.sign(&account)?;
+                let json = serde_json::to_value(&signed)?;
+                if *qr {
+                    Ok(Output::new(signed.to_qr(*fragment_len)?.join("\n"), json))
+                } else {
+                    Ok(Output::new(serde_json::to_string_pretty(&json)?, json))
+                }
+            }
+            Command::Offline(OfflineCommand::Qr { file, fragment_len }) => {
+                let frames = match read_transaction(file)? {
+                    TransactionFile::Unsigned(unsigned) => unsigned.to_qr(*fragment_len)?,
+                    TransactionFile::Signed(signed) => signed.to_qr(*fragment_len)?,
+                };
+                Ok(Output::new(frames.join("\n"), json!(frames)))
+            }
+            #[cfg(feature = "rpc")]
+            Command::Send {
+                from,
+                to,
+                value,
+                max_fee,
+                wait,
+                api,
+            } => {
+                let from: FlairAddress = parse_address(from)?.into();
+                let wallet = self.keystore()?.load()?;
+                let client = fil_wallet::rpc::LotusClient::connect(&api.api, api.api_token.clone());
+                let options = fil_wallet::SendOptions {
+                    max_fee: max_fee.as_deref().map(parse_fil).transpose()?,
+                    wait: *wait,
+                };
+                let receipt = wallet.send(
+                    &client,
+                    &from,
+                    &parse_address(to)?,
+                    parse_fil(value)?,
+                    &options,
+                )?;
+
+                let mut text = receipt.cid.to_string();
+                let mut json = json!({"cid": receipt.cid.to_string()});
+                if let Some(lookup) = &receipt.lookup {
+                    text = format!(
+                        "{}\nexecuted at height {}, exit code {}",
+                        text, lookup.height, lookup.receipt.exit_code
+                    );
+                    json["receipt"] = serde_json::to_value(lookup)?;
+                }
+                Ok(Output::new(text, json))
+            }
+            #[cfg(feature = "rpc")]
+            Command::Replace {
+                cid,
+                cancel,
+                bump,
+                gas_premium,
+                max_fee,
+                api,
+            } => {
+                let wallet = self.keystore()?.load()?;
+                let client = fil_wallet::rpc::LotusClient::connect(&api.api, api.api_token.clone());
+                let pending = client.chain_get_message(&cid::Cid::from_str(cid.trim())?)?;
+                let options = fil_wallet::ReplaceOptions {
+                    bump_percent: *bump,
+                    gas_premium: gas_premium.as_deref().map(parse_atto).transpose()?,
+                    max_fee: max_fee.as_deref().map(parse_fil).transpose()?,
+                };
+                let receipt = if *cancel {
+                    wallet.cancel(&client, &pending, &options)?
+                } else {
+                    wallet.replace(&client, &pending, &options)?
+                };
+                Ok(Output::new(
+                    receipt.cid.to_string(),
+                    json!({"cid": receipt.cid.to_string()}),
+                ))
+            }
+            #[cfg(feature = "server")]
+            Command::Serve {
+                listen,
+                token,
+                admin_token,
+                policy,
+                audit_log,
+            } => {
+                use fil_wallet::{
+                    policy::{PolicyEngine, SigningPolicy},
+                    rpc::server::{Permission, WalletServer},
+                };
+
+                let mut server = WalletServer::with_keystore(self.keystore()?)?;
+                if token.is_none() && admin_token.is_none() {
+                    return Err(anyhow::anyhow!(
+                        "set FIL_WALLET_TOKEN or FIL_WALLET_ADMIN_TOKEN"
+                    ));
+                }
+                if let Some(token) = token {
+                    server.add_token(token, Permission::Sign);
+                }
+                if let Some(token) = admin_token {
+                    server.add_token(token, Permission::Admin);
+                }
+                if let Some(policy) = policy {
+                    let policy: SigningPolicy = serde_json::from_slice(&std::fs::read(policy)?)?;
+                    let mut engine = PolicyEngine::new(&policy)?;
+                    if let Some(audit_log) = audit_log {
+                        let log = std::fs::OpenOptions::new()
+                            .create(true)
+                            .append(true)
+                            .open(audit_log)?;
+                        engine = engine.with_audit_log(Box::new(log));
+                    }
+                    server.set_policy(std::sync::Arc::new(engine));
+                }
+
+                eprintln!("serving the wallet API on http://{}/rpc/v0", listen);
+                server.serve(listen)?;
+                Ok(Output::new("", Value::Null))
+            }
+        }
+    }
+
+    /// Actor policy from `file`, the selected network's without one
+    fn actor_policy(&self, file: Option<&Path>) -> anyhow::Result<Policy> {
+        match file {
+            Some(file) => Policy::from_json(&std::fs::read_to_string(file)?),
+            None => Ok(Policy::for_network(self.network)),
+        }
+    }
+
+    /// Code CIDs of the builtin actors, from a node on the selected network
+    #[cfg(feature = "rpc")]
+    fn node_manifest(&self, api: &ApiOpts) -> anyhow::Result<fil_wallet::manifest::Manifest> {
+        let client = fil_wallet::rpc::LotusClient::connect(&api.api, api.api_token.clone());
+        let name = client.state_network_name()?;
+        // local networks have names of their own
+        let network = ChainType::from_str(&name).unwrap_or(ChainType::Devnet);
+        if network != self.network {
+            return Err(anyhow::anyhow!(
+                "the node runs {}, not {}",
+                name,
+                self.network
+            ));
+        }
+        client.state_actor_code_cids(client.state_network_version()?)
+    }
+
+    fn message(
+        &self,
+        opts: &MessageOpts,
+        to: Address,
+        value: TokenAmount,
+        method: MethodNum,
+        params: Vec<u8>,
+        multisig: Option<MultisigContext>,
+    ) -> anyhow::Result<Output> {
+        let from = parse_address(&opts.from)?;
+        let message: Message = MessageBuilder::new(from, to)
+            .nonce(opts.nonce)
+            .value(value)
+            .method(method)
+            .params(params.into())
+            .gas(
+                opts.gas_limit,
+                parse_atto(&opts.gas_fee_cap)?,
+                parse_atto(&opts.gas_premium)?,
+            )
+            .build();
+
+        let summary = summarize(self.network, &message, multisig.as_ref());
+        let output = if opts.offline {
+            let unsigned = UnsignedTransaction::new(self.network, &message, multisig)?;
+            eprintln!("{}", summary);
+            let json = serde_json::to_value(&unsigned)?;
+            Output::new(serde_json::to_string_pretty(&json)?, json)
+        } else if opts.sign {
+            let account = self.account(&opts.from)?;
+            let signed = fil_wallet::sign_message(&account, message)?;
+            let json = serde_json::to_value(SignedMessageJson::from(&signed))?;
+            Output::new(
+                format!("{}\n{}", summary, serde_json::to_string_pretty(&json)?),
+                json,
+            )
+        } else {
+            let json = serde_json::to_value(MessageJson::from(&message))?;
+            Output::new(
+                format!("{}\n{}", summary, serde_json::to_string_pretty(&json)?),
+                json,
+            )
+        };
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+
+    use super::*;
+
+    /// answers prompts from a script
+    struct Script(VecDeque<&'static str>);
+
+    impl Prompt for Script {
+        fn secret(&mut self, _prompt: &str) -> anyhow::Result<Zeroizing<String>> {
+            let answer = self.0.pop_front().expect("unexpected prompt");
+            Ok(Zeroizing::new(answer.to_string()))
+        }
+
+        fn confirm(&mut self, _prompt: &str) -> anyhow::Result<bool> {
+            Ok(self.0.pop_front().expect("unexpected prompt") == "y")
+        }
+    }
+
+    fn run(keystore: &Path, args: &[&str], answers: &[&'static str]) -> anyhow::Result<Value> {
+        let mut argv = vec![
+            "fil-wallet",
+            "--json",
+            "--keystore",
+            keystore.to_str().unwrap(),
+        ];
+        argv.extend(args);
+        let cli = Cli::from_iter_safe(argv)?;
+        let mut script = Script(answers.iter().copied().collect());
+        let output = cli.run(&mut script)?;
+        assert!(script.0.is_empty(), "unused answers");
+        Ok(output.json)
+    }
+
+    fn keystore(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("fil-wallet-cli-{}-{}", name, std::process::id()))
+    }
+
+    const SECP_KEY: &str = "7b2254797065223a22736563703235366b31222c22507269766174654b6579223a2235734d384f2b6639554161686d78726d61653533776a667056374338664b6b426c414c4c44366e717a666b3d227d";
+    const MULTISIG_CODE: &str = "bafk2bzaceduf3hayh63jnl4z2knxv7cnrdenoubni22fxersc4octlwpxpmy4";
+
+    #[test]
+    fn test_cli_keys() {
+        let dir = keystore("keys");
+        let new = run(&dir, &["new", "--type", "bls"], &[]).unwrap();
+        assert_eq!(new["type"], "bls");
+
+        let imported = run(&dir, &["--network", "calibnet", "import"], &[SECP_KEY]).unwrap();
+        assert_eq!(
+            imported["address"],
+            "t1jkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi"
+        );
+
+        let list = run(&dir, &["list"], &[]).unwrap();
+        assert_eq!(list.as_array().unwrap().len(), 2);
+        assert!(list
+            .as_array()
+            .unwrap()
+            .contains(&json!("f1jkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi")));
+
+        let exported = run(
+            &dir,
+            &["export", "t1jkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi"],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(exported, json!(SECP_KEY));
+        assert!(run(
+            &dir,
+            &["export", "f1x6l4c645ze63at7t7a6jdbqsmicugnafz3wldjq"],
+            &[]
+        )
+        .is_err());
+
+        // sign / verify round trip
+        let address = new["address"].as_str().unwrap();
+        let cli = Cli::from_iter_safe(vec![
+            "fil-wallet",
+            "--keystore",
+            dir.to_str().unwrap(),
+            "sign",
+            address,
+            "cafe",
+        ])
+        .unwrap();
+        let signature = cli.run(&mut Script(VecDeque::new())).unwrap().text;
+        assert!(signature.starts_with("02"));
+        let valid = run(&dir, &["verify", address, "cafe", &signature], &[]).unwrap();
+        assert_eq!(valid["valid"], true);
+        assert!(run(&dir, &["verify", address, "beef", &signature], &[]).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_cli_derive() {
+        let dir = keystore("derive");
+        let phrase =
+            "bring certain hover weekend purity whisper tooth recall blush jump aspect drama";
+        let derived = run(&dir, &["derive", "--save"], &[phrase, ""]).unwrap();
+        assert_eq!(
+            derived["address"],
+            "f1x6l4c645ze63at7t7a6jdbqsmicugnafz3wldjq"
+        );
+        let list = run(&dir, &["list"], &[]).unwrap();
+        assert_eq!(list, json!(["f1x6l4c645ze63at7t7a6jdbqsmicugnafz3wldjq"]));
+
+        let generated = run(
+            &dir,
+            &["derive", "--generate", "--words", "12", "--type", "bls"],
+            &["pass", "pass"],
+        )
+        .unwrap();
+        assert_eq!(generated["phrase"].as_str().unwrap().split(' ').count(), 12);
+        assert!(run(&dir, &["derive", "--generate"], &["pass", "typo"]).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_cli_messages() {
+        let dir = keystore("messages");
+        run(&dir, &["import"], &[SECP_KEY]).unwrap();
+        let from = "f1jkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi";
+
+        let message = run(
+            &dir,
+            &[
+                "msg",
+                "build",
+                "--from",
+                from,
+                "--to",
+                "f01234",
+                "--value",
+                "1.5",
+                "--nonce",
+                "3",
+                "--gas-limit",
+                "1000000",
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(message["Value"], "1500000000000000000");
+        assert_eq!(message["Nonce"], 3);
+        assert!(message["CID"]["/"].is_string());
+
+        let signed = run(
+            &dir,
+            &["msg", "build", "--from", from, "--to", "f01234", "--sign"],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(signed["Signature"]["Type"], 1);
+
+        // methods by name, FRC-42 or builtin
+        let named = run(
+            &dir,
+            &[
+                "msg",
+                "build",
+                "--from",
+                from,
+                "--to",
+                "f01234",
+                "--method",
+                "Increment",
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            named["Method"],
+            fil_wallet::invoke::method_hash("Increment").unwrap()
+        );
+        let builtin = run(
+            &dir,
+            &[
+                "msg", "build", "--from", from, "--to", "f01234", "--method", "Propose", "--actor",
+                "msig",
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(builtin["Method"], multisig::METHOD_PROPOSE);
+        assert!(run(
+            &dir,
+            &["msg", "build", "--from", from, "--to", "f01234", "--method", "propose"],
+            &[],
+        )
+        .is_err());
+
+        let create = run(
+            &dir,
+            &[
+                "msig",
+                "create",
+                "--from",
+                from,
+                "--signer",
+                from,
+                "--signer",
+                &format!(" {} ", "f01234"),
+                "--threshold",
+                "2",
+                "--code",
+                MULTISIG_CODE,
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(create["To"], "f01");
+        assert_eq!(create["Method"], fil_wallet::init::METHOD_EXEC);
+        let vesting = run(
+            &dir,
+            &[
+                "msig",
+                "create",
+                "--from",
+                from,
+                "--signer",
+                from,
+                "--unlock-duration",
+                "180 days 12h",
+                "--start-epoch",
+                "2020-08-25 22:00",
+                "--code",
+                MULTISIG_CODE,
+            ],
+            &[],
+        )
+        .unwrap();
+        let params = base64::decode(vesting["Params"].as_str().unwrap()).unwrap();
+        let params: fil_wallet::init::ExecParams = fvm_ipld_encoding::from_slice(&params).unwrap();
+        assert_eq!(params.code_cid.to_string(), MULTISIG_CODE);
+        // signers, threshold, unlock duration and start epoch
+        let (_, _, unlock_duration, start_epoch): (Vec<Address>, u64, i64, i64) =
+            params.constructor_params.deserialize().unwrap();
+        assert_eq!(unlock_duration, 180 * 2880 + 12 * 120);
+        assert_eq!(start_epoch, 2880);
+        assert!(run(
+            &dir,
+            &[
+                "msig",
+                "create",
+                "--from",
+                from,
+                "--signer",
+                from,
+                "--unlock-duration",
+                "45s"
+            ],
+            &[]
+        )
+        .is_err());
+        assert!(run(
+            &dir,
+            &[
+                "msig",
+                "create",
+                "--from",
+                from,
+                "--signer",
+                from,
+                "--threshold",
+                "2"
+            ],
+            &[]
+        )
+        .is_err());
+
+        let propose = run(
+            &dir,
+            &[
+                "msig", "propose", "--from", from, "--msig", "f02000", "--to", "f01234", "--value",
+                "10",
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            propose["Params"],
+            multisig_send_propose_params("f01234".to_string(), "10000000000000000000".to_string())
+        );
+
+        // decoded back, from JSON and from CBOR
+        let decoded = run(
+            &dir,
+            &["msg", "decode", &propose.to_string(), "--actor", "multisig"],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(decoded["Method"], "Propose");
+        assert_eq!(decoded["Params"]["To"], "f01234");
+        assert_eq!(decoded["Params"]["Value"], "10 FIL");
+        let message =
+            Message::try_from(serde_json::from_value::<MessageJson>(propose).unwrap()).unwrap();
+        let cbor = hex::encode(fvm_ipld_encoding::to_vec(&message).unwrap());
+        let decoded = run(&dir, &["msg", "decode", &cbor], &[]).unwrap();
+        assert_eq!(decoded["Actor"], Value::Null);
+        assert_eq!(decoded["MethodNum"], multisig::METHOD_PROPOSE);
+        assert!(run(&dir, &["msg", "decode", "not a message"], &[]).is_err());
+
+        let approve = run(
+            &dir,
+            &[
+                "msig", "approve", "--from", from, "--msig", "f02000", "--txn-id", "4",
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(approve["Method"], multisig::METHOD_APPROVE);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_cli_miner() {
+        let dir = keystore("miner");
+        run(&dir, &["import"], &[SECP_KEY]).unwrap();
+        let from = "f1jkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi";
+
+        let withdraw = run(
+            &dir,
+            &[
+                "miner", "withdraw", "--from", from, "--miner", "f01000", "--amount", "5",
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(withdraw["To"], "f01000");
+        assert_eq!(withdraw["Method"], 16);
+
+        // owned by a multisig, the call is proposed to it
+        let proposed = run(
+            &dir,
+            &[
+                "miner", "withdraw", "--from", from, "--miner", "f01000", "--msig", "f02000",
+                "--amount", "5",
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(proposed["To"], "f02000");
+        assert_eq!(proposed["Method"], multisig::METHOD_PROPOSE);
+        let decoded = run(
+            &dir,
+            &[
+                "msg",
+                "decode",
+                &proposed.to_string(),
+                "--actor",
+                "multisig",
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(decoded["Params"]["To"], "f01000");
+        assert_eq!(decoded["Params"]["Method"], 16);
+
+        let mut args = vec![
+            "miner",
+            "change-worker",
+            "--from",
+            from,
+            "--miner",
+            "f01000",
+            "--worker",
+            from,
+        ];
+        for _ in 0..11 {
+            args.extend(["--control", "f01001"]);
+        }
+        assert!(run(&dir, &args, &[]).is_err());
+        let confirm = run(
+            &dir,
+            &[
+                "miner",
+                "confirm-worker",
+                "--from",
+                from,
+                "--miner",
+                "f01000",
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(confirm["Method"], 21);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_cli_market() {
+        let dir = keystore("market");
+        run(&dir, &["import"], &[SECP_KEY]).unwrap();
+        let from = "f1jkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi";
+
+        let deposit = run(
+            &dir,
+            &["market", "add-balance", "--from", from, "--amount", "2"],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(deposit["To"], "f05");
+        assert_eq!(deposit["Method"], 2);
+        assert_eq!(deposit["Value"], "2000000000000000000");
+        let decoded = run(&dir, &["msg", "decode", &deposit.to_string()], &[]).unwrap();
+        assert_eq!(decoded["Params"]["Address"], from);
+
+        let withdraw = run(
+            &dir,
+            &[
+                "market",
+                "withdraw",
+                "--from",
+                from,
+                "--address",
+                "f01000",
+                "--amount",
+                "1",
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(withdraw["Method"], 3);
+        assert_eq!(withdraw["Value"], "0");
+        assert!(run(
+            &dir,
+            &["market", "add-balance", "--from", from, "--amount", "0"],
+            &[]
+        )
+        .is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_cli_filplus() {
+        let dir = keystore("filplus");
+        run(&dir, &["import"], &[SECP_KEY]).unwrap();
+        let from = "f1jkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi";
+
+        let grant = run(
+            &dir,
+            &[
+                "filplus",
+                "grant-datacap",
+                "--from",
+                from,
+                "--client",
+                "f01001",
+                "--allowance",
+                "34359738368",
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(grant["To"], "f06");
+        assert_eq!(grant["Method"], 4);
+        let decoded = run(&dir, &["msg", "decode", &grant.to_string()], &[]).unwrap();
+        assert_eq!(decoded["Params"]["Allowance"], "34359738368 bytes");
+        // below the minimum allocation size
+        assert!(run(
+            &dir,
+            &[
+                "filplus",
+                "grant-datacap",
+                "--from",
+                from,
+                "--client",
+                "f01001",
+                "--allowance",
+                "1024",
+            ],
+            &[],
+        )
+        .is_err());
+        // a network whose minimum allocation size is lower
+        let policy = dir.join("policy.json");
+        std::fs::write(
+            &policy,
+            serde_json::to_string(&fil_actor_states::Policy::for_network(ChainType::Devnet))
+                .unwrap(),
+        )
+        .unwrap();
+        let grant = run(
+            &dir,
+            &[
+                "filplus",
+                "grant-datacap",
+                "--from",
+                from,
+                "--client",
+                "f01001",
+                "--allowance",
+                "1024",
+                "--policy",
+                policy.to_str().unwrap(),
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(grant["Method"], 4);
+
+        let transfer = run(
+            &dir,
+            &[
+                "filplus", "transfer", "--from", from, "--to", "f01001", "--amount", "1048576",
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(transfer["To"], "f07");
+        let decoded = run(&dir, &["msg", "decode", &transfer.to_string()], &[]).unwrap();
+        assert_eq!(decoded["Method"], "Transfer");
+        assert_eq!(decoded["Params"]["Amount"], "1048576 DataCap");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_cli_evm() {
+        let dir = keystore("evm");
+        run(&dir, &["import"], &[SECP_KEY]).unwrap();
+        let from = "f1jkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi";
+
+        let invoke = run(
+            &dir,
+            &[
+                "evm",
+                "invoke",
+                "--from",
+                from,
+                "--contract",
+                "f01234",
+                "--sig",
+                "transfer(address,uint256)",
+                "0xff000000000000000000000000000000000004d2",
+                "1000",
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(invoke["Method"], evm::METHOD_INVOKE_CONTRACT);
+        let params = base64::decode(invoke["Params"].as_str().unwrap()).unwrap();
+        let calldata = evm::decode_return(&params).unwrap();
+        assert_eq!(hex::encode(&calldata[..4]), "a9059cbb");
+        assert!(run(
+            &dir,
+            &[
+                "evm",
+                "invoke",
+                "--from",
+                from,
+                "--contract",
+                "f01234",
+                "--sig",
+                "transfer(address,uint256)",
+                "1000",
+            ],
+            &[],
+        )
+        .is_err());
+
+        let deploy = run(
+            &dir,
+            &["evm", "deploy", "--from", from, "--initcode", "0x6080"],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(deploy["To"], "f010");
+        assert_eq!(deploy["Method"], evm::METHOD_EAM_CREATE_EXTERNAL);
+
+        // 0x20 wrapped in CBOR bytes
+        let output = fvm_ipld_encoding::to_vec(&fvm_ipld_encoding::BytesSer(
+            &abi::encode(&[abi::ParamType::Uint(256)], &[json!(32)]).unwrap(),
+        ))
+        .unwrap();
+        let decoded = run(
+            &dir,
+            &[
+                "evm",
+                "decode-return",
+                "--sig",
+                "balanceOf(address)(uint256)",
+                &base64::encode(output),
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(decoded, json!(["32"]));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_cli_offline() {
+        let dir = keystore("offline");
+        run(&dir, &["import"], &[SECP_KEY]).unwrap();
+        let from = "t1jkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi";
+
+        // online machine
+        let unsigned = run(
+            &dir,
+            &[
+                "--network",
+                "calibnet",
+                "msig",
+                "propose",
+                "--offline",
+                "--from",
+                from,
+                "--msig",
+                "t02000",
+                "--to",
+                "t01234",
+                "--value",
+                "10",
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(unsigned["Multisig"]["Action"], "Propose");
+        assert!(unsigned["Summary"]
+            .as_str()
+            .unwrap()
+            .contains("propose sending 10 FIL from multisig t02000 to t01234"));
+        let file = dir.join("unsigned.json");
+        std::fs::write(&file, unsigned.to_string()).unwrap();
+
+        // moved as QR codes
+        let frames = run(
+            &dir,
+            &[
+                "offline",
+                "qr",
+                file.to_str().unwrap(),
+                "--fragment-len",
+                "160",
+            ],
+            &[],
+        )
+        .unwrap();
+        let frames: Vec<String> = serde_json::from_value(frames).unwrap();
+        assert!(frames.len() > 1);
+        let scanned = dir.join("scanned.txt");
+        std::fs::write(&scanned, frames.join("\n")).unwrap();
+
+        // offline machine
+        let path = scanned.to_str().unwrap();
+        assert!(run(&dir, &["offline", "sign", path], &["n"]).is_err());
+        let signed = run(&dir, &["offline", "sign", path], &["y"]).unwrap();
+        assert_eq!(signed["SignedMessage"]["Signature"]["Type"], 1);
+        let file = dir.join("signed.json");
+        std::fs::write(&file, signed.to_string()).unwrap();
+
+        let shown = run(&dir, &["offline", "show", file.to_str().unwrap()], &[]).unwrap();
+        assert_eq!(shown, signed);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_cli_address_convert() {
+        let dir = keystore("address");
+        let converted = run(
+            &dir,
+            &[
+                "--network",
+                "calibnet",
+                "address",
+                "convert",
+                "f1jkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi",
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            converted["address"],
+            "t1jkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi"
+        );
+        assert_eq!(converted["protocol"], 1);
+        assert!(run(&dir, &["address", "convert", "x1abc"], &[]).is_err());
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
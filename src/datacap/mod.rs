@@ -0,0 +1,125 @@
+use frc42_dispatch::method_hash;
+pub use frc46_token::token::types::{
+    BurnParams, DecreaseAllowanceParams, GetAllowanceParams, IncreaseAllowanceParams,
+    RevokeAllowanceParams, TransferFromParams, TransferParams,
+};
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::{address::Address, econ::TokenAmount, message::Message, MethodNum};
+
+use crate::{actors::DATACAP_TOKEN_ACTOR_ADDR, message::MessageBuilder};
+
+// datacap actor methods and params, as of actors v9
+
+/// DataCap is a token with 18 decimals, one whole token per byte
+pub const TOKEN_PRECISION: u64 = 1_000_000_000_000_000_000;
+
+pub const METHOD_TRANSFER: MethodNum = 14;
+pub const METHOD_TRANSFER_FROM: MethodNum = 15;
+pub const METHOD_INCREASE_ALLOWANCE: MethodNum = 16;
+pub const METHOD_DECREASE_ALLOWANCE: MethodNum = 17;
+pub const METHOD_REVOKE_ALLOWANCE: MethodNum = 18;
+pub const METHOD_BURN: MethodNum = 19;
+pub const METHOD_BURN_FROM: MethodNum = 20;
+pub const METHOD_ALLOWANCE: MethodNum = 21;
+
+// FRC-46 methods under their FRC-42 numbers, the ones other actors and
+// later versions of the actor dispatch on
+pub const METHOD_NAME_EXPORTED: MethodNum = method_hash!("Name");
+pub const METHOD_SYMBOL_EXPORTED: MethodNum = method_hash!("Symbol");
+pub const METHOD_TOTAL_SUPPLY_EXPORTED: MethodNum = method_hash!("TotalSupply");
+pub const METHOD_BALANCE_EXPORTED: MethodNum = method_hash!("Balance");
+pub const METHOD_TRANSFER_EXPORTED: MethodNum = method_hash!("Transfer");
+pub const METHOD_TRANSFER_FROM_EXPORTED: MethodNum = method_hash!("TransferFrom");
+pub const METHOD_INCREASE_ALLOWANCE_EXPORTED: MethodNum = method_hash!("IncreaseAllowance");
+pub const METHOD_DECREASE_ALLOWANCE_EXPORTED: MethodNum = method_hash!("DecreaseAllowance");
+pub const METHOD_REVOKE_ALLOWANCE_EXPORTED: MethodNum = method_hash!("RevokeAllowance");
+pub const METHOD_BURN_EXPORTED: MethodNum = method_hash!("Burn");
+pub const METHOD_BURN_FROM_EXPORTED: MethodNum = method_hash!("BurnFrom");
+pub const METHOD_ALLOWANCE_EXPORTED: MethodNum = method_hash!("Allowance");
+pub const METHOD_GRANULARITY_EXPORTED: MethodNum = method_hash!("Granularity");
+
+/// DataCap in whole bytes, the unit allocations are sized in
+pub fn bytes_to_datacap(bytes: u64) -> TokenAmount {
+    TokenAmount::from_atto(fvm_shared::bigint::BigInt::from(bytes) * TOKEN_PRECISION)
+}
+
+/// Transfer `amount` DataCap, the receiver hook of `to` gets `operator_data`
+pub fn transfer_message(
+    from: Address,
+    to: Address,
+    amount: TokenAmount,
+    operator_data: RawBytes,
+) -> anyhow::Result<Message> {
+    if amount.is_negative() {
+        return Err(anyhow::anyhow!("negative transfer requested"));
+    }
+    let params = TransferParams {
+        to,
+        amount,
+        operator_data,
+    };
+    Ok(MessageBuilder::new(from, DATACAP_TOKEN_ACTOR_ADDR)
+        .method(METHOD_TRANSFER_EXPORTED)
+        .params(RawBytes::serialize(params)?)
+        .build())
+}
+
+/// DataCap balance of `address`, to run with StateCall
+pub fn balance_message(from: Address, address: Address) -> anyhow::Result<Message> {
+    Ok(MessageBuilder::new(from, DATACAP_TOKEN_ACTOR_ADDR)
+        .method(METHOD_BALANCE_EXPORTED)
+        .params(RawBytes::serialize(address)?)
+        .build())
+}
+
+/// DataCap `operator` may transfer on behalf of `owner`, to run with StateCall
+pub fn allowance_message(
+    from: Address,
+    owner: Address,
+    operator: Address,
+) -> anyhow::Result<Message> {
+    Ok(MessageBuilder::new(from, DATACAP_TOKEN_ACTOR_ADDR)
+        .method(METHOD_ALLOWANCE_EXPORTED)
+        .params(RawBytes::serialize(GetAllowanceParams { owner, operator })?)
+        .build())
+}
+
+#[test]
+fn test_datacap_messages() {
+    let from = Address::new_id(1000);
+    let transfer = transfer_message(
+        from,
+        Address::new_id(1001),
+        bytes_to_datacap(1 << 20),
+        RawBytes::default(),
+    )
+    .unwrap();
+    assert_eq!(transfer.to, DATACAP_TOKEN_ACTOR_ADDR);
+    assert_eq!(transfer.method_num, METHOD_TRANSFER_EXPORTED);
+    let params: TransferParams = transfer.params.deserialize().unwrap();
+    assert_eq!(
+        params.amount.atto(),
+        &(fvm_shared::bigint::BigInt::from(1u64 << 20) * TOKEN_PRECISION)
+    );
+    assert!(transfer_message(from, from, TokenAmount::from_atto(-1), RawBytes::default()).is_err());
+
+    let balance = balance_message(from, Address::new_id(1001)).unwrap();
+    assert_eq!(balance.method_num, METHOD_BALANCE_EXPORTED);
+    assert_eq!(
+        balance.params.deserialize::<Address>().unwrap(),
+        Address::new_id(1001)
+    );
+    let allowance = allowance_message(from, from, Address::new_id(6)).unwrap();
+    assert_eq!(allowance.method_num, METHOD_ALLOWANCE_EXPORTED);
+    let params: GetAllowanceParams = allowance.params.deserialize().unwrap();
+    assert_eq!(params.operator, Address::new_id(6));
+}
+
+#[test]
+fn test_frc42_method_numbers() {
+    // numbers of the FRC-46 reference and the v10 datacap actor
+    assert_eq!(METHOD_NAME_EXPORTED, 48890204);
+    assert_eq!(METHOD_TRANSFER_EXPORTED, 80475954);
+    assert_eq!(METHOD_BALANCE_EXPORTED, 3261979605);
+    assert_eq!(METHOD_GRANULARITY_EXPORTED, 3936767397);
+}
@@ -0,0 +1,150 @@
+use std::fmt;
+
+use cid::Cid;
+use serde::{
+    de::{MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use serde_json::{json, Map, Value};
+
+/// Any DAG-CBOR value as JSON, the way Lotus shows undecoded params: byte
+/// strings in base64 and CIDs as {"/": cid}
+pub(crate) struct CborValue(pub Value);
+
+impl<'de> Deserialize<'de> for CborValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(CborVisitor).map(CborValue)
+    }
+}
+
+struct CborVisitor;
+
+impl<'de> Visitor<'de> for CborVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a DAG-CBOR value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(json!(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(json!(v))
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_u128<E>(self, v: u128) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(json!(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::String(base64::encode(v)))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    // only CIDs are newtypes in DAG-CBOR
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Value, D::Error> {
+        let cid = deserializer.deserialize_bytes(CidVisitor)?;
+        Ok(json!({ "/": cid.to_string() }))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = vec![];
+        while let Some(CborValue(value)) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut values = Map::new();
+        while let Some((CborValue(key), CborValue(value))) = map.next_entry()? {
+            let key = match key {
+                Value::String(key) => key,
+                key => key.to_string(),
+            };
+            values.insert(key, value);
+        }
+        Ok(Value::Object(values))
+    }
+}
+
+struct CidVisitor;
+
+impl<'de> Visitor<'de> for CidVisitor {
+    type Value = Cid;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a CID")
+    }
+
+    fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Cid, E> {
+        Cid::try_from(v).map_err(E::custom)
+    }
+}
+
+/// Params as JSON, or as base64 when they aren't DAG-CBOR
+pub(crate) fn generic_params(params: &[u8]) -> Value {
+    if params.is_empty() {
+        return Value::Null;
+    }
+    match fvm_ipld_encoding::from_slice::<CborValue>(params) {
+        Ok(CborValue(value)) => value,
+        Err(_) => Value::String(base64::encode(params)),
+    }
+}
+
+#[test]
+fn test_generic_params() {
+    use crate::multisig::construct::ExecParams;
+
+    let cid: Cid = "bafk2bzaceduf3hayh63jnl4z2knxv7cnrdenoubni22fxersc4octlwpxpmy4"
+        .parse()
+        .unwrap();
+    let params = fvm_ipld_encoding::to_vec(&ExecParams {
+        code_cid: cid,
+        constructor_params: vec![1, 2, 3].into(),
+    })
+    .unwrap();
+    assert_eq!(
+        generic_params(&params),
+        json!([
+            { "/": "bafk2bzaceduf3hayh63jnl4z2knxv7cnrdenoubni22fxersc4octlwpxpmy4" },
+            "AQID"
+        ])
+    );
+    assert_eq!(generic_params(&[]), Value::Null);
+    // truncated CBOR
+    assert_eq!(generic_params(&[0x82, 0x01]), json!("ggE="));
+}
@@ -0,0 +1,257 @@
+use fvm_shared::MethodNum;
+
+use super::ActorType;
+use crate::datacap;
+
+/// FRC-42 number of UniversalReceiverHook, called on token transfers
+pub const METHOD_UNIVERSAL_RECEIVER_HOOK: MethodNum = 3726118371;
+
+// exported methods of the builtin actors, as of actors v9
+
+const SYSTEM: &[&str] = &["Constructor"];
+const INIT: &[&str] = &["Constructor", "Exec"];
+const CRON: &[&str] = &["Constructor", "EpochTick"];
+const ACCOUNT: &[&str] = &["Constructor", "PubkeyAddress", "AuthenticateMessage"];
+const POWER: &[&str] = &[
+    "Constructor",
+    "CreateMiner",
+    "UpdateClaimedPower",
+    "EnrollCronEvent",
+    "CronTick",
+    "UpdatePledgeTotal",
+    "OnConsensusFault",
+    "SubmitPoRepForBulkVerify",
+    "CurrentTotalPower",
+];
+const MINER: &[&str] = &[
+    "Constructor",
+    "ControlAddresses",
+    "ChangeWorkerAddress",
+    "ChangePeerID",
+    "SubmitWindowedPoSt",
+    "PreCommitSector",
+    "ProveCommitSector",
+    "ExtendSectorExpiration",
+    "TerminateSectors",
+    "DeclareFaults",
+    "DeclareFaultsRecovered",
+    "OnDeferredCronEvent",
+    "CheckSectorProven",
+    "ApplyRewards",
+    "ReportConsensusFault",
+    "WithdrawBalance",
+    "ConfirmSectorProofsValid",
+    "ChangeMultiaddrs",
+    "CompactPartitions",
+    "CompactSectorNumbers",
+    "ConfirmUpdateWorkerKey",
+    "RepayDebt",
+    "ChangeOwnerAddress",
+    "DisputeWindowedPoSt",
+    "PreCommitSectorBatch",
+    "ProveCommitAggregate",
+    "ProveReplicaUpdates",
+    "PreCommitSectorBatch2",
+    "ProveReplicaUpdates2",
+    "ChangeBeneficiary",
+    "GetBeneficiary",
+];
+const MARKET: &[&str] = &[
+    "Constructor",
+    "AddBalance",
+    "WithdrawBalance",
+    "PublishStorageDeals",
+    "VerifyDealsForActivation",
+    "ActivateDeals",
+    "OnMinerSectorsTerminate",
+    "ComputeDataCommitment",
+    "CronTick",
+];
+const PAYMENT_CHANNEL: &[&str] = &["Constructor", "UpdateChannelState", "Settle", "Collect"];
+const MULTISIG: &[&str] = &[
+    "Constructor",
+    "Propose",
+    "Approve",
+    "Cancel",
+    "AddSigner",
+    "RemoveSigner",
+    "SwapSigner",
+    "ChangeNumApprovalsThreshold",
+    "LockBalance",
+];
+const REWARD: &[&str] = &[
+    "Constructor",
+    "AwardBlockReward",
+    "ThisEpochReward",
+    "UpdateNetworkKPI",
+];
+const VERIFIED_REGISTRY: &[&str] = &[
+    "Constructor",
+    "AddVerifier",
+    "RemoveVerifier",
+    "AddVerifiedClient",
+    "UseBytes",
+    "RestoreBytes",
+    "RemoveVerifiedClientDataCap",
+    "RemoveExpiredAllocations",
+    "ClaimAllocations",
+    "GetClaims",
+    "ExtendClaimTerms",
+    "RemoveExpiredClaims",
+];
+// 4 to 9 are unused
+const DATACAP: &[&str] = &[
+    "Constructor",
+    "Mint",
+    "Destroy",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "Name",
+    "Symbol",
+    "TotalSupply",
+    "BalanceOf",
+    "Transfer",
+    "TransferFrom",
+    "IncreaseAllowance",
+    "DecreaseAllowance",
+    "RevokeAllowance",
+    "Burn",
+    "BurnFrom",
+    "Allowance",
+];
+
+// FRC-46 methods of the datacap actor under their FRC-42 numbers
+const DATACAP_EXPORTED: &[(MethodNum, &str)] = &[
+    (datacap::METHOD_NAME_EXPORTED, "Name"),
+    (datacap::METHOD_SYMBOL_EXPORTED, "Symbol"),
+    (datacap::METHOD_TOTAL_SUPPLY_EXPORTED, "TotalSupply"),
+    (datacap::METHOD_BALANCE_EXPORTED, "Balance"),
+    (datacap::METHOD_TRANSFER_EXPORTED, "Transfer"),
+    (datacap::METHOD_TRANSFER_FROM_EXPORTED, "TransferFrom"),
+    (
+        datacap::METHOD_INCREASE_ALLOWANCE_EXPORTED,
+        "IncreaseAllowance",
+    ),
+    (
+        datacap::METHOD_DECREASE_ALLOWANCE_EXPORTED,
+        "DecreaseAllowance",
+    ),
+    (datacap::METHOD_REVOKE_ALLOWANCE_EXPORTED, "RevokeAllowance"),
+    (datacap::METHOD_BURN_EXPORTED, "Burn"),
+    (datacap::METHOD_BURN_FROM_EXPORTED, "BurnFrom"),
+    (datacap::METHOD_ALLOWANCE_EXPORTED, "Allowance"),
+    (datacap::METHOD_GRANULARITY_EXPORTED, "Granularity"),
+];
+
+fn methods(actor: ActorType) -> &'static [&'static str] {
+    match actor {
+        ActorType::System => SYSTEM,
+        ActorType::Init => INIT,
+        ActorType::Cron => CRON,
+        ActorType::Account => ACCOUNT,
+        ActorType::Power => POWER,
+        ActorType::Miner => MINER,
+        ActorType::Market => MARKET,
+        ActorType::PaymentChannel => PAYMENT_CHANNEL,
+        ActorType::Multisig => MULTISIG,
+        ActorType::Reward => REWARD,
+        ActorType::VerifiedRegistry => VERIFIED_REGISTRY,
+        ActorType::DataCap => DATACAP,
+    }
+}
+
+/// Name of a method of `actor`, None when it doesn't export it
+pub fn method_name(actor: ActorType, method: MethodNum) -> Option<&'static str> {
+    match method {
+        0 => Some("Send"),
+        METHOD_UNIVERSAL_RECEIVER_HOOK
+            if matches!(
+                actor,
+                ActorType::Account | ActorType::Multisig | ActorType::VerifiedRegistry
+            ) =>
+        {
+            Some("UniversalReceiverHook")
+        }
+        _ if actor == ActorType::DataCap && method > DATACAP.len() as MethodNum => DATACAP_EXPORTED
+            .iter()
+            .find(|(number, _)| *number == method)
+            .map(|(_, name)| *name),
+        _ => methods(actor)
+            .get((method as usize).checked_sub(1)?)
+            .copied()
+            .filter(|name| !name.is_empty()),
+    }
+}
+
+/// Number of the method `name` of `actor`, the reverse of `method_name`
+pub fn method_number(actor: ActorType, name: &str) -> Option<MethodNum> {
+    if name == "Send" {
+        return Some(0);
+    }
+    if let Some(index) = methods(actor)
+        .iter()
+        .position(|m| !m.is_empty() && *m == name)
+    {
+        return Some(index as MethodNum + 1);
+    }
+    if actor == ActorType::DataCap {
+        return DATACAP_EXPORTED
+            .iter()
+            .find(|(_, m)| *m == name)
+            .map(|(number, _)| *number);
+    }
+    (method_name(actor, METHOD_UNIVERSAL_RECEIVER_HOOK) == Some(name))
+        .then_some(METHOD_UNIVERSAL_RECEIVER_HOOK)
+}
+
+#[test]
+fn test_method_name() {
+    assert_eq!(method_name(ActorType::Multisig, 0), Some("Send"));
+    assert_eq!(method_name(ActorType::Multisig, 2), Some("Propose"));
+    assert_eq!(method_name(ActorType::Multisig, 10), None);
+    assert_eq!(method_name(ActorType::Miner, 16), Some("WithdrawBalance"));
+    assert_eq!(method_name(ActorType::Miner, 30), Some("ChangeBeneficiary"));
+    assert_eq!(method_name(ActorType::Market, 2), Some("AddBalance"));
+    assert_eq!(method_name(ActorType::DataCap, 14), Some("Transfer"));
+    assert_eq!(method_name(ActorType::DataCap, 5), None);
+    assert_eq!(
+        method_name(ActorType::DataCap, datacap::METHOD_TRANSFER_EXPORTED),
+        Some("Transfer")
+    );
+    assert_eq!(
+        method_name(ActorType::DataCap, datacap::METHOD_BALANCE_EXPORTED),
+        Some("Balance")
+    );
+    assert_eq!(
+        method_name(ActorType::Account, METHOD_UNIVERSAL_RECEIVER_HOOK),
+        Some("UniversalReceiverHook")
+    );
+    assert_eq!(
+        method_name(ActorType::Miner, METHOD_UNIVERSAL_RECEIVER_HOOK),
+        None
+    );
+
+    assert_eq!(method_number(ActorType::Multisig, "Propose"), Some(2));
+    assert_eq!(
+        method_number(ActorType::Miner, "ChangeBeneficiary"),
+        Some(30)
+    );
+    assert_eq!(method_number(ActorType::DataCap, "Transfer"), Some(14));
+    assert_eq!(
+        method_number(ActorType::DataCap, "Granularity"),
+        Some(datacap::METHOD_GRANULARITY_EXPORTED)
+    );
+    assert_eq!(
+        method_number(ActorType::Account, "UniversalReceiverHook"),
+        Some(METHOD_UNIVERSAL_RECEIVER_HOOK)
+    );
+    assert_eq!(
+        method_number(ActorType::Miner, "UniversalReceiverHook"),
+        None
+    );
+    assert_eq!(method_number(ActorType::Multisig, ""), None);
+}
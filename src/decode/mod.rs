@@ -0,0 +1,456 @@
+mod cbor;
+mod methods;
+mod params;
+
+use std::fmt;
+
+use fil_actor_states::singletons::{
+    CRON_ACTOR_ID, DATACAP_TOKEN_ACTOR_ID, INIT_ACTOR_ID, REWARD_ACTOR_ID, STORAGE_MARKET_ACTOR_ID,
+    STORAGE_POWER_ACTOR_ID, SYSTEM_ACTOR_ID, VERIFIED_REGISTRY_ACTOR_ID,
+};
+pub use fil_actor_states::BuiltinActor as ActorType;
+use fvm_ipld_encoding::Cbor;
+use fvm_shared::{address::Address, econ::TokenAmount, message::Message, MethodNum};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub use methods::{method_name, method_number, METHOD_UNIVERSAL_RECEIVER_HOOK};
+pub use params::decode_params;
+
+use crate::{
+    helpers::units::format_fil,
+    message::{MessageJson, SignedMessage, SignedMessageJson},
+};
+
+// Readable previews of messages, shown on confirmation screens before
+// signing. The target actor type isn't part of the message, the caller
+// knows it or looks it up on chain.
+
+/// Every builtin actor type
+pub const ACTOR_TYPES: [ActorType; 12] = [
+    ActorType::System,
+    ActorType::Init,
+    ActorType::Cron,
+    ActorType::Account,
+    ActorType::Power,
+    ActorType::Miner,
+    ActorType::Market,
+    ActorType::PaymentChannel,
+    ActorType::Multisig,
+    ActorType::Reward,
+    ActorType::VerifiedRegistry,
+    ActorType::DataCap,
+];
+
+/// Type of the singleton actor at a reserved ID address
+pub fn singleton_type(address: &Address) -> Option<ActorType> {
+    match address.id().ok()? {
+        SYSTEM_ACTOR_ID => Some(ActorType::System),
+        INIT_ACTOR_ID => Some(ActorType::Init),
+        REWARD_ACTOR_ID => Some(ActorType::Reward),
+        CRON_ACTOR_ID => Some(ActorType::Cron),
+        STORAGE_POWER_ACTOR_ID => Some(ActorType::Power),
+        STORAGE_MARKET_ACTOR_ID => Some(ActorType::Market),
+        VERIFIED_REGISTRY_ACTOR_ID => Some(ActorType::VerifiedRegistry),
+        DATACAP_TOKEN_ACTOR_ID => Some(ActorType::DataCap),
+        _ => None,
+    }
+}
+
+/// Actor type by its manifest name or a short one, e.g. "msig"
+pub fn parse_actor_type(s: &str) -> anyhow::Result<ActorType> {
+    let name = s.to_ascii_lowercase();
+    ACTOR_TYPES
+        .into_iter()
+        .find(|t| t.name() == name)
+        .or(match name.as_str() {
+            "power" => Some(ActorType::Power),
+            "miner" => Some(ActorType::Miner),
+            "market" => Some(ActorType::Market),
+            "paych" => Some(ActorType::PaymentChannel),
+            "msig" => Some(ActorType::Multisig),
+            "verifreg" => Some(ActorType::VerifiedRegistry),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow::anyhow!("unknown actor type: {}", s))
+}
+
+/// What a message does, field by field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MessagePreview {
+    #[serde(rename = "CID")]
+    pub cid: String,
+    pub from: String,
+    pub to: String,
+    /// type of the receiver, when known
+    pub actor: Option<String>,
+    pub value: String,
+    pub nonce: u64,
+    pub method_num: MethodNum,
+    /// None for methods the receiver doesn't export, or an unknown receiver
+    pub method: Option<String>,
+    pub params: Value,
+    pub max_fee: String,
+}
+
+impl fmt::Display for MessagePreview {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let to = match &self.actor {
+            Some(actor) => format!("{} ({})", self.to, actor),
+            None => self.to.clone(),
+        };
+        let method = match &self.method {
+            Some(method) => format!("{} ({})", method, self.method_num),
+            None => self.method_num.to_string(),
+        };
+        writeln!(f, "From:    {}", self.from)?;
+        writeln!(f, "To:      {}", to)?;
+        writeln!(f, "Method:  {}", method)?;
+        writeln!(f, "Value:   {}", self.value)?;
+        writeln!(f, "Nonce:   {}", self.nonce)?;
+        writeln!(f, "Max fee: {}", self.max_fee)?;
+        if !self.params.is_null() {
+            let params = serde_json::to_string_pretty(&self.params).map_err(|_| fmt::Error)?;
+            writeln!(f, "Params:")?;
+            for line in params.lines() {
+                writeln!(f, "  {}", line)?;
+            }
+        }
+        write!(f, "CID:     {}", self.cid)
+    }
+}
+
+/// Preview of a message sent to an actor of type `actor`, singletons are
+/// recognized by their address when `actor` is None. Addresses in the params
+/// are shown on the network of the receiver.
+pub fn preview_message(
+    message: &Message,
+    actor: Option<ActorType>,
+) -> anyhow::Result<MessagePreview> {
+    let actor = actor.or_else(|| singleton_type(&message.to));
+    let params = match actor {
+        Some(actor) => decode_params(
+            message.to.network(),
+            actor,
+            message.method_num,
+            message.params.bytes(),
+        )
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "params don't match method {} of {}: {}",
+                message.method_num,
+                actor.name(),
+                e
+            )
+        })?,
+        None => cbor::generic_params(message.params.bytes()),
+    };
+
+    Ok(MessagePreview {
+        cid: message.cid()?.to_string(),
+        from: message.from.to_string(),
+        to: message.to.to_string(),
+        actor: actor.map(|a| a.name().to_string()),
+        value: format_fil(&message.value),
+        nonce: message.sequence,
+        method_num: message.method_num,
+        method: actor
+            .and_then(|a| method_name(a, message.method_num))
+            .map(str::to_string),
+        params,
+        max_fee: format_fil(&TokenAmount::from_atto(
+            message.gas_fee_cap.atto() * message.gas_limit,
+        )),
+    })
+}
+
+/// Message out of its CBOR encoding, signed or not
+pub fn message_from_cbor(bytes: &[u8]) -> anyhow::Result<Message> {
+    match Message::unmarshal_cbor(bytes) {
+        Ok(message) => Ok(message),
+        Err(_) => Ok(SignedMessage::unmarshal_cbor(bytes)
+            .map_err(|_| anyhow::anyhow!("not a CBOR encoded message"))?
+            .message),
+    }
+}
+
+/// Message out of its Lotus JSON form, signed or not
+pub fn message_from_json(json: &str) -> anyhow::Result<Message> {
+    match serde_json::from_str::<MessageJson>(json) {
+        Ok(message) => Message::try_from(message),
+        Err(_) => {
+            let signed: SignedMessageJson = serde_json::from_str(json)
+                .map_err(|_| anyhow::anyhow!("not a Lotus JSON message"))?;
+            Ok(SignedMessage::try_from(signed)?.message)
+        }
+    }
+}
+
+/// Preview of a CBOR encoded message, signed or not
+pub fn preview_cbor(bytes: &[u8], actor: Option<ActorType>) -> anyhow::Result<MessagePreview> {
+    preview_message(&message_from_cbor(bytes)?, actor)
+}
+
+/// Preview of a message in Lotus JSON, signed or not
+pub fn preview_json(json: &str, actor: Option<ActorType>) -> anyhow::Result<MessagePreview> {
+    preview_message(&message_from_json(json)?, actor)
+}
+
+#[cfg(test)]
+mod test {
+    use fvm_ipld_encoding::RawBytes;
+    use fvm_shared::{bigint::BigInt, crypto::signature::Signature};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        datacap::TransferParams,
+        market,
+        message::{sign_message, MessageBuilder},
+        miner::{ChangeWorkerAddressParams, WithdrawBalanceParams},
+        models::account::FlairAccount,
+        multisig::{
+            approve::{TxnID, TxnIDParams},
+            propose::ProposeParams,
+        },
+        types::WalletType,
+    };
+
+    fn fil(amount: u64) -> TokenAmount {
+        TokenAmount::from_atto(BigInt::from(amount) * BigInt::from(10u64.pow(18)))
+    }
+
+    fn testnet(address: &str) -> Address {
+        address.parse().unwrap()
+    }
+
+    fn message(to: Address, method: MethodNum, params: RawBytes) -> Message {
+        MessageBuilder::new(testnet("t1jkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi"), to)
+            .nonce(4)
+            .method(method)
+            .params(params)
+            .gas(
+                1_000_000,
+                TokenAmount::from_atto(100_000),
+                TokenAmount::from_atto(1_000),
+            )
+            .build()
+    }
+
+    #[test]
+    fn test_preview_multisig() {
+        let propose = ProposeParams {
+            to: Address::new_id(1234),
+            value: fil(10),
+            method: 0,
+            params: RawBytes::default(),
+        };
+        let message = message(testnet("t02000"), 2, RawBytes::serialize(&propose).unwrap());
+        // a multisig isn't a singleton, without its type the params stay raw
+        let preview = preview_message(&message, None).unwrap();
+        assert_eq!(preview.method, None);
+        assert_eq!(preview.params[0], json!("ANIJ"));
+
+        let preview = preview_message(&message, Some(ActorType::Multisig)).unwrap();
+        assert_eq!(preview.actor.as_deref(), Some("multisig"));
+        assert_eq!(preview.method.as_deref(), Some("Propose"));
+        assert_eq!(preview.to, "t02000");
+        assert_eq!(preview.max_fee, "0.0000001 FIL");
+        assert_eq!(
+            preview.params,
+            json!({"To": "t01234", "Value": "10 FIL", "Method": 0, "Params": null})
+        );
+        let text = preview.to_string();
+        assert!(text.contains("To:      t02000 (multisig)"));
+        assert!(text.contains("Method:  Propose (2)"));
+        assert!(text.contains("\"Value\": \"10 FIL\""));
+
+        let approve = TxnIDParams {
+            id: TxnID(7),
+            proposal_hash: vec![],
+        };
+        let message = self::message(testnet("t02000"), 3, RawBytes::serialize(&approve).unwrap());
+        let preview = preview_message(&message, Some(ActorType::Multisig)).unwrap();
+        assert_eq!(preview.method.as_deref(), Some("Approve"));
+        assert_eq!(preview.params, json!({"ID": 7, "ProposalHash": ""}));
+
+        // params of another method are refused
+        let message = self::message(testnet("t02000"), 2, RawBytes::serialize(&approve).unwrap());
+        assert!(preview_message(&message, Some(ActorType::Multisig)).is_err());
+    }
+
+    #[test]
+    fn test_preview_actors() {
+        let miner = testnet("t01000");
+        let worker = testnet("t3vtr3myof7qghg3eq75qfagbfemrhycsqypmz2t6pfwwq5m3w73wlthhbtlrnjpyjxnhwz5pxboonb3bliv6q");
+        let withdraw = WithdrawBalanceParams {
+            amount_requested: fil(3),
+        };
+        let preview = preview_message(
+            &message(miner, 16, RawBytes::serialize(&withdraw).unwrap()),
+            Some(ActorType::Miner),
+        )
+        .unwrap();
+        assert_eq!(preview.method.as_deref(), Some("WithdrawBalance"));
+        assert_eq!(preview.params, json!({"AmountRequested": "3 FIL"}));
+
+        let change = ChangeWorkerAddressParams {
+            new_worker: worker,
+            new_control_addresses: vec![Address::new_id(1001)],
+        };
+        let preview = preview_message(
+            &message(miner, 3, RawBytes::serialize(&change).unwrap()),
+            Some(ActorType::Miner),
+        )
+        .unwrap();
+        assert_eq!(
+            preview.params,
+            json!({"NewWorker": worker.to_string(), "NewControlAddrs": ["t01001"]})
+        );
+
+        // the market actor is a singleton
+        let preview = preview_message(
+            &message(
+                Address::new_id(5),
+                market::METHOD_ADD_BALANCE,
+                RawBytes::serialize(miner).unwrap(),
+            ),
+            None,
+        )
+        .unwrap();
+        assert_eq!(preview.actor.as_deref(), Some("storagemarket"));
+        assert_eq!(preview.method.as_deref(), Some("AddBalance"));
+        assert_eq!(preview.params, json!({"Address": "f01000"}));
+
+        let deal = market::DealProposal {
+            piece_cid: fvm_shared::commcid::data_commitment_v1_to_cid(&[1; 32]).unwrap(),
+            piece_size: fvm_shared::piece::PaddedPieceSize(2048),
+            verified_deal: true,
+            client: Address::new_id(1001),
+            provider: miner,
+            label: market::Label::String("data".to_string()),
+            start_epoch: 100,
+            end_epoch: 200,
+            storage_price_per_epoch: fil(0),
+            provider_collateral: fil(1),
+            client_collateral: fil(0),
+        };
+        let publish = market::PublishStorageDealsParams {
+            deals: vec![market::ClientDealProposal {
+                proposal: deal,
+                client_signature: Signature::new_secp256k1(vec![0; 65]),
+            }],
+        };
+        let preview = preview_message(
+            &message(
+                Address::new_id(5),
+                market::METHOD_PUBLISH_STORAGE_DEALS,
+                RawBytes::serialize(&publish).unwrap(),
+            ),
+            None,
+        )
+        .unwrap();
+        assert_eq!(preview.method.as_deref(), Some("PublishStorageDeals"));
+        let proposal = &preview.params["Deals"][0]["Proposal"];
+        assert_eq!(proposal["Provider"], "f01000");
+        assert_eq!(proposal["Label"], "data");
+        assert_eq!(proposal["ProviderCollateral"], "1 FIL");
+
+        let transfer = TransferParams {
+            to: Address::new_id(6),
+            amount: fil(1024),
+            operator_data: RawBytes::serialize(vec![1u8]).unwrap(),
+        };
+        let preview = preview_message(
+            &message(testnet("t07"), 14, RawBytes::serialize(&transfer).unwrap()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(preview.method.as_deref(), Some("Transfer"));
+        assert_eq!(
+            preview.params,
+            json!({"To": "t06", "Amount": "1024 DataCap", "OperatorData": [1]})
+        );
+
+        // allocation requests sent to the verified registry are decoded
+        let policy = crate::actors::Policy::mainnet();
+        let requests = crate::verifreg::AllocationRequests {
+            allocations: vec![crate::verifreg::AllocationRequest {
+                provider: Address::new_id(1000),
+                data: fvm_shared::commcid::data_commitment_v1_to_cid(&[1; 32]).unwrap(),
+                size: fvm_shared::piece::PaddedPieceSize(1 << 20),
+                term_min: policy.minimum_verified_allocation_term,
+                term_max: policy.maximum_verified_allocation_term,
+                expiration: 1000,
+            }],
+            extensions: vec![],
+        };
+        let allocate =
+            crate::verifreg::allocate_message(&policy, Address::new_id(1001), &requests, 0)
+                .unwrap();
+        let preview = preview_message(&allocate, None).unwrap();
+        assert_eq!(preview.method.as_deref(), Some("Transfer"));
+        assert_eq!(preview.params["Amount"], "1048576 DataCap");
+        let allocation = &preview.params["OperatorData"]["Allocations"][0];
+        assert_eq!(allocation["Provider"], "f01000");
+        assert_eq!(allocation["Size"], 1 << 20);
+
+        let voucher = crate::paych::SignedVoucher::new(testnet("t02001"), 1, 3, fil(2));
+        let update =
+            crate::paych::update_channel_state_message(Address::new_id(1001), &voucher, &[])
+                .unwrap();
+        let preview = preview_message(&update, Some(ActorType::PaymentChannel)).unwrap();
+        assert_eq!(preview.method.as_deref(), Some("UpdateChannelState"));
+        assert_eq!(preview.params["Sv"]["Amount"], "2 FIL");
+        assert_eq!(preview.params["Sv"]["Signed"], false);
+    }
+
+    #[test]
+    fn test_preview_encodings() {
+        let account = FlairAccount::generate(WalletType::Secp256k1).unwrap();
+        let message = MessageBuilder::new(
+            account.get_address().try_into().unwrap(),
+            Address::new_id(1234),
+        )
+        .value(fil(1))
+        .build();
+        let expected = preview_message(&message, None).unwrap();
+        assert_eq!(expected.method.as_deref(), None);
+        assert_eq!(expected.value, "1 FIL");
+
+        let signed = sign_message(&account, message.clone()).unwrap();
+        assert_eq!(
+            preview_cbor(&message.marshal_cbor().unwrap(), None).unwrap(),
+            expected
+        );
+        assert_eq!(
+            preview_cbor(&signed.marshal_cbor().unwrap(), None).unwrap(),
+            expected
+        );
+        let json = serde_json::to_string(&MessageJson::from(&message)).unwrap();
+        assert_eq!(preview_json(&json, None).unwrap(), expected);
+        let json = serde_json::to_string(&SignedMessageJson::from(&signed)).unwrap();
+        assert_eq!(preview_json(&json, None).unwrap(), expected);
+        assert!(preview_cbor(&[1, 2, 3], None).is_err());
+        assert!(preview_json("{}", None).is_err());
+
+        let preview = preview_message(&message, Some(ActorType::Account)).unwrap();
+        assert_eq!(preview.method.as_deref(), Some("Send"));
+    }
+
+    #[test]
+    fn test_actor_type() {
+        for actor in ACTOR_TYPES {
+            assert_eq!(parse_actor_type(actor.name()).unwrap(), actor);
+        }
+        assert_eq!(parse_actor_type("msig").unwrap(), ActorType::Multisig);
+        assert_eq!(ActorType::DataCap as u32, 12);
+        assert!(parse_actor_type("evm").is_err());
+        assert_eq!(
+            singleton_type(&Address::new_id(7)),
+            Some(ActorType::DataCap)
+        );
+        assert_eq!(singleton_type(&Address::new_id(100)), None);
+    }
+}
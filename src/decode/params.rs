@@ -0,0 +1,326 @@
+use fvm_ipld_encoding::{serde_bytes, tuple::*, BytesDe};
+use fvm_shared::{
+    address::{Address, Network},
+    econ::TokenAmount,
+    MethodNum,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use super::{cbor::generic_params, ActorType};
+use crate::{
+    actors::VERIFIED_REGISTRY_ACTOR_ID, datacap, helpers::units::format_fil, init, market, miner,
+    multisig, paych, verifreg,
+};
+
+#[derive(Serialize_tuple, Deserialize_tuple)]
+struct CreateMinerParams {
+    owner: Address,
+    worker: Address,
+    window_post_proof_type: i64,
+    #[serde(with = "serde_bytes")]
+    peer: Vec<u8>,
+    multiaddrs: Vec<BytesDe>,
+}
+
+const METHOD_POWER_CREATE_MINER: MethodNum = 2;
+
+fn decode<T: DeserializeOwned>(params: &[u8]) -> anyhow::Result<T> {
+    Ok(fvm_ipld_encoding::from_slice(params)?)
+}
+
+/// Decoded params of a message to `actor`, JSON with the field names Lotus
+/// uses. Methods without a decoder get a generic CBOR decoding, params that
+/// don't match their method are an error.
+pub fn decode_params(
+    network: Network,
+    actor: ActorType,
+    method: MethodNum,
+    params: &[u8],
+) -> anyhow::Result<Value> {
+    let show = |address: &Address| {
+        let mut address = *address;
+        address.set_network(network);
+        address.to_string()
+    };
+    let datacap =
+        |amount: &TokenAmount| format!("{} DataCap", format_fil(amount).trim_end_matches(" FIL"));
+    let multiaddrs =
+        |addrs: &[BytesDe]| -> Vec<String> { addrs.iter().map(|a| base64::encode(&a.0)).collect() };
+
+    let decoded = match (actor, method) {
+        (ActorType::Init, init::METHOD_EXEC) => {
+            let p: multisig::construct::ExecParams = decode(params)?;
+            json!({
+                "CodeCid": { "/": p.code_cid.to_string() },
+                "ConstructorParams": constructor_params(network, p.constructor_params.bytes()),
+            })
+        }
+        (ActorType::Multisig, multisig::METHOD_PROPOSE) => {
+            let p: multisig::propose::ProposeParams = decode(params)?;
+            json!({
+                "To": show(&p.to),
+                "Value": format_fil(&p.value),
+                "Method": p.method,
+                "Params": generic_params(p.params.bytes()),
+            })
+        }
+        (ActorType::Multisig, multisig::METHOD_APPROVE | multisig::METHOD_CANCEL) => {
+            let p: multisig::approve::TxnIDParams = decode(params)?;
+            json!({
+                "ID": p.id.0,
+                "ProposalHash": base64::encode(&p.proposal_hash),
+            })
+        }
+        (ActorType::Multisig, multisig::METHOD_ADD_SIGNER) => {
+            let p: multisig::signers::AddSignerParams = decode(params)?;
+            json!({ "Signer": show(&p.signer), "Increase": p.increase })
+        }
+        (ActorType::Multisig, multisig::METHOD_REMOVE_SIGNER) => {
+            let p: multisig::signers::RemoveSignerParams = decode(params)?;
+            json!({ "Signer": show(&p.signer), "Decrease": p.decrease })
+        }
+        (ActorType::Multisig, multisig::METHOD_SWAP_SIGNER) => {
+            let p: multisig::signers::SwapSignerParams = decode(params)?;
+            json!({ "From": show(&p.from), "To": show(&p.to) })
+        }
+        (ActorType::Multisig, multisig::METHOD_CHANGE_THRESHOLD) => {
+            let p: multisig::signers::ChangeNumApprovalsThresholdParams = decode(params)?;
+            json!({ "NewThreshold": p.new_threshold })
+        }
+        (ActorType::Multisig, multisig::METHOD_LOCK_BALANCE) => {
+            let p: multisig::signers::LockBalanceParams = decode(params)?;
+            json!({
+                "StartEpoch": p.start_epoch,
+                "UnlockDuration": p.unlock_duration,
+                "Amount": format_fil(&p.amount),
+            })
+        }
+        (ActorType::Miner, miner::METHOD_CHANGE_WORKER_ADDRESS) => {
+            let p: miner::ChangeWorkerAddressParams = decode(params)?;
+            json!({
+                "NewWorker": show(&p.new_worker),
+                "NewControlAddrs": p.new_control_addresses.iter().map(show).collect::<Vec<_>>(),
+            })
+        }
+        (ActorType::Miner, miner::METHOD_CHANGE_PEER_ID) => {
+            let p: miner::ChangePeerIDParams = decode(params)?;
+            json!({ "NewID": base64::encode(&p.new_id) })
+        }
+        (ActorType::Miner, miner::METHOD_WITHDRAW_BALANCE) => {
+            let p: miner::WithdrawBalanceParams = decode(params)?;
+            json!({ "AmountRequested": format_fil(&p.amount_requested) })
+        }
+        (ActorType::Miner, miner::METHOD_CHANGE_MULTIADDRS) => {
+            let p: miner::ChangeMultiaddrsParams = decode(params)?;
+            json!({ "NewMultiaddrs": multiaddrs(&p.new_multi_addrs) })
+        }
+        (ActorType::Miner, miner::METHOD_CHANGE_OWNER_ADDRESS) => {
+            let p: Address = decode(params)?;
+            json!({ "NewOwner": show(&p) })
+        }
+        (ActorType::Miner, miner::METHOD_CHANGE_BENEFICIARY) => {
+            let p: miner::ChangeBeneficiaryParams = decode(params)?;
+            json!({
+                "NewBeneficiary": show(&p.new_beneficiary),
+                "NewQuota": format_fil(&p.new_quota),
+                "NewExpiration": p.new_expiration,
+            })
+        }
+        (ActorType::Market, market::METHOD_ADD_BALANCE) => {
+            let p: Address = decode(params)?;
+            json!({ "Address": show(&p) })
+        }
+        (ActorType::Market, market::METHOD_WITHDRAW_BALANCE) => {
+            let p: market::WithdrawBalanceParams = decode(params)?;
+            json!({
+                "ProviderOrClientAddress": show(&p.provider_or_client),
+                "Amount": format_fil(&p.amount),
+            })
+        }
+        (ActorType::Market, market::METHOD_PUBLISH_STORAGE_DEALS) => {
+            let p: market::PublishStorageDealsParams = decode(params)?;
+            let deals: Vec<Value> = p
+                .deals
+                .iter()
+                .map(|deal| {
+                    let d = &deal.proposal;
+                    let label = match &d.label {
+                        market::Label::String(s) => json!(s),
+                        market::Label::Bytes(b) => json!(base64::encode(b)),
+                    };
+                    json!({
+                        "Proposal": {
+                            "PieceCID": {"/": d.piece_cid.to_string()},
+                            "PieceSize": d.piece_size.0,
+                            "VerifiedDeal": d.verified_deal,
+                            "Client": show(&d.client),
+                            "Provider": show(&d.provider),
+                            "Label": label,
+                            "StartEpoch": d.start_epoch,
+                            "EndEpoch": d.end_epoch,
+                            "StoragePricePerEpoch": format_fil(&d.storage_price_per_epoch),
+                            "ProviderCollateral": format_fil(&d.provider_collateral),
+                            "ClientCollateral": format_fil(&d.client_collateral),
+                        },
+                        "ClientSignature": base64::encode(deal.client_signature.bytes()),
+                    })
+                })
+                .collect();
+            json!({ "Deals": deals })
+        }
+        (ActorType::Power, METHOD_POWER_CREATE_MINER) => {
+            let p: CreateMinerParams = decode(params)?;
+            json!({
+                "Owner": show(&p.owner),
+                "Worker": show(&p.worker),
+                "WindowPoStProofType": p.window_post_proof_type,
+                "Peer": base64::encode(&p.peer),
+                "Multiaddrs": multiaddrs(&p.multiaddrs),
+            })
+        }
+        (
+            ActorType::VerifiedRegistry,
+            verifreg::METHOD_ADD_VERIFIER | verifreg::METHOD_ADD_VERIFIED_CLIENT,
+        ) => {
+            let p: verifreg::VerifierParams = decode(params)?;
+            json!({
+                "Address": show(&p.address),
+                "Allowance": format!("{} bytes", p.allowance),
+            })
+        }
+        (ActorType::VerifiedRegistry, verifreg::METHOD_REMOVE_VERIFIER) => {
+            let p: Address = decode(params)?;
+            json!({ "Verifier": show(&p) })
+        }
+        (ActorType::PaymentChannel, 1) => {
+            let p: paych::ConstructorParams = decode(params)?;
+            json!({ "From": show(&p.from), "To": show(&p.to) })
+        }
+        (ActorType::PaymentChannel, paych::METHOD_UPDATE_CHANNEL_STATE) => {
+            let p: paych::UpdateChannelStateParams = decode(params)?;
+            let sv = &p.sv;
+            json!({
+                "Sv": {
+                    "ChannelAddr": show(&sv.channel_addr),
+                    "TimeLockMin": sv.time_lock_min,
+                    "TimeLockMax": sv.time_lock_max,
+                    "SecretHash": base64::encode(&sv.secret_pre_image),
+                    "Lane": sv.lane,
+                    "Nonce": sv.nonce,
+                    "Amount": format_fil(&sv.amount),
+                    "MinSettleHeight": sv.min_settle_height,
+                    "Merges": sv.merges.iter().map(|m| json!({"Lane": m.lane, "Nonce": m.nonce})).collect::<Vec<_>>(),
+                    "Signed": sv.signature.is_some(),
+                },
+                "Secret": base64::encode(&p.secret),
+            })
+        }
+        (ActorType::DataCap, datacap::METHOD_TRANSFER | datacap::METHOD_TRANSFER_EXPORTED) => {
+            let p: datacap::TransferParams = decode(params)?;
+            // the verified registry takes allocation requests
+            let operator_data =
+                match decode::<verifreg::AllocationRequests>(p.operator_data.bytes()) {
+                    Ok(requests) if p.to.id().ok() == Some(VERIFIED_REGISTRY_ACTOR_ID) => {
+                        allocation_requests(&show, &requests)
+                    }
+                    _ => generic_params(p.operator_data.bytes()),
+                };
+            json!({
+                "To": show(&p.to),
+                "Amount": datacap(&p.amount),
+                "OperatorData": operator_data,
+            })
+        }
+        (ActorType::DataCap, datacap::METHOD_BALANCE_EXPORTED) => {
+            let p: Address = decode(params)?;
+            json!({ "Address": show(&p) })
+        }
+        (ActorType::DataCap, datacap::METHOD_ALLOWANCE | datacap::METHOD_ALLOWANCE_EXPORTED) => {
+            let p: datacap::GetAllowanceParams = decode(params)?;
+            json!({ "Owner": show(&p.owner), "Operator": show(&p.operator) })
+        }
+        (
+            ActorType::DataCap,
+            datacap::METHOD_TRANSFER_FROM | datacap::METHOD_TRANSFER_FROM_EXPORTED,
+        ) => {
+            let p: datacap::TransferFromParams = decode(params)?;
+            json!({
+                "From": show(&p.from),
+                "To": show(&p.to),
+                "Amount": datacap(&p.amount),
+                "OperatorData": generic_params(p.operator_data.bytes()),
+            })
+        }
+        (
+            ActorType::DataCap,
+            datacap::METHOD_INCREASE_ALLOWANCE | datacap::METHOD_INCREASE_ALLOWANCE_EXPORTED,
+        ) => {
+            let p: datacap::IncreaseAllowanceParams = decode(params)?;
+            json!({ "Operator": show(&p.operator), "Increase": datacap(&p.increase) })
+        }
+        (
+            ActorType::DataCap,
+            datacap::METHOD_DECREASE_ALLOWANCE | datacap::METHOD_DECREASE_ALLOWANCE_EXPORTED,
+        ) => {
+            let p: datacap::DecreaseAllowanceParams = decode(params)?;
+            json!({ "Operator": show(&p.operator), "Decrease": datacap(&p.decrease) })
+        }
+        (
+            ActorType::DataCap,
+            datacap::METHOD_REVOKE_ALLOWANCE | datacap::METHOD_REVOKE_ALLOWANCE_EXPORTED,
+        ) => {
+            let p: datacap::RevokeAllowanceParams = decode(params)?;
+            json!({ "Operator": show(&p.operator) })
+        }
+        (ActorType::DataCap, datacap::METHOD_BURN | datacap::METHOD_BURN_EXPORTED) => {
+            let p: datacap::BurnParams = decode(params)?;
+            json!({ "Amount": datacap(&p.amount) })
+        }
+        _ => generic_params(params),
+    };
+    Ok(decoded)
+}
+
+fn allocation_requests(
+    show: &dyn Fn(&Address) -> String,
+    r: &verifreg::AllocationRequests,
+) -> Value {
+    json!({
+        "Allocations": r.allocations.iter().map(|a| json!({
+            "Provider": show(&a.provider),
+            "Data": {"/": a.data.to_string()},
+            "Size": a.size.0,
+            "TermMin": a.term_min,
+            "TermMax": a.term_max,
+            "Expiration": a.expiration,
+        })).collect::<Vec<_>>(),
+        "Extensions": r.extensions.iter().map(|e| json!({
+            "Provider": show(&e.provider),
+            "Claim": e.claim,
+            "TermMax": e.term_max,
+        })).collect::<Vec<_>>(),
+    })
+}
+
+// Exec doesn't say which actor it creates without the code CIDs of the
+// network, the two account holders create are recognized by their shape
+fn constructor_params(network: Network, params: &[u8]) -> Value {
+    let show = |address: &Address| {
+        let mut address = *address;
+        address.set_network(network);
+        address.to_string()
+    };
+    if let Ok(p) = decode::<multisig::construct::ConstructorParams>(params) {
+        return json!({
+            "Signers": p.signers.iter().map(show).collect::<Vec<_>>(),
+            "NumApprovalsThreshold": p.num_approvals_threshold,
+            "UnlockDuration": p.unlock_duration,
+            "StartEpoch": p.start_epoch,
+        });
+    }
+    if let Ok(p) = decode::<paych::ConstructorParams>(params) {
+        return json!({ "From": show(&p.from), "To": show(&p.to) });
+    }
+    generic_params(params)
+}
@@ -0,0 +1,654 @@
+use std::{fmt, str::FromStr};
+
+use fvm_shared::bigint::{BigInt, Sign};
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+
+use super::eth_address_from_id;
+
+// Solidity ABI encoding of function calls. Values are JSON: numbers as
+// decimal or 0x strings, bytes and addresses as 0x strings, arrays and
+// tuples as arrays.
+
+const WORD: usize = 32;
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamType {
+    Address,
+    Bool,
+    /// bits
+    Uint(usize),
+    Int(usize),
+    /// bytes1 to bytes32
+    FixedBytes(usize),
+    Bytes,
+    String,
+    Array(Box<ParamType>),
+    FixedArray(Box<ParamType>, usize),
+    Tuple(Vec<ParamType>),
+}
+
+impl ParamType {
+    fn is_dynamic(&self) -> bool {
+        match self {
+            ParamType::Bytes | ParamType::String | ParamType::Array(_) => true,
+            ParamType::FixedArray(t, _) => t.is_dynamic(),
+            ParamType::Tuple(ts) => ts.iter().any(ParamType::is_dynamic),
+            _ => false,
+        }
+    }
+
+    /// bytes taken in the head of the enclosing tuple
+    fn head_size(&self) -> usize {
+        match self {
+            _ if self.is_dynamic() => WORD,
+            ParamType::FixedArray(t, n) => t.head_size() * n,
+            ParamType::Tuple(ts) => ts.iter().map(ParamType::head_size).sum(),
+            _ => WORD,
+        }
+    }
+}
+
+impl fmt::Display for ParamType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParamType::Address => f.write_str("address"),
+            ParamType::Bool => f.write_str("bool"),
+            ParamType::Uint(bits) => write!(f, "uint{}", bits),
+            ParamType::Int(bits) => write!(f, "int{}", bits),
+            ParamType::FixedBytes(n) => write!(f, "bytes{}", n),
+            ParamType::Bytes => f.write_str("bytes"),
+            ParamType::String => f.write_str("string"),
+            ParamType::Array(t) => write!(f, "{}[]", t),
+            ParamType::FixedArray(t, n) => write!(f, "{}[{}]", t, n),
+            ParamType::Tuple(ts) => write!(f, "({})", join(ts)),
+        }
+    }
+}
+
+fn join(types: &[ParamType]) -> String {
+    types
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl FromStr for ParamType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim();
+        if let Some(inner) = s.strip_suffix(']') {
+            let open = inner
+                .rfind('[')
+                .ok_or_else(|| anyhow::anyhow!("invalid type {}", s))?;
+            let element = Box::new(inner[..open].parse()?);
+            let size = &inner[open + 1..];
+            return Ok(if size.is_empty() {
+                ParamType::Array(element)
+            } else {
+                ParamType::FixedArray(element, size.parse()?)
+            });
+        }
+        if s.starts_with('(') {
+            let inner = s
+                .strip_prefix('(')
+                .and_then(|s| s.strip_suffix(')'))
+                .ok_or_else(|| anyhow::anyhow!("invalid type {}", s))?;
+            return Ok(ParamType::Tuple(parse_types(inner)?));
+        }
+        let bits = |digits: &str| -> anyhow::Result<usize> {
+            let bits = if digits.is_empty() {
+                256
+            } else {
+                digits.parse()?
+            };
+            if bits == 0 || bits > 256 || bits % 8 != 0 {
+                return Err(anyhow::anyhow!("invalid type {}", s));
+            }
+            Ok(bits)
+        };
+        Ok(match s {
+            "address" => ParamType::Address,
+            "bool" => ParamType::Bool,
+            "bytes" => ParamType::Bytes,
+            "string" => ParamType::String,
+            _ if s.starts_with("uint") => ParamType::Uint(bits(&s[4..])?),
+            _ if s.starts_with("int") => ParamType::Int(bits(&s[3..])?),
+            _ if s.starts_with("bytes") => match s[5..].parse() {
+                Ok(n) if (1..=32).contains(&n) => ParamType::FixedBytes(n),
+                _ => return Err(anyhow::anyhow!("invalid type {}", s)),
+            },
+            _ => return Err(anyhow::anyhow!("unknown type {}", s)),
+        })
+    }
+}
+
+/// Comma separated types, commas inside tuples don't split
+fn parse_types(s: &str) -> anyhow::Result<Vec<ParamType>> {
+    if s.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    let mut types = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                types.push(s[start..i].parse()?);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    types.push(s[start..].parse()?);
+    Ok(types)
+}
+
+/// Index of the parenthesis closing the one at `open`
+fn closing(s: &str, open: usize) -> anyhow::Result<usize> {
+    let mut depth = 0;
+    for (i, c) in s[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(open + i);
+                }
+            }
+            _ => {}
+        }
+    }
+    Err(anyhow::anyhow!("unbalanced parentheses in {}", s))
+}
+
+/// Contract function, parsed from a signature like `transfer(address,uint256)`
+/// with the return types optionally following, `balanceOf(address)(uint256)`
+/// or `balanceOf(address) returns (uint256)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub inputs: Vec<ParamType>,
+    pub outputs: Vec<ParamType>,
+}
+
+impl FromStr for Function {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim();
+        let open = s
+            .find('(')
+            .ok_or_else(|| anyhow::anyhow!("expected name(types) in {}", s))?;
+        let name = s[..open].trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(anyhow::anyhow!("invalid function name in {}", s));
+        }
+        let close = closing(s, open)?;
+        let inputs = parse_types(&s[open + 1..close])?;
+        let rest = s[close + 1..].trim();
+        let rest = rest.strip_prefix("returns").unwrap_or(rest).trim();
+        let outputs = match rest {
+            "" => vec![],
+            _ if rest.starts_with('(') && closing(rest, 0)? == rest.len() - 1 => {
+                parse_types(&rest[1..rest.len() - 1])?
+            }
+            _ => return Err(anyhow::anyhow!("invalid return types in {}", s)),
+        };
+        Ok(Function {
+            name: name.to_string(),
+            inputs,
+            outputs,
+        })
+    }
+}
+
+impl Function {
+    /// `name(type,...)`, what the selector hashes
+    pub fn signature(&self) -> String {
+        format!("{}({})", self.name, join(&self.inputs))
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        let hash = keccak256(self.signature().as_bytes());
+        [hash[0], hash[1], hash[2], hash[3]]
+    }
+
+    /// Calldata: the selector followed by the encoded arguments
+    pub fn encode_input(&self, args: &[Value]) -> anyhow::Result<Vec<u8>> {
+        let mut calldata = self.selector().to_vec();
+        calldata.extend(encode(&self.inputs, args)?);
+        Ok(calldata)
+    }
+
+    /// Calldata out of arguments given as strings, arrays and tuples in JSON
+    pub fn encode_input_str(&self, args: &[&str]) -> anyhow::Result<Vec<u8>> {
+        let args = self
+            .inputs
+            .iter()
+            .zip(args)
+            .map(|(t, arg)| match t {
+                ParamType::Array(_) | ParamType::FixedArray(..) | ParamType::Tuple(_) => {
+                    serde_json::from_str(arg)
+                        .map_err(|_| anyhow::anyhow!("expected a JSON array for {}", t))
+                }
+                _ => Ok(json!(arg)),
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if args.len() != self.inputs.len() {
+            return Err(anyhow::anyhow!(
+                "{} takes {} arguments, {} given",
+                self.signature(),
+                self.inputs.len(),
+                args.len()
+            ));
+        }
+        self.encode_input(&args)
+    }
+
+    pub fn decode_output(&self, data: &[u8]) -> anyhow::Result<Vec<Value>> {
+        decode(&self.outputs, data)
+    }
+}
+
+/// ABI encoding of `values` as a tuple of `types`
+pub fn encode(types: &[ParamType], values: &[Value]) -> anyhow::Result<Vec<u8>> {
+    if types.len() != values.len() {
+        return Err(anyhow::anyhow!(
+            "expected {} values, got {}",
+            types.len(),
+            values.len()
+        ));
+    }
+    let head_size: usize = types.iter().map(ParamType::head_size).sum();
+    let mut head = Vec::with_capacity(head_size);
+    let mut tail = vec![];
+    for (t, value) in types.iter().zip(values) {
+        let encoded =
+            encode_value(t, value).map_err(|e| anyhow::anyhow!("{} {}: {}", t, value, e))?;
+        if t.is_dynamic() {
+            head.extend(word(&BigInt::from(head_size + tail.len()))?);
+            tail.extend(encoded);
+        } else {
+            head.extend(encoded);
+        }
+    }
+    head.extend(tail);
+    Ok(head)
+}
+
+fn encode_value(t: &ParamType, value: &Value) -> anyhow::Result<Vec<u8>> {
+    match t {
+        ParamType::Address => {
+            let mut encoded = vec![0; 12];
+            encoded.extend(parse_address(value)?);
+            Ok(encoded)
+        }
+        ParamType::Bool => {
+            let b = match value {
+                Value::Bool(b) => *b,
+                Value::String(s) if s == "true" => true,
+                Value::String(s) if s == "false" => false,
+                _ => return Err(anyhow::anyhow!("expected a boolean")),
+            };
+            word(&BigInt::from(b as u8))
+        }
+        ParamType::Uint(bits) => {
+            let n = parse_number(value)?;
+            if n.sign() == Sign::Minus || n.bits() > *bits as u64 {
+                return Err(anyhow::anyhow!("out of range"));
+            }
+            word(&n)
+        }
+        ParamType::Int(bits) => {
+            let n = parse_number(value)?;
+            let limit = BigInt::from(1) << (bits - 1);
+            if n >= limit || n < -limit {
+                return Err(anyhow::anyhow!("out of range"));
+            }
+            word(&n)
+        }
+        ParamType::FixedBytes(size) => {
+            let bytes = parse_hex(value)?;
+            if bytes.len() != *size {
+                return Err(anyhow::anyhow!("expected {} bytes", size));
+            }
+            Ok(pad_right(&bytes))
+        }
+        ParamType::Bytes | ParamType::String => {
+            let bytes = match t {
+                ParamType::Bytes => parse_hex(value)?,
+                _ => value
+                    .as_str()
+                    .ok_or_else(|| anyhow::anyhow!("expected a string"))?
+                    .as_bytes()
+                    .to_vec(),
+            };
+            let mut encoded = word(&BigInt::from(bytes.len()))?;
+            encoded.extend(pad_right(&bytes));
+            Ok(encoded)
+        }
+        ParamType::Array(element) => {
+            let values = as_array(value)?;
+            let mut encoded = word(&BigInt::from(values.len()))?;
+            encoded.extend(encode(&vec![*element.clone(); values.len()], values)?);
+            Ok(encoded)
+        }
+        ParamType::FixedArray(element, size) => {
+            encode(&vec![*element.clone(); *size], as_array(value)?)
+        }
+        ParamType::Tuple(types) => encode(types, as_array(value)?),
+    }
+}
+
+/// Decode `data`, the ABI encoding of a tuple of `types`
+pub fn decode(types: &[ParamType], data: &[u8]) -> anyhow::Result<Vec<Value>> {
+    decode_tuple(types, data, 0)
+}
+
+fn decode_tuple(types: &[ParamType], data: &[u8], base: usize) -> anyhow::Result<Vec<Value>> {
+    let mut position = base;
+    let mut values = Vec::with_capacity(types.len());
+    for t in types {
+        let value = if t.is_dynamic() {
+            let offset = read_usize(data, position)?;
+            decode_value(t, data, base.checked_add(offset).ok_or_else(overflow)?)?
+        } else {
+            decode_value(t, data, position)?
+        };
+        values.push(value);
+        position += t.head_size();
+    }
+    Ok(values)
+}
+
+fn decode_value(t: &ParamType, data: &[u8], position: usize) -> anyhow::Result<Value> {
+    let word = read_word(data, position)?;
+    Ok(match t {
+        ParamType::Address => json!(format!("0x{}", hex::encode(&word[12..]))),
+        ParamType::Bool => json!(word[31] == 1),
+        ParamType::Uint(_) => json!(BigInt::from_bytes_be(Sign::Plus, word).to_string()),
+        ParamType::Int(_) => json!(BigInt::from_signed_bytes_be(word).to_string()),
+        ParamType::FixedBytes(size) => json!(format!("0x{}", hex::encode(&word[..*size]))),
+        ParamType::Bytes | ParamType::String => {
+            let len = read_usize(data, position)?;
+            let start = position + WORD;
+            let bytes = start
+                .checked_add(len)
+                .and_then(|end| data.get(start..end))
+                .ok_or_else(overflow)?;
+            match t {
+                ParamType::Bytes => json!(format!("0x{}", hex::encode(bytes))),
+                _ => json!(String::from_utf8_lossy(bytes)),
+            }
+        }
+        ParamType::Array(element) => {
+            let len = read_usize(data, position)?;
+            // every element takes at least a word, don't trust the length
+            if len > data.len() / WORD {
+                return Err(overflow());
+            }
+            json!(decode_tuple(
+                &vec![*element.clone(); len],
+                data,
+                position + WORD
+            )?)
+        }
+        ParamType::FixedArray(element, size) => {
+            if *size > data.len() / WORD {
+                return Err(overflow());
+            }
+            json!(decode_tuple(
+                &vec![*element.clone(); *size],
+                data,
+                position
+            )?)
+        }
+        ParamType::Tuple(types) => json!(decode_tuple(types, data, position)?),
+    })
+}
+
+fn overflow() -> anyhow::Error {
+    anyhow::anyhow!("ABI data too short")
+}
+
+fn read_word(data: &[u8], position: usize) -> anyhow::Result<&[u8]> {
+    position
+        .checked_add(WORD)
+        .and_then(|end| data.get(position..end))
+        .ok_or_else(overflow)
+}
+
+fn read_usize(data: &[u8], position: usize) -> anyhow::Result<usize> {
+    let word = read_word(data, position)?;
+    if word[..24].iter().any(|b| *b != 0) {
+        return Err(overflow());
+    }
+    Ok(u64::from_be_bytes(word[24..].try_into().unwrap()) as usize)
+}
+
+/// 32 bytes big endian, two's complement for negative numbers
+fn word(n: &BigInt) -> anyhow::Result<Vec<u8>> {
+    let bytes = n.to_signed_bytes_be();
+    if bytes.len() > WORD + 1 || (bytes.len() == WORD + 1 && bytes[0] != 0) {
+        return Err(anyhow::anyhow!("{} doesn't fit in 256 bits", n));
+    }
+    let bytes = &bytes[bytes.len().saturating_sub(WORD)..];
+    let fill = if n.sign() == Sign::Minus { 0xff } else { 0 };
+    let mut encoded = vec![fill; WORD - bytes.len()];
+    encoded.extend(bytes);
+    Ok(encoded)
+}
+
+fn pad_right(bytes: &[u8]) -> Vec<u8> {
+    let mut padded = bytes.to_vec();
+    padded.resize(bytes.len().div_ceil(WORD) * WORD, 0);
+    padded
+}
+
+fn as_array(value: &Value) -> anyhow::Result<&[Value]> {
+    value
+        .as_array()
+        .map(Vec::as_slice)
+        .ok_or_else(|| anyhow::anyhow!("expected an array"))
+}
+
+fn parse_number(value: &Value) -> anyhow::Result<BigInt> {
+    let s = match value {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.trim().to_string(),
+        _ => return Err(anyhow::anyhow!("expected a number")),
+    };
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, s.as_str()),
+    };
+    let n = match digits.strip_prefix("0x") {
+        Some(hex) => BigInt::parse_bytes(hex.as_bytes(), 16),
+        None => BigInt::parse_bytes(digits.as_bytes(), 10),
+    }
+    .ok_or_else(|| anyhow::anyhow!("invalid number {}", s))?;
+    Ok(if negative { -n } else { n })
+}
+
+fn parse_hex(value: &Value) -> anyhow::Result<Vec<u8>> {
+    let s = value
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("expected a 0x string"))?;
+    let hex = s
+        .trim()
+        .strip_prefix("0x")
+        .ok_or_else(|| anyhow::anyhow!("expected a 0x string"))?;
+    hex::decode(hex).map_err(|_| anyhow::anyhow!("invalid hex {}", s))
+}
+
+/// 0x followed by 20 bytes, or an f0/t0 ID address as its masked ID address
+fn parse_address(value: &Value) -> anyhow::Result<[u8; 20]> {
+    let s = value
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("expected an address"))?
+        .trim();
+    if let Some(id) = s.strip_prefix("f0").or_else(|| s.strip_prefix("t0")) {
+        let id = id
+            .parse()
+            .map_err(|_| anyhow::anyhow!("invalid address {}", s))?;
+        return Ok(eth_address_from_id(id));
+    }
+    parse_hex(value)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("expected a 20 bytes address"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_types() {
+        for t in [
+            "uint256",
+            "int8",
+            "bytes32",
+            "address[]",
+            "uint8[3][]",
+            "(uint256,(bool,string)[])",
+        ] {
+            assert_eq!(t.parse::<ParamType>().unwrap().to_string(), t);
+        }
+        assert_eq!("uint".parse::<ParamType>().unwrap(), ParamType::Uint(256));
+        for t in [
+            "uint7", "uint264", "bytes0", "bytes33", "float", "uint[", "(bool",
+        ] {
+            assert!(t.parse::<ParamType>().is_err(), "{}", t);
+        }
+
+        let f: Function = "balanceOf(address) returns (uint256)".parse().unwrap();
+        assert_eq!(f.signature(), "balanceOf(address)");
+        assert_eq!(f.outputs, vec![ParamType::Uint(256)]);
+        let f: Function = "balanceOf(address)(uint256)".parse().unwrap();
+        assert_eq!(hex::encode(f.selector()), "70a08231");
+        let f: Function = "transfer(address,uint256)".parse().unwrap();
+        assert_eq!(hex::encode(f.selector()), "a9059cbb");
+        assert!(f.outputs.is_empty());
+        assert!("transfer".parse::<Function>().is_err());
+        assert!("transfer(address".parse::<Function>().is_err());
+    }
+
+    // examples of the Solidity ABI specification
+    #[test]
+    fn test_encode() {
+        let f: Function = "baz(uint32,bool)".parse().unwrap();
+        assert_eq!(
+            hex::encode(f.encode_input_str(&["69", "true"]).unwrap()),
+            "cdcd77c0\
+             0000000000000000000000000000000000000000000000000000000000000045\
+             0000000000000000000000000000000000000000000000000000000000000001"
+        );
+
+        let f: Function = "sam(bytes,bool,uint256[])".parse().unwrap();
+        let calldata = f
+            .encode_input(&[json!("0x64617665"), json!(true), json!([1, 2, 3])])
+            .unwrap();
+        assert_eq!(
+            hex::encode(&calldata),
+            "a5643bf2\
+             0000000000000000000000000000000000000000000000000000000000000060\
+             0000000000000000000000000000000000000000000000000000000000000001\
+             00000000000000000000000000000000000000000000000000000000000000a0\
+             0000000000000000000000000000000000000000000000000000000000000004\
+             6461766500000000000000000000000000000000000000000000000000000000\
+             0000000000000000000000000000000000000000000000000000000000000003\
+             0000000000000000000000000000000000000000000000000000000000000001\
+             0000000000000000000000000000000000000000000000000000000000000002\
+             0000000000000000000000000000000000000000000000000000000000000003"
+        );
+        let types = f.inputs.clone();
+        assert_eq!(
+            decode(&types, &calldata[4..]).unwrap(),
+            vec![json!("0x64617665"), json!(true), json!(["1", "2", "3"])]
+        );
+
+        let f: Function = "f(uint256,uint32[],bytes10,bytes)".parse().unwrap();
+        let calldata = f
+            .encode_input_str(&[
+                "0x123",
+                "[\"0x456\", \"0x789\"]",
+                "0x31323334353637383930",
+                "0x48656c6c6f2c20776f726c6421",
+            ])
+            .unwrap();
+        assert_eq!(
+            hex::encode(calldata),
+            "8be65246\
+             0000000000000000000000000000000000000000000000000000000000000123\
+             0000000000000000000000000000000000000000000000000000000000000080\
+             3132333435363738393000000000000000000000000000000000000000000000\
+             00000000000000000000000000000000000000000000000000000000000000e0\
+             0000000000000000000000000000000000000000000000000000000000000002\
+             0000000000000000000000000000000000000000000000000000000000000456\
+             0000000000000000000000000000000000000000000000000000000000000789\
+             000000000000000000000000000000000000000000000000000000000000000d\
+             48656c6c6f2c20776f726c642100000000000000000000000000000000000000"
+        );
+    }
+
+    #[test]
+    fn test_values() {
+        let types: Vec<ParamType> = vec![
+            "int16".parse().unwrap(),
+            "address".parse().unwrap(),
+            "string".parse().unwrap(),
+            "(uint8,bytes)[]".parse().unwrap(),
+        ];
+        let values = [
+            json!("-2"),
+            json!("f01234"),
+            json!("héllo"),
+            json!([["7", "0x01"], ["8", "0x"]]),
+        ];
+        let encoded = encode(&types, &values).unwrap();
+        assert_eq!(
+            hex::encode(&encoded[..32]),
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe"
+        );
+        assert_eq!(
+            hex::encode(&encoded[44..64]),
+            "ff000000000000000000000000000000000004d2"
+        );
+        assert_eq!(
+            decode(&types, &encoded).unwrap(),
+            vec![
+                json!("-2"),
+                json!("0xff000000000000000000000000000000000004d2"),
+                json!("héllo"),
+                json!([["7", "0x01"], ["8", "0x"]]),
+            ]
+        );
+
+        // out of range, wrong shapes, truncated data
+        let uint8 = vec![ParamType::Uint(8)];
+        assert!(encode(&uint8, &[json!(256)]).is_err());
+        assert!(encode(&uint8, &[json!(-1)]).is_err());
+        assert!(encode(&[ParamType::Int(8)], &[json!(-129)]).is_err());
+        assert!(encode(&[ParamType::FixedBytes(2)], &[json!("0x01")]).is_err());
+        assert!(encode(&[ParamType::Address], &[json!("0x01")]).is_err());
+        assert!(encode(&uint8, &[]).is_err());
+        assert!(decode(&types, &encoded[..encoded.len() - 1]).is_err());
+        assert!(decode(&[ParamType::Bytes], &[0xff; 64]).is_err());
+
+        // offsets and sizes past the data
+        let mut far = vec![0; 32];
+        far[24..].copy_from_slice(&0xffff_ffff_ffff_fff0u64.to_be_bytes());
+        assert!(decode(&[ParamType::String], &far).is_err());
+        let huge: ParamType = "uint256[1000000000000]".parse().unwrap();
+        assert!(decode(&[huge], &[0; 64]).is_err());
+    }
+}
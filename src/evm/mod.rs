@@ -0,0 +1,333 @@
+pub mod abi;
+
+use fvm_ipld_encoding::{serde_bytes, tuple::*, BytesDe, BytesSer, RawBytes};
+use fvm_shared::{address::Address, econ::TokenAmount, message::Message, ActorID, MethodNum};
+
+use crate::{message::MessageBuilder, rpc::client::MessageReceipt};
+
+// Calls to Solidity contracts on the FEVM, and deployments through the
+// Ethereum address manager
+
+/// FRC-42 number of InvokeEVM, taking the calldata as CBOR bytes
+pub const METHOD_INVOKE_CONTRACT: MethodNum = 3844450837;
+
+pub const EAM_ACTOR_ID: ActorID = 10;
+pub const EAM_ACTOR_ADDR: Address = Address::new_id(EAM_ACTOR_ID);
+
+pub const METHOD_EAM_CREATE: MethodNum = 2;
+pub const METHOD_EAM_CREATE2: MethodNum = 3;
+pub const METHOD_EAM_CREATE_EXTERNAL: MethodNum = 4;
+
+/// Exit code of a call the contract reverted
+pub const EVM_CONTRACT_REVERTED: i64 = 33;
+
+/// Selectors of the Solidity `Error(string)` and `Panic(uint256)` reverts
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+pub type EthAddress = [u8; 20];
+
+/// Ethereum address of the actor `id`: 0xff, zeros, then the ID big endian
+pub fn eth_address_from_id(id: ActorID) -> EthAddress {
+    let mut address = [0; 20];
+    address[0] = 0xff;
+    address[12..].copy_from_slice(&id.to_be_bytes());
+    address
+}
+
+/// CBOR bytes of an exact length
+mod fixed_bytes {
+    use fvm_ipld_encoding::{serde_bytes, BytesDe};
+    use serde::{de::Error, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        bytes: &[u8; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serde_bytes::serialize(bytes.as_slice(), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[u8; N], D::Error> {
+        let bytes: BytesDe = serde::Deserialize::deserialize(deserializer)?;
+        let len = bytes.0.len();
+        bytes
+            .0
+            .try_into()
+            .map_err(|_| D::Error::custom(format!("expected {} bytes, got {}", N, len)))
+    }
+}
+
+/// Create from a caller with an Ethereum address, `nonce` being its nonce
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct CreateParams {
+    #[serde(with = "serde_bytes")]
+    pub initcode: Vec<u8>,
+    pub nonce: u64,
+}
+
+/// Create at an address derived from the caller, `salt` and the initcode
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct Create2Params {
+    #[serde(with = "serde_bytes")]
+    pub initcode: Vec<u8>,
+    #[serde(with = "fixed_bytes")]
+    pub salt: [u8; 32],
+}
+
+/// Returned by the three create methods
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct CreateReturn {
+    pub actor_id: ActorID,
+    pub robust_address: Option<Address>,
+    #[serde(with = "fixed_bytes")]
+    pub eth_address: EthAddress,
+}
+
+/// InvokeContract params: the calldata as CBOR bytes, nothing when empty
+pub fn invoke_contract_params(calldata: &[u8]) -> anyhow::Result<RawBytes> {
+    if calldata.is_empty() {
+        return Ok(RawBytes::default());
+    }
+    Ok(RawBytes::serialize(BytesSer(calldata))?)
+}
+
+/// Call `contract` with ABI encoded `calldata`
+pub fn invoke_contract_message(
+    from: Address,
+    contract: Address,
+    calldata: &[u8],
+    value: TokenAmount,
+) -> anyhow::Result<Message> {
+    Ok(MessageBuilder::new(from, contract)
+        .value(value)
+        .method(METHOD_INVOKE_CONTRACT)
+        .params(invoke_contract_params(calldata)?)
+        .build())
+}
+
+/// Deploy from an f1 or f3 account, what Lotus does for `evm deploy`
+pub fn create_external_message(
+    from: Address,
+    initcode: &[u8],
+    value: TokenAmount,
+) -> anyhow::Result<Message> {
+    Ok(MessageBuilder::new(from, EAM_ACTOR_ADDR)
+        .value(value)
+        .method(METHOD_EAM_CREATE_EXTERNAL)
+        .params(RawBytes::serialize(BytesSer(initcode))?)
+        .build())
+}
+
+/// Deploy with Create, the EAM only takes it from callers with an Ethereum
+/// address
+pub fn create_message(
+    from: Address,
+    initcode: Vec<u8>,
+    nonce: u64,
+    value: TokenAmount,
+) -> anyhow::Result<Message> {
+    Ok(MessageBuilder::new(from, EAM_ACTOR_ADDR)
+        .value(value)
+        .method(METHOD_EAM_CREATE)
+        .params(RawBytes::serialize(CreateParams { initcode, nonce })?)
+        .build())
+}
+
+/// Deploy with Create2, from callers with an Ethereum address
+pub fn create2_message(
+    from: Address,
+    initcode: Vec<u8>,
+    salt: [u8; 32],
+    value: TokenAmount,
+) -> anyhow::Result<Message> {
+    Ok(MessageBuilder::new(from, EAM_ACTOR_ADDR)
+        .value(value)
+        .method(METHOD_EAM_CREATE2)
+        .params(RawBytes::serialize(Create2Params { initcode, salt })?)
+        .build())
+}
+
+/// Return data of InvokeContract, the CBOR bytes unwrapped
+pub fn decode_return(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    if data.is_empty() {
+        return Ok(vec![]);
+    }
+    let bytes: BytesDe = fvm_ipld_encoding::from_slice(data)
+        .map_err(|_| anyhow::anyhow!("contract return is not CBOR bytes"))?;
+    Ok(bytes.0)
+}
+
+/// Readable reason out of the revert data of a contract
+pub fn revert_reason(data: &[u8]) -> String {
+    if data.is_empty() {
+        return "reverted without a reason".to_string();
+    }
+    let (selector, args) = data.split_at(data.len().min(4));
+    let decoded = |t: abi::ParamType| {
+        abi::decode(&[t], args)
+            .ok()
+            .and_then(|values| values[0].as_str().map(str::to_string))
+    };
+    if selector == ERROR_SELECTOR {
+        if let Some(message) = decoded(abi::ParamType::String) {
+            return message;
+        }
+    }
+    if selector == PANIC_SELECTOR {
+        if let Some(code) = decoded(abi::ParamType::Uint(256)) {
+            return format!("panic {} ({})", code, panic_reason(&code));
+        }
+    }
+    format!("reverted with 0x{}", hex::encode(data))
+}
+
+/// Solidity panic codes
+fn panic_reason(code: &str) -> &'static str {
+    match code.parse::<u64>().unwrap_or(u64::MAX) {
+        0x00 => "generic panic",
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow",
+        0x12 => "division by zero",
+        0x21 => "invalid enum value",
+        0x22 => "invalid storage byte array",
+        0x31 => "pop on an empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to an invalid internal function",
+        _ => "unknown panic",
+    }
+}
+
+/// Output of a contract call out of its receipt, an error with the revert
+/// reason when it failed
+pub fn contract_output(receipt: &MessageReceipt) -> anyhow::Result<Vec<u8>> {
+    let data = match &receipt.return_data {
+        Some(data) => {
+            base64::decode(data).map_err(|_| anyhow::anyhow!("invalid return data encoding"))?
+        }
+        None => vec![],
+    };
+    match receipt.exit_code {
+        0 => decode_return(&data),
+        EVM_CONTRACT_REVERTED => Err(anyhow::anyhow!(
+            "contract reverted: {}",
+            revert_reason(&decode_return(&data).unwrap_or(data))
+        )),
+        code => Err(anyhow::anyhow!("call failed with exit code {}", code)),
+    }
+}
+
+/// Deployed contract out of the receipt of a create message
+pub fn create_return(receipt: &MessageReceipt) -> anyhow::Result<CreateReturn> {
+    if receipt.exit_code != 0 {
+        return Err(anyhow::anyhow!(
+            "deployment failed with exit code {}",
+            receipt.exit_code
+        ));
+    }
+    let data = receipt
+        .return_data
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("deployment returned nothing"))?;
+    let data = base64::decode(data).map_err(|_| anyhow::anyhow!("invalid return data encoding"))?;
+    Ok(fvm_ipld_encoding::from_slice(&data)?)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn receipt(exit_code: i64, data: &[u8]) -> MessageReceipt {
+        MessageReceipt {
+            exit_code,
+            return_data: (!data.is_empty()).then(|| base64::encode(data)),
+            gas_used: 0,
+        }
+    }
+
+    #[test]
+    fn test_invoke_contract() {
+        let f: abi::Function = "transfer(address,uint256)(bool)".parse().unwrap();
+        let calldata = f.encode_input_str(&["f01234", "1000"]).unwrap();
+        let message = invoke_contract_message(
+            Address::new_id(1000),
+            Address::new_id(2000),
+            &calldata,
+            TokenAmount::from_atto(0),
+        )
+        .unwrap();
+        assert_eq!(message.method_num, METHOD_INVOKE_CONTRACT);
+        // a CBOR byte string of 68 bytes
+        assert_eq!(&message.params.bytes()[..2], &[0x58, 68]);
+        assert_eq!(decode_return(message.params.bytes()).unwrap(), calldata);
+        assert!(invoke_contract_params(&[]).unwrap().is_empty());
+
+        let output =
+            RawBytes::serialize(BytesSer(&abi::encode(&f.outputs, &[json!(true)]).unwrap()))
+                .unwrap();
+        let output = contract_output(&receipt(0, output.bytes())).unwrap();
+        assert_eq!(f.decode_output(&output).unwrap(), vec![json!(true)]);
+        assert!(contract_output(&receipt(0, &[])).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_revert_reason() {
+        let mut error = ERROR_SELECTOR.to_vec();
+        error.extend(
+            abi::encode(&[abi::ParamType::String], &[json!("not enough balance")]).unwrap(),
+        );
+        assert_eq!(revert_reason(&error), "not enough balance");
+        let reverted = RawBytes::serialize(BytesSer(&error)).unwrap();
+        let err = contract_output(&receipt(EVM_CONTRACT_REVERTED, reverted.bytes())).unwrap_err();
+        assert_eq!(err.to_string(), "contract reverted: not enough balance");
+
+        let mut panic = PANIC_SELECTOR.to_vec();
+        panic.extend(abi::encode(&[abi::ParamType::Uint(256)], &[json!("0x11")]).unwrap());
+        assert_eq!(revert_reason(&panic), "panic 17 (arithmetic overflow)");
+        assert_eq!(revert_reason(&[]), "reverted without a reason");
+        assert_eq!(revert_reason(&[1, 2]), "reverted with 0x0102");
+        // a custom error, or an Error without its string
+        assert_eq!(revert_reason(&ERROR_SELECTOR), "reverted with 0x08c379a0");
+        let mut far = ERROR_SELECTOR.to_vec();
+        far.extend([0; 24]);
+        far.extend(0xffff_ffff_ffff_fff0u64.to_be_bytes());
+        assert!(revert_reason(&far).starts_with("reverted with 0x08c379a0"));
+        assert!(contract_output(&receipt(7, &[])).is_err());
+    }
+
+    #[test]
+    fn test_deploy() {
+        let from = Address::new_id(1000);
+        let initcode = vec![0x60, 0x80, 0x60, 0x40];
+        let external = create_external_message(from, &initcode, TokenAmount::from_atto(0)).unwrap();
+        assert_eq!(external.to, EAM_ACTOR_ADDR);
+        assert_eq!(external.method_num, METHOD_EAM_CREATE_EXTERNAL);
+        assert_eq!(decode_return(external.params.bytes()).unwrap(), initcode);
+
+        let create = create_message(from, initcode.clone(), 3, TokenAmount::from_atto(0)).unwrap();
+        assert_eq!(create.method_num, METHOD_EAM_CREATE);
+        let params: CreateParams = create.params.deserialize().unwrap();
+        assert_eq!(params.nonce, 3);
+        let create2 = create2_message(from, initcode, [7; 32], TokenAmount::from_atto(0)).unwrap();
+        assert_eq!(create2.method_num, METHOD_EAM_CREATE2);
+        let params: Create2Params = create2.params.deserialize().unwrap();
+        assert_eq!(params.salt, [7; 32]);
+
+        let created = CreateReturn {
+            actor_id: 1234,
+            robust_address: Some(Address::new_actor(b"contract")),
+            eth_address: [0xaa; 20],
+        };
+        let data = fvm_ipld_encoding::to_vec(&created).unwrap();
+        assert_eq!(create_return(&receipt(0, &data)).unwrap(), created);
+        assert!(create_return(&receipt(1, &data)).is_err());
+        assert_eq!(
+            hex::encode(eth_address_from_id(1234)),
+            "ff000000000000000000000000000000000004d2"
+        );
+    }
+}
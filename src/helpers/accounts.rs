@@ -1,27 +1,32 @@
 use crate::{
-    models::account::{FlairAccount, FlairAddress, FlairPrivate, FlairPublic},
+    models::account::{FlairAccount, FlairAddress, FlairPrivate, FlairPublic, KeyInfo},
     types::WalletType,
 };
-use anyhow::Context;
 use bls_signatures::{PrivateKey as BlsPrivate, Serialize};
 use fvm_shared::address::Address;
 use libsecp256k1::{PublicKey as SecpPublic, SecretKey as SecpPrivate};
+use zeroize::{Zeroize, Zeroizing};
 
-/// private key -> public key -> filecoin address -> account
+// private key -> public key -> filecoin address -> account
 
 /// Generate a new private key
 pub fn generate_private(wallet_type: &WalletType) -> anyhow::Result<FlairPrivate> {
-    let rng = &mut rand::rngs::OsRng::default();
-    match wallet_type {
+    let rng = &mut rand::rngs::OsRng;
+    let mut private_key: FlairPrivate = match wallet_type {
         WalletType::Bls => {
             let key = BlsPrivate::generate(rng);
-            Ok(key.as_bytes().into())
+            key.as_bytes().into()
         }
         WalletType::Secp256k1 => {
             let key = SecpPrivate::random(rng);
-            Ok(key.serialize().to_vec().into())
+            let mut bytes = key.serialize();
+            let private_key = bytes.to_vec().into();
+            bytes.zeroize();
+            private_key
         }
-    }
+    };
+    private_key.set_type(*wallet_type);
+    Ok(private_key)
 }
 
 /// Generate a new public key from supplied private key
@@ -30,15 +35,12 @@ pub fn generate_public(
     private_key: &FlairPrivate,
 ) -> anyhow::Result<FlairPublic> {
     match wallet_type {
-        WalletType::Bls => {
-            let pkey = private_key.to_vec();
-            Ok(BlsPrivate::from_bytes(&pkey)?
-                .public_key()
-                .as_bytes()
-                .into())
-        }
+        WalletType::Bls => Ok(BlsPrivate::from_bytes(private_key.as_bytes())?
+            .public_key()
+            .as_bytes()
+            .into()),
         WalletType::Secp256k1 => {
-            let private_key = SecpPrivate::parse_slice(&private_key.to_vec())?;
+            let private_key = SecpPrivate::parse_slice(private_key.as_bytes())?;
             let public_key = SecpPublic::from_secret_key(&private_key);
             Ok(public_key.serialize().to_vec().into())
         }
@@ -89,44 +91,27 @@ pub fn generate_account_from_private(
     let public_key = generate_public(wallet_type, private_key)?;
     let address = generate_address(wallet_type, &public_key)?;
     let mut account = generate_account_from_address(wallet_type, address)?;
-    account.set_private(private_key.clone());
+    // keep the exported KeyInfo type in line with the account
+    let mut private_key = private_key.clone();
+    private_key.set_type(*wallet_type);
+    account.set_private(private_key);
     account.set_public(public_key);
     Ok(account)
 }
 
 pub fn parse_private_key_string(key: &str) -> anyhow::Result<FlairAccount> {
-    let decoded_key: serde_json::Value = serde_json::from_str(key)?;
-
-    dbg!(&decoded_key);
-
-    let pkey = decoded_key
-        .get("PrivateKey")
-        .context("Private key format error!")?
-        .as_str()
-        .context("Private key format error!")?
-        .trim();
-
-    let wallet_type = match decoded_key.get("Type") {
-        Some(t) if t.to_string().trim() != "null" => {
-            let tt = t.to_string();
-            if tt.contains("bls") {
-                WalletType::Bls
-            } else if tt.contains("secp256k1") {
-                WalletType::Secp256k1
-            } else {
-                return Err(anyhow::anyhow!("Private key format error!"));
-            }
-        }
-        _ => WalletType::Secp256k1,
+    // the error would echo the input, which contains the key
+    let decoded_key: KeyInfo =
+        serde_json::from_str(key).map_err(|_| anyhow::anyhow!("Private key format error!"))?;
+
+    let wallet_type = match &decoded_key.wallet_type {
+        Some(t) if t.contains("bls") => WalletType::Bls,
+        Some(t) if t.contains("secp256k1") => WalletType::Secp256k1,
+        Some(_) => return Err(anyhow::anyhow!("Private key format error!")),
+        None => WalletType::Secp256k1,
     };
-    dbg!(&wallet_type);
-
-    // let wallet_type = match rtype.as_str() {
-    //     "secp256k1" => WalletType::Secp256k1,
-    //     "bls" => WalletType::Bls,
-    //     _ => return Err(anyhow::anyhow!("Private key format error!")),
-    // };
 
+    let pkey = decoded_key.private_key.trim();
     let mut private_key: FlairPrivate = base64::decode(pkey)?.into();
     private_key.set_type(wallet_type);
     let account = generate_account_from_private(&wallet_type, &private_key)?;
@@ -137,9 +122,9 @@ pub fn parse_private_key_string(key: &str) -> anyhow::Result<FlairAccount> {
 /// Exported wallet private key from wallets/lotus
 pub fn generate_account_from_encoded_string(key: &str) -> anyhow::Result<FlairAccount> {
     let key = key.trim();
-    let decoded_key = hex::decode(key)?;
-    let key = String::from_utf8(decoded_key)?;
-    let account = parse_private_key_string(&key)?;
+    let decoded_key = Zeroizing::new(hex::decode(key)?);
+    let key = std::str::from_utf8(&decoded_key)?;
+    let account = parse_private_key_string(key)?;
 
     Ok(account)
 }
@@ -0,0 +1,204 @@
+use sha2::{Digest, Sha256};
+
+// user supplied randomness (dice rolls, coin flips) -> entropy -> mnemonic
+
+/// Approximate 99.9% quantile of the standard normal distribution
+const CHI_SQUARED_Z: f64 = 3.09;
+
+/// Minimum number of observations needed to cover the given entropy bits
+/// with a source that has `outcomes` equally likely outcomes.
+pub fn required_observations(outcomes: u32, entropy_bits: usize) -> usize {
+    let bits_per_observation = (outcomes as f64).log2();
+    (entropy_bits as f64 / bits_per_observation).ceil() as usize
+}
+
+/// Reject obviously biased sources with a chi-squared goodness-of-fit test
+/// against the uniform distribution at a 0.1% significance level.
+pub fn check_bias(counts: &[usize]) -> anyhow::Result<()> {
+    let total: usize = counts.iter().sum();
+    if counts.len() < 2 || total == 0 {
+        return Err(anyhow::anyhow!("not enough observations to check for bias"));
+    }
+
+    let expected = total as f64 / counts.len() as f64;
+    let statistic: f64 = counts
+        .iter()
+        .map(|&c| (c as f64 - expected).powi(2) / expected)
+        .sum();
+
+    // Wilson-Hilferty approximation of the chi-squared critical value
+    let df = (counts.len() - 1) as f64;
+    let h = 2.0 / (9.0 * df);
+    let critical = df * (1.0 - h + CHI_SQUARED_Z * h.sqrt()).powi(3);
+
+    if statistic > critical {
+        return Err(anyhow::anyhow!(
+            "input looks biased (chi-squared {:.2} > {:.2}), use a fair source",
+            statistic,
+            critical
+        ));
+    }
+    Ok(())
+}
+
+/// Convert dice rolls (values `1..=sides`) into `entropy_bits` of entropy.
+///
+/// The rolls are written as decimal numbers (without separator for dice with less
+/// than 10 sides, comma separated otherwise) and hashed with SHA-256, which matches
+/// the dice-roll seed generation of Coldcard for six sided dice.
+pub fn dice_to_entropy(rolls: &[u8], sides: u8, entropy_bits: usize) -> anyhow::Result<Vec<u8>> {
+    if sides < 2 {
+        return Err(anyhow::anyhow!("dice need at least 2 sides, got {}", sides));
+    }
+    if !entropy_bits.is_multiple_of(8) || entropy_bits > 256 {
+        return Err(anyhow::anyhow!(
+            "unsupported entropy size: {}",
+            entropy_bits
+        ));
+    }
+
+    let required = required_observations(sides as u32, entropy_bits);
+    if rolls.len() < required {
+        return Err(anyhow::anyhow!(
+            "{} rolls of a {} sided die are needed for {} bits of entropy, got {}",
+            required,
+            sides,
+            entropy_bits,
+            rolls.len()
+        ));
+    }
+
+    let mut counts = vec![0usize; sides as usize];
+    for &roll in rolls {
+        if roll == 0 || roll > sides {
+            return Err(anyhow::anyhow!(
+                "invalid roll {} for a {} sided die",
+                roll,
+                sides
+            ));
+        }
+        counts[roll as usize - 1] += 1;
+    }
+    check_bias(&counts)?;
+
+    let separator = if sides < 10 { "" } else { "," };
+    let canonical = rolls
+        .iter()
+        .map(|r| r.to_string())
+        .collect::<Vec<String>>()
+        .join(separator);
+
+    let digest = Sha256::digest(canonical.as_bytes());
+    Ok(digest[..entropy_bits / 8].to_vec())
+}
+
+/// Convert coin flips into `entropy_bits` of entropy, one flip per bit (heads = 1),
+/// most significant bit first. Extra flips are only used for the bias check.
+pub fn coin_flips_to_entropy(flips: &[bool], entropy_bits: usize) -> anyhow::Result<Vec<u8>> {
+    if !entropy_bits.is_multiple_of(8) || entropy_bits > 256 {
+        return Err(anyhow::anyhow!(
+            "unsupported entropy size: {}",
+            entropy_bits
+        ));
+    }
+    if flips.len() < entropy_bits {
+        return Err(anyhow::anyhow!(
+            "{} coin flips are needed for {} bits of entropy, got {}",
+            entropy_bits,
+            entropy_bits,
+            flips.len()
+        ));
+    }
+
+    let heads = flips.iter().filter(|f| **f).count();
+    check_bias(&[heads, flips.len() - heads])?;
+
+    let entropy = flips[..entropy_bits]
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0u8, |acc, bit| acc << 1 | *bit as u8))
+        .collect();
+    Ok(entropy)
+}
+
+/// Parse dice rolls written as digits, optionally separated by whitespace or commas
+/// (e.g. "3 6 1 2" or "3612"). Dice with 10 or more sides need separators.
+pub fn parse_dice_rolls(rolls: &str) -> anyhow::Result<Vec<u8>> {
+    let tokens: Vec<&str> = rolls
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    let single_digits = tokens.len() == 1 || tokens.iter().all(|t| t.len() == 1);
+    if single_digits {
+        return tokens
+            .concat()
+            .chars()
+            .map(|c| {
+                c.to_digit(10)
+                    .map(|d| d as u8)
+                    .ok_or_else(|| anyhow::anyhow!("invalid dice roll: {}", c))
+            })
+            .collect();
+    }
+
+    tokens
+        .iter()
+        .map(|t| {
+            t.parse::<u8>()
+                .map_err(|_| anyhow::anyhow!("invalid dice roll: {}", t))
+        })
+        .collect()
+}
+
+/// Parse coin flips written as `h`/`t` or `1`/`0`, ignoring whitespace.
+pub fn parse_coin_flips(flips: &str) -> anyhow::Result<Vec<bool>> {
+    flips
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c.to_ascii_lowercase() {
+            'h' | '1' => Ok(true),
+            't' | '0' => Ok(false),
+            _ => Err(anyhow::anyhow!("invalid coin flip: {}", c)),
+        })
+        .collect()
+}
+
+#[test]
+fn test_dice_to_entropy() {
+    let rolls = parse_dice_rolls(&"123456".repeat(17)).unwrap();
+    let entropy = dice_to_entropy(&rolls, 6, 128).unwrap();
+    assert_eq!(hex::encode(entropy), "8fd128918b2e29d6dcbfa5b9a118e5c1");
+
+    // 49 rolls only carry ~126 bits
+    assert!(dice_to_entropy(&rolls[..49], 6, 128).is_err());
+    // out of range roll
+    let mut invalid = rolls.clone();
+    invalid[3] = 7;
+    assert!(dice_to_entropy(&invalid, 6, 128).is_err());
+}
+
+#[test]
+fn test_dice_bias() {
+    let rolls = vec![6u8; 100];
+    let err = dice_to_entropy(&rolls, 6, 128).unwrap_err();
+    assert!(err.to_string().contains("biased"));
+}
+
+#[test]
+fn test_coin_flips_to_entropy() {
+    let flips = parse_coin_flips(&"hhtt".repeat(32)).unwrap();
+    let entropy = coin_flips_to_entropy(&flips, 128).unwrap();
+    assert_eq!(entropy, vec![0b1100_1100; 16]);
+
+    let flips = vec![true; 128];
+    assert!(coin_flips_to_entropy(&flips, 128).is_err());
+    assert!(coin_flips_to_entropy(&flips[..100], 128).is_err());
+}
+
+#[test]
+fn test_parse_dice_rolls() {
+    assert_eq!(parse_dice_rolls("3 6 1,2").unwrap(), vec![3, 6, 1, 2]);
+    assert_eq!(parse_dice_rolls("3612").unwrap(), vec![3, 6, 1, 2]);
+    assert_eq!(parse_dice_rolls("12 20 7").unwrap(), vec![12, 20, 7]);
+    assert!(parse_dice_rolls("3a").is_err());
+}
@@ -0,0 +1,341 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use fvm_shared::clock::{ChainEpoch, EPOCH_DURATION_SECONDS};
+
+use crate::{
+    actors::{EPOCHS_IN_YEAR, SECONDS_IN_DAY, SECONDS_IN_HOUR},
+    types::ChainType,
+};
+
+// Chain epochs as UTC dates and as durations like "2 days 4h", for vesting,
+// deal windows and confirmations written by users
+
+/// Duration units and their length in seconds, a year being the actors' year
+const UNITS: &[(&[&str], i64)] = &[
+    (
+        &["y", "yr", "yrs", "year", "years"],
+        EPOCHS_IN_YEAR * EPOCH_DURATION_SECONDS,
+    ),
+    (&["w", "week", "weeks"], 7 * SECONDS_IN_DAY),
+    (&["d", "day", "days"], SECONDS_IN_DAY),
+    (&["h", "hr", "hrs", "hour", "hours"], SECONDS_IN_HOUR),
+    (&["m", "min", "mins", "minute", "minutes"], 60),
+    (&["s", "sec", "secs", "second", "seconds"], 1),
+    (&["epoch", "epochs"], EPOCH_DURATION_SECONDS),
+];
+
+/// Converts between the epochs and the unix time of a network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainClock {
+    genesis_timestamp: u64,
+}
+
+impl ChainClock {
+    pub fn new(genesis_timestamp: u64) -> Self {
+        ChainClock { genesis_timestamp }
+    }
+
+    /// Clock of `chain`, an error for networks without a fixed genesis
+    pub fn for_network(chain: ChainType) -> anyhow::Result<Self> {
+        chain
+            .genesis_timestamp()
+            .map(ChainClock::new)
+            .ok_or_else(|| anyhow::anyhow!("the genesis time of {} is not known", chain))
+    }
+
+    pub fn genesis_timestamp(&self) -> u64 {
+        self.genesis_timestamp
+    }
+
+    /// Unix time `epoch` starts at
+    pub fn timestamp(&self, epoch: ChainEpoch) -> u64 {
+        self.genesis_timestamp
+            .saturating_add_signed(epoch.saturating_mul(EPOCH_DURATION_SECONDS))
+    }
+
+    /// Epoch in progress at unix time `timestamp`, negative before genesis
+    pub fn epoch_at(&self, timestamp: u64) -> ChainEpoch {
+        (timestamp as i64 - self.genesis_timestamp as i64).div_euclid(EPOCH_DURATION_SECONDS)
+    }
+
+    pub fn current_epoch(&self) -> anyhow::Result<ChainEpoch> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        Ok(self.epoch_at(now))
+    }
+}
+
+/// Parse an epoch, either its number or a UTC date like "2024-03-01 12:00"
+pub fn parse_epoch(chain: ChainType, epoch: &str) -> anyhow::Result<ChainEpoch> {
+    let epoch = epoch.trim();
+    if let Ok(epoch) = epoch.parse() {
+        return Ok(epoch);
+    }
+    let timestamp = parse_utc(epoch)?;
+    Ok(ChainClock::for_network(chain)?.epoch_at(timestamp))
+}
+
+/// Parse a duration like "2 days 4h", "180d" or "1 year" into epochs, a plain
+/// number being epochs
+pub fn parse_duration(duration: &str) -> anyhow::Result<ChainEpoch> {
+    let invalid = || anyhow::anyhow!("invalid duration: {}", duration);
+    let trimmed = duration.trim();
+    if trimmed.is_empty() {
+        return Err(invalid());
+    }
+    if let Ok(epochs) = trimmed.parse::<u64>() {
+        return ChainEpoch::try_from(epochs).map_err(|_| invalid());
+    }
+
+    let mut rest = trimmed;
+    let mut seconds: i64 = 0;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (number, tail) = rest.split_at(split);
+        let number: i64 = number.parse().map_err(|_| invalid())?;
+        let tail = tail.trim_start();
+        let split = tail
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(split);
+        let unit = unit.to_lowercase();
+        let length = UNITS
+            .iter()
+            .find(|(names, _)| names.contains(&unit.as_str()))
+            .map(|(_, length)| *length)
+            .ok_or_else(|| match unit.is_empty() {
+                true => anyhow::anyhow!("missing unit in duration: {}", duration),
+                false => anyhow::anyhow!("unknown unit: {}", unit),
+            })?;
+        seconds = number
+            .checked_mul(length)
+            .and_then(|s| seconds.checked_add(s))
+            .ok_or_else(invalid)?;
+        rest = tail.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    }
+    if seconds % EPOCH_DURATION_SECONDS != 0 {
+        return Err(anyhow::anyhow!(
+            "{} is not a whole number of {}s epochs",
+            duration,
+            EPOCH_DURATION_SECONDS
+        ));
+    }
+    Ok(seconds / EPOCH_DURATION_SECONDS)
+}
+
+/// Format a number of epochs as a duration, e.g. "2d 4h"
+pub fn format_duration(epochs: ChainEpoch) -> String {
+    let seconds = epochs.unsigned_abs() * EPOCH_DURATION_SECONDS as u64;
+    let parts: Vec<String> = [
+        (SECONDS_IN_DAY as u64, "d"),
+        (SECONDS_IN_HOUR as u64, "h"),
+        (60, "m"),
+        (1, "s"),
+    ]
+    .iter()
+    .scan(seconds, |left, (length, unit)| {
+        let count = *left / length;
+        *left %= length;
+        Some((count, unit))
+    })
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, unit)| format!("{}{}", count, unit))
+    .collect();
+
+    let sign = if epochs < 0 { "-" } else { "" };
+    match parts.is_empty() {
+        true => "0s".to_string(),
+        false => format!("{}{}", sign, parts.join(" ")),
+    }
+}
+
+/// Parse a UTC date, "2024-03-01", "2024-03-01 12:00" or "2024-03-01T12:00:00Z",
+/// into unix time
+pub fn parse_utc(date: &str) -> anyhow::Result<u64> {
+    let invalid = || anyhow::anyhow!("invalid UTC date: {}", date);
+    let trimmed = date.trim();
+    let trimmed = trimmed
+        .strip_suffix('Z')
+        .or_else(|| trimmed.strip_suffix("UTC"))
+        .unwrap_or(trimmed)
+        .trim_end();
+    let (day, time) = trimmed.split_once(['T', ' ']).unwrap_or((trimmed, ""));
+
+    let numbers = |s: &str, sep: char| -> anyhow::Result<Vec<u64>> {
+        s.split(sep)
+            .map(
+                |n| match !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) {
+                    true => n.parse().map_err(|_| invalid()),
+                    false => Err(invalid()),
+                },
+            )
+            .collect()
+    };
+    let (year, month, day) = match numbers(day, '-')?[..] {
+        [year, month, day] => (year, month, day),
+        _ => return Err(invalid()),
+    };
+    let time = time.trim();
+    let (hour, minute, second) = match time.is_empty() {
+        true => (0, 0, 0),
+        false => match numbers(time, ':')?[..] {
+            [hour, minute] => (hour, minute, 0),
+            [hour, minute, second] => (hour, minute, second),
+            _ => return Err(invalid()),
+        },
+    };
+    if !(1970..=9999).contains(&year)
+        || !(1..=12).contains(&month)
+        || day == 0
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return Err(invalid());
+    }
+    let days = days_from_civil(year as i64, month, day) as u64;
+    Ok(days * SECONDS_IN_DAY as u64 + hour * 3600 + minute * 60 + second)
+}
+
+/// Format unix time as a UTC date, e.g. "2020-08-24T22:00:00Z"
+pub fn format_utc(timestamp: u64) -> String {
+    let day = SECONDS_IN_DAY as u64;
+    let (year, month, date) = civil_from_days((timestamp / day) as i64);
+    let seconds = timestamp % day;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        date,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u64, day: u64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Date of a number of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u64, u64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u64;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u64;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[test]
+fn test_chain_clock() {
+    let clock = ChainClock::for_network(ChainType::Mainnet).unwrap();
+    assert_eq!(clock.timestamp(0), 1598306400);
+    assert_eq!(format_utc(clock.timestamp(0)), "2020-08-24T22:00:00Z");
+    assert_eq!(clock.epoch_at(1598306400 + 2880 * 30), 2880);
+    assert_eq!(clock.epoch_at(1598306400 + 59), 1);
+    assert_eq!(clock.epoch_at(1598306400 - 1), -1);
+    assert_eq!(clock.epoch_at(clock.timestamp(2_500_000)), 2_500_000);
+    assert!(ChainClock::for_network(ChainType::Calibnet).is_ok());
+    assert!(ChainClock::for_network(ChainType::Devnet).is_err());
+
+    let devnet = ChainClock::new(1_700_000_000);
+    assert_eq!(devnet.epoch_at(1_700_000_000 + 3600), 120);
+}
+
+#[test]
+fn test_parse_epoch() {
+    assert_eq!(parse_epoch(ChainType::Devnet, " 1234 ").unwrap(), 1234);
+    assert_eq!(
+        parse_epoch(ChainType::Mainnet, "2020-08-25 22:00").unwrap(),
+        2880
+    );
+    assert_eq!(
+        parse_epoch(ChainType::Mainnet, "2020-08-24T22:01:00Z").unwrap(),
+        2
+    );
+    assert!(parse_epoch(ChainType::Devnet, "2020-08-25").is_err());
+    assert!(parse_epoch(ChainType::Mainnet, "tomorrow").is_err());
+}
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("2 days 4h").unwrap(), 2 * 2880 + 4 * 120);
+    assert_eq!(parse_duration("180d").unwrap(), 180 * 2880);
+    assert_eq!(parse_duration("1 year").unwrap(), EPOCHS_IN_YEAR);
+    assert_eq!(parse_duration("1w, 1d").unwrap(), 8 * 2880);
+    assert_eq!(parse_duration("1h30m").unwrap(), 180);
+    assert_eq!(parse_duration("90 seconds").unwrap(), 3);
+    assert_eq!(parse_duration("10 epochs").unwrap(), 10);
+    assert_eq!(parse_duration("1000").unwrap(), 1000);
+    assert_eq!(parse_duration("2 Days 4H").unwrap(), 6240);
+
+    for invalid in ["", "d", "2 fortnights", "2 days 4", "45s", "-1d", "1.5d"] {
+        assert!(parse_duration(invalid).is_err(), "{}", invalid);
+    }
+    assert!(parse_duration("99999999999999999 years").is_err());
+}
+
+#[test]
+fn test_format_duration() {
+    assert_eq!(format_duration(6240), "2d 4h");
+    assert_eq!(format_duration(3), "1m 30s");
+    assert_eq!(format_duration(0), "0s");
+    assert_eq!(format_duration(-120), "-1h");
+    for epochs in [1, 121, 2880, 6240, 1_000_001] {
+        assert_eq!(parse_duration(&format_duration(epochs)).unwrap(), epochs);
+    }
+}
+
+#[test]
+fn test_utc() {
+    assert_eq!(parse_utc("1970-01-01").unwrap(), 0);
+    assert_eq!(parse_utc("2020-08-24T22:00:00Z").unwrap(), 1598306400);
+    assert_eq!(parse_utc("2022-11-01 18:13 UTC").unwrap(), 1667326380);
+    assert_eq!(parse_utc("2024-02-29").unwrap(), 1709164800);
+    assert_eq!(format_utc(1709164800), "2024-02-29T00:00:00Z");
+    assert_eq!(format_utc(4107542399), "2100-02-28T23:59:59Z");
+    assert_eq!(parse_utc("9999-12-31T23:59:59Z").unwrap(), 253402300799);
+    for timestamp in [0, 951782400, 1598306400, 4102444800] {
+        assert_eq!(parse_utc(&format_utc(timestamp)).unwrap(), timestamp);
+    }
+    for invalid in [
+        "2023-02-29",
+        "2024-13-01",
+        "2024-01-01 24:00",
+        "1969-12-31",
+        "2024-1",
+        "2024-01-01T12",
+        "01/02/2024",
+        "10000-01-01",
+        "99999999999999-01-01",
+        "9999999999999999999-01-01",
+    ] {
+        assert!(parse_utc(invalid).is_err(), "{}", invalid);
+    }
+}
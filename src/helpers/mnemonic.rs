@@ -1,9 +1,12 @@
 use bip39::{Mnemonic, Seed};
 // use crypto::{digest::Digest, sha2::Sha256};
-use crypto_wallet_gen::{Bip39Mnemonic, Bip44DerivationPath, MnemonicFactory};
+use crypto_wallet_gen::{
+    Bip39Mnemonic, Bip44DerivationPath, HDPrivKey, MnemonicFactory, Seed as HDSeed,
+};
 use num_bigint::BigUint;
 use num_traits::{FromPrimitive, Num, Pow};
 use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, Zeroizing};
 
 use crate::{
     helpers::accounts::generate_account_from_private, types::WalletType, FlairAccount, FlairPrivate,
@@ -22,8 +25,8 @@ pub fn derive_master_key(seed: &[u8]) -> anyhow::Result<Vec<u8>> {
 
 fn hkdf(ikm: &[u8], salt: &[u8], info: &[u8], okm: &mut [u8]) {
     use hkdf::Hkdf;
-    let hk = Hkdf::<Sha256>::new(Some(&salt[..]), &ikm);
-    hk.expand(&info, okm).unwrap();
+    let hk = Hkdf::<Sha256>::new(Some(salt), ikm);
+    hk.expand(info, okm).unwrap();
 }
 
 pub fn hkdf_mod_r(ikm: &[u8]) -> Vec<u8> {
@@ -43,6 +46,7 @@ pub fn hkdf_mod_r(ikm: &[u8]) -> Vec<u8> {
 
     let mut okm = [0u8; 48];
     hkdf(&ikm, &salt, &[0, 48], &mut okm);
+    ikm.zeroize();
 
     let r = BigUint::from_str_radix(
         "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001",
@@ -50,6 +54,7 @@ pub fn hkdf_mod_r(ikm: &[u8]) -> Vec<u8> {
     )
     .unwrap();
     let out = BigUint::from_bytes_be(okm.as_ref()) % r;
+    okm.zeroize();
 
     out.to_bytes_be()
 }
@@ -88,7 +93,7 @@ fn parent_sk_to_lamport_pk(ikm: &[u8], index: u32) -> Vec<u8> {
     let mut flattened_key = [0u8; OUTPUT_SIZE * 2];
     for i in 0..NUM_DIGESTS * 2 {
         let mut sha256 = Sha256::new();
-        let sha_slice = combined[i].clone();
+        let sha_slice = combined[i];
         // sha256.input(sha_slice);
         // sha256.result(sha_slice);
 
@@ -98,9 +103,13 @@ fn parent_sk_to_lamport_pk(ikm: &[u8], index: u32) -> Vec<u8> {
 
         flattened_key[i * DIGEST_SIZE..(i + 1) * DIGEST_SIZE].clone_from_slice(&result);
     }
+    lamport_0.zeroize();
+    lamport_1.zeroize();
+    combined.zeroize();
+
     let mut sha256 = Sha256::new();
     // sha256.input(&flattened_key);
-    sha256.update(&flattened_key);
+    sha256.update(flattened_key);
     // let cmp_pk: &mut [u8] = &mut [0u8; DIGEST_SIZE];
     // sha256.result(cmp_pk);
     // cmp_pk.to_vec()
@@ -131,12 +140,13 @@ pub fn path_to_node(path: &str) -> anyhow::Result<Vec<u32>> {
     Ok(ret)
 }
 
-pub fn drive_from_path(ikm: &[u8], path: &str) -> anyhow::Result<Vec<u8>> {
+/// child key at `path`, wiped when dropped
+pub fn drive_from_path(ikm: &[u8], path: &str) -> anyhow::Result<Zeroizing<Vec<u8>>> {
     let indexes = path_to_node(path)?;
-    let mut key = ikm.to_vec();
+    let mut key = Zeroizing::new(ikm.to_vec());
 
     for index in indexes {
-        key = derive_child_sk(&key, index);
+        key = Zeroizing::new(derive_child_sk(&key, index));
     }
 
     Ok(key)
@@ -145,11 +155,21 @@ pub fn drive_from_path(ikm: &[u8], path: &str) -> anyhow::Result<Vec<u8>> {
 pub fn gen_account(phrase: &str, password: &str) -> anyhow::Result<FlairAccount> {
     use crypto_wallet_gen::Mnemonic;
     let mnemonic = Bip39Mnemonic::from_phrase(phrase)?;
+    let master_key = mnemonic.to_private_key(password)?;
+    gen_account_from_master_key(&master_key)
+}
+
+/// Secp256k1 account from a raw (BIP39 or scrypt) seed
+pub fn gen_account_from_seed(seed: &[u8]) -> anyhow::Result<FlairAccount> {
+    let master_key = HDPrivKey::new(HDSeed::from_bytes(seed.to_vec()))?;
+    gen_account_from_master_key(&master_key)
+}
+
+fn gen_account_from_master_key(master_key: &HDPrivKey) -> anyhow::Result<FlairAccount> {
     let account_index = 0;
     let change_index: Option<u32> = Some(0);
     let address_index: Option<u32> = Some(0);
 
-    let master_key = mnemonic.to_private_key(password)?;
     let derivation_path = Bip44DerivationPath {
         coin_type: crypto_wallet_gen::CoinType::FIL,
         account: account_index,
@@ -167,17 +187,20 @@ pub fn gen_account(phrase: &str, password: &str) -> anyhow::Result<FlairAccount>
 
 pub fn gen_account_bls(mnemonic: &Mnemonic, password: &str) -> anyhow::Result<FlairAccount> {
     let seed = Seed::new(mnemonic, password);
-    let seed_bytes = seed.as_bytes();
+    gen_account_bls_from_seed(seed.as_bytes())
+}
 
+/// Bls account from a raw (BIP39 or scrypt) seed, using EIP-2333 key derivation
+pub fn gen_account_bls_from_seed(seed_bytes: &[u8]) -> anyhow::Result<FlairAccount> {
     let mut idx = 0;
 
     // add use index until success
     // as eip-2333 shows "more than 54% of keys generated by BIP32 would be invalid"
     loop {
-        let master_key = derive_master_key(seed_bytes)?;
+        let master_key = Zeroizing::new(derive_master_key(seed_bytes)?);
         let key = drive_from_path(&master_key, &format!("m/12381/461/0/{idx}"))?;
 
-        let mut private_key: FlairPrivate = key.into();
+        let mut private_key: FlairPrivate = key.to_vec().into();
         private_key.set_type(WalletType::Bls);
 
         match generate_account_from_private(&WalletType::Bls, &private_key) {
@@ -190,24 +213,38 @@ pub fn gen_account_bls(mnemonic: &Mnemonic, password: &str) -> anyhow::Result<Fl
 #[test]
 fn test_derive_master_key() {
     let phrase = "poverty fury pencil useful catch turn nation select bid fashion need intact";
-    let mnemonic = Mnemonic::from_phrase(&phrase, bip39::Language::English).unwrap();
+    let mnemonic = Mnemonic::from_phrase(phrase, bip39::Language::English).unwrap();
     let seed = Seed::new(&mnemonic, "");
     let seed_bytes = seed.as_bytes();
 
     let master_key = derive_master_key(seed_bytes).unwrap();
     let key = drive_from_path(&master_key, "m/12381/461/0/0").unwrap();
 
-    let hex_key = hex::encode(&key);
+    let hex_key = hex::encode(&*key);
     // according to results from https://iancoleman.io/eip2333/
     assert_eq!(
         hex_key.as_str(),
         "4bfbf5bfbc86ebbcd71d183441d2cebed2759de45401863b3fe1c7293ca1f56f"
     );
 
-    let mut private_key: FlairPrivate = key.into();
+    let mut private_key: FlairPrivate = key.to_vec().into();
     private_key.set_type(WalletType::Bls);
 
     let account = generate_account_from_private(&WalletType::Bls, &private_key).unwrap();
 
     println!("{}", account.display());
 }
+
+#[test]
+fn test_derived_key_is_wiped_on_drop() {
+    let seed: [u8; 32] = rand::random();
+    let master_key = Zeroizing::new(derive_master_key(&seed).unwrap());
+    let key = drive_from_path(&master_key, "m/12381/461/0/0").unwrap();
+
+    let mut secret = [0u8; 16];
+    secret.copy_from_slice(&key[..16]);
+    assert!(!crypto_wallet_gen::wipe::freed_unwiped(
+        &secret,
+        move || drop(key)
+    ));
+}
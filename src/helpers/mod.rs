@@ -1,2 +1,5 @@
 pub mod accounts;
+pub mod entropy;
+pub mod epochs;
 pub mod mnemonic;
+pub mod units;
@@ -0,0 +1,116 @@
+use std::str::FromStr;
+
+use fvm_shared::{bigint::BigInt, econ::TokenAmount};
+
+// FIL amounts as written by users, following Lotus `types.ParseFIL`
+
+const FIL_DECIMALS: usize = 18;
+
+const UNITS: &[(&str, usize)] = &[
+    ("fil", 0),
+    ("millifil", 3),
+    ("microfil", 6),
+    ("nanofil", 9),
+    ("picofil", 12),
+    ("femtofil", 15),
+    ("attofil", 18),
+];
+
+/// Parse an amount like "1.5", "1.5 FIL", "10 nanoFIL" or "100attofil", FIL when no unit is given
+pub fn parse_fil(amount: &str) -> anyhow::Result<TokenAmount> {
+    let amount = amount.trim();
+    let split = amount
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(amount.len());
+    let (number, unit) = amount.split_at(split);
+    let (number, unit) = (number.trim(), unit.trim().to_lowercase());
+
+    let shift = if unit.is_empty() {
+        0
+    } else {
+        UNITS
+            .iter()
+            .find(|(u, _)| *u == unit)
+            .map(|(_, s)| *s)
+            .ok_or_else(|| anyhow::anyhow!("unknown unit: {}", unit))?
+    };
+    let decimals = FIL_DECIMALS - shift;
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() && fraction.is_empty()
+        || !whole.chars().all(|c| c.is_ascii_digit())
+        || !fraction.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(anyhow::anyhow!("invalid amount: {}", amount));
+    }
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > decimals {
+        return Err(anyhow::anyhow!(
+            "{} has more precision than 1 attoFIL",
+            amount
+        ));
+    }
+
+    let digits = format!(
+        "{}{}{}",
+        whole,
+        fraction,
+        "0".repeat(decimals - fraction.len())
+    );
+    let atto =
+        BigInt::from_str(&digits).map_err(|_| anyhow::anyhow!("invalid amount: {}", amount))?;
+    Ok(TokenAmount::from_atto(atto))
+}
+
+/// Format an amount in FIL, e.g. "1.5 FIL"
+pub fn format_fil(amount: &TokenAmount) -> String {
+    let atto = amount.atto();
+    let sign = if atto.sign() == fvm_shared::bigint::Sign::Minus {
+        "-"
+    } else {
+        ""
+    };
+    let digits = atto.magnitude().to_string();
+    let digits = format!("{:0>width$}", digits, width = FIL_DECIMALS + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - FIL_DECIMALS);
+    let fraction = fraction.trim_end_matches('0');
+
+    if fraction.is_empty() {
+        format!("{}{} FIL", sign, whole)
+    } else {
+        format!("{}{}.{} FIL", sign, whole, fraction)
+    }
+}
+
+#[test]
+fn test_parse_fil() {
+    let atto = |a: u64| TokenAmount::from_atto(a);
+    assert_eq!(parse_fil("1").unwrap(), atto(1_000_000_000_000_000_000));
+    assert_eq!(
+        parse_fil("1.5 FIL").unwrap(),
+        atto(1_500_000_000_000_000_000)
+    );
+    assert_eq!(parse_fil(".25fil").unwrap(), atto(250_000_000_000_000_000));
+    assert_eq!(parse_fil("10 nanoFIL").unwrap(), atto(10_000_000_000));
+    assert_eq!(parse_fil("100attofil").unwrap(), atto(100));
+    assert_eq!(parse_fil("0.000000000000000001").unwrap(), atto(1));
+    assert!(parse_fil("0.0000000000000000001").is_err());
+    assert!(parse_fil("1.5 attofil").is_err());
+    assert!(parse_fil("1 BTC").is_err());
+    assert!(parse_fil("-1").is_err());
+    assert!(parse_fil("").is_err());
+}
+
+#[test]
+fn test_format_fil() {
+    assert_eq!(format_fil(&TokenAmount::from_atto(0)), "0 FIL");
+    assert_eq!(
+        format_fil(&TokenAmount::from_atto(1)),
+        "0.000000000000000001 FIL"
+    );
+    assert_eq!(format_fil(&parse_fil("1234.5").unwrap()), "1234.5 FIL");
+    assert_eq!(
+        format_fil(&TokenAmount::from_atto(-5_000_000_000_000_000_000i64)),
+        "-5 FIL"
+    );
+}
@@ -0,0 +1,211 @@
+use cid::Cid;
+use fvm_ipld_encoding::{tuple::*, RawBytes};
+use fvm_shared::{
+    address::{Address, Protocol},
+    econ::TokenAmount,
+    message::Message,
+    MethodNum,
+};
+use serde::Serialize;
+
+use crate::{
+    actors::INIT_ACTOR_ADDR, decode::ActorType, manifest::Manifest, message::MessageBuilder,
+    rpc::client::MessageReceipt,
+};
+
+// init actor Exec, creating actors, as of actors v9
+
+pub const METHOD_EXEC: MethodNum = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct ExecParams {
+    pub code_cid: Cid,
+    pub constructor_params: RawBytes,
+}
+
+/// The new actor, by ID and by its robust f2 address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct ExecReturn {
+    pub id_address: Address,
+    pub robust_address: Address,
+}
+
+/// Only these can be created by accounts, miners are created by the power
+/// actor and the others are singletons
+pub fn can_exec(actor: ActorType) -> bool {
+    matches!(actor, ActorType::Multisig | ActorType::PaymentChannel)
+}
+
+/// Message creating an actor running `code`, funded with `value`
+pub fn exec_message(
+    from: Address,
+    code: Cid,
+    constructor_params: RawBytes,
+    value: TokenAmount,
+) -> anyhow::Result<Message> {
+    let params = ExecParams {
+        code_cid: code,
+        constructor_params,
+    };
+    Ok(MessageBuilder::new(from, INIT_ACTOR_ADDR)
+        .value(value)
+        .method(METHOD_EXEC)
+        .params(RawBytes::serialize(params)?)
+        .build())
+}
+
+/// Message creating a builtin `actor`, its code taken from `manifest`
+pub fn exec_actor_message<P: Serialize>(
+    manifest: &Manifest,
+    actor: ActorType,
+    from: Address,
+    constructor_params: &P,
+    value: TokenAmount,
+) -> anyhow::Result<Message> {
+    if !can_exec(actor) {
+        return Err(anyhow::anyhow!(
+            "the init actor can't create a {} actor",
+            actor.name()
+        ));
+    }
+    exec_message(
+        from,
+        manifest.code(actor)?,
+        RawBytes::serialize(constructor_params)?,
+        value,
+    )
+}
+
+/// Created actor out of the receipt of an Exec message
+pub fn exec_return(receipt: &MessageReceipt) -> anyhow::Result<ExecReturn> {
+    if receipt.exit_code != 0 {
+        return Err(anyhow::anyhow!(
+            "actor creation failed with exit code {}",
+            receipt.exit_code
+        ));
+    }
+    let data = receipt
+        .return_data
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("actor creation returned nothing"))?;
+    let data = base64::decode(data).map_err(|_| anyhow::anyhow!("invalid return data encoding"))?;
+    Ok(fvm_ipld_encoding::from_slice(&data)?)
+}
+
+/// Robust address the chain gives to an actor created by the message of
+/// `origin` with nonce `sequence`. `index` counts the actors the message
+/// created before this one, 0 for the actor of an Exec.
+pub fn predict_robust_address(
+    origin: &Address,
+    sequence: u64,
+    index: u64,
+) -> anyhow::Result<Address> {
+    if !matches!(origin.protocol(), Protocol::Secp256k1 | Protocol::BLS) {
+        return Err(anyhow::anyhow!(
+            "{} is not a key address, the origin is resolved to its key",
+            origin
+        ));
+    }
+    let mut buf = fvm_ipld_encoding::to_vec(origin)?;
+    buf.extend_from_slice(&sequence.to_be_bytes());
+    buf.extend_from_slice(&index.to_be_bytes());
+    Ok(Address::new_actor(&buf))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, str::FromStr};
+
+    use super::*;
+    use crate::multisig::construct::ConstructorParams;
+
+    #[test]
+    fn test_exec_message() {
+        let code: Cid = "bafk2bzaceduf3hayh63jnl4z2knxv7cnrdenoubni22fxersc4octlwpxpmy4"
+            .parse()
+            .unwrap();
+        let manifest = Manifest::new(HashMap::from([("multisig".to_string(), code)]));
+        let constructor = ConstructorParams {
+            signers: vec![Address::new_id(1000), Address::new_id(1001)],
+            num_approvals_threshold: 2,
+            unlock_duration: 0,
+            start_epoch: 0,
+        };
+        let message = exec_actor_message(
+            &manifest,
+            ActorType::Multisig,
+            Address::new_id(1000),
+            &constructor,
+            TokenAmount::from_atto(10),
+        )
+        .unwrap();
+        assert_eq!(message.to, INIT_ACTOR_ADDR);
+        assert_eq!(message.method_num, METHOD_EXEC);
+        assert_eq!(message.value, TokenAmount::from_atto(10));
+        let params: ExecParams = message.params.deserialize().unwrap();
+        assert_eq!(params.code_cid, code);
+        assert_eq!(
+            params.constructor_params,
+            RawBytes::serialize(&constructor).unwrap()
+        );
+
+        for actor in [ActorType::Miner, ActorType::Account, ActorType::Init] {
+            assert!(exec_actor_message(
+                &manifest,
+                actor,
+                Address::new_id(1000),
+                &constructor,
+                TokenAmount::from_atto(0),
+            )
+            .is_err());
+        }
+        // not in the manifest
+        assert!(exec_actor_message(
+            &manifest,
+            ActorType::PaymentChannel,
+            Address::new_id(1000),
+            &constructor,
+            TokenAmount::from_atto(0),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_exec_return() {
+        let created = ExecReturn {
+            id_address: Address::new_id(1234),
+            robust_address: Address::new_actor(b"created"),
+        };
+        let receipt = MessageReceipt {
+            exit_code: 0,
+            return_data: Some(base64::encode(fvm_ipld_encoding::to_vec(&created).unwrap())),
+            gas_used: 0,
+        };
+        assert_eq!(exec_return(&receipt).unwrap(), created);
+        assert!(exec_return(&MessageReceipt {
+            exit_code: 16,
+            ..receipt.clone()
+        })
+        .is_err());
+        assert!(exec_return(&MessageReceipt {
+            return_data: None,
+            ..receipt
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_predict_robust_address() {
+        let origin = Address::from_str("f1jkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi").unwrap();
+        let mut buf = vec![0x55];
+        buf.extend(origin.to_bytes());
+        buf.extend(7u64.to_be_bytes());
+        buf.extend(0u64.to_be_bytes());
+        let predicted = predict_robust_address(&origin, 7, 0).unwrap();
+        assert_eq!(predicted, Address::new_actor(&buf));
+        assert_eq!(predicted.protocol(), Protocol::Actor);
+        assert_ne!(predict_robust_address(&origin, 8, 0).unwrap(), predicted);
+        assert_ne!(predict_robust_address(&origin, 7, 1).unwrap(), predicted);
+        assert!(predict_robust_address(&Address::new_id(1000), 7, 0).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use std::{fmt, str::FromStr};
+
+use blake2b_simd::Params;
+use frc42_dispatch::hasher::hash::{Hasher, MethodResolver};
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::{address::Address, econ::TokenAmount, message::Message, MethodNum};
+use serde::Serialize;
+
+use crate::{
+    decode::{method_number, singleton_type, ActorType},
+    message::MessageBuilder,
+};
+
+// FRC-42 method numbers and messages calling any actor by method name
+
+/// Blake2b-512, the hash FRC-42 method numbers are taken from
+#[derive(Debug, Default)]
+struct Blake2b;
+
+impl Hasher for Blake2b {
+    fn hash(&self, bytes: &[u8]) -> Vec<u8> {
+        Params::new()
+            .hash_length(64)
+            .hash(bytes)
+            .as_bytes()
+            .to_vec()
+    }
+}
+
+/// FRC-42 method number of `name`, 1 for the constructor
+pub fn method_hash(name: &str) -> anyhow::Result<MethodNum> {
+    MethodResolver::new(Blake2b)
+        .method_number(name)
+        .map_err(|e| anyhow::anyhow!("method {}: {}", name, e))
+}
+
+/// Method to call, by number or by name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MethodRef {
+    Number(MethodNum),
+    Name(String),
+}
+
+impl MethodRef {
+    /// Number of the method on an actor of type `actor`. Names of builtin
+    /// methods resolve to their builtin number like Lotus does, others to
+    /// their FRC-42 number.
+    pub fn resolve(&self, actor: Option<ActorType>) -> anyhow::Result<MethodNum> {
+        match self {
+            MethodRef::Number(number) => Ok(*number),
+            MethodRef::Name(name) => match actor.and_then(|actor| method_number(actor, name)) {
+                Some(number) => Ok(number),
+                None => method_hash(name),
+            },
+        }
+    }
+}
+
+impl From<MethodNum> for MethodRef {
+    fn from(number: MethodNum) -> Self {
+        MethodRef::Number(number)
+    }
+}
+
+impl From<&str> for MethodRef {
+    fn from(name: &str) -> Self {
+        MethodRef::Name(name.to_string())
+    }
+}
+
+impl FromStr for MethodRef {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(anyhow::anyhow!("empty method"));
+        }
+        match s.parse() {
+            Ok(number) => Ok(MethodRef::Number(number)),
+            Err(_) => {
+                method_hash(s)?;
+                Ok(MethodRef::Name(s.to_string()))
+            }
+        }
+    }
+}
+
+impl fmt::Display for MethodRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MethodRef::Number(number) => write!(f, "{}", number),
+            MethodRef::Name(name) => f.write_str(name),
+        }
+    }
+}
+
+/// Call of a method of any actor, builtin or user deployed
+#[derive(Debug, Clone)]
+pub struct Invoke {
+    to: Address,
+    method: MethodRef,
+    actor: Option<ActorType>,
+    params: RawBytes,
+    value: TokenAmount,
+}
+
+impl Invoke {
+    pub fn new(to: Address, method: impl Into<MethodRef>) -> Self {
+        Self {
+            to,
+            method: method.into(),
+            actor: None,
+            params: RawBytes::default(),
+            value: TokenAmount::from_atto(0),
+        }
+    }
+
+    /// Builtin type of the receiver, singletons are recognized without it
+    pub fn actor(mut self, actor: ActorType) -> Self {
+        self.actor = Some(actor);
+        self
+    }
+
+    /// CBOR encoded params
+    pub fn params<P: Serialize>(mut self, params: &P) -> anyhow::Result<Self> {
+        self.params = RawBytes::serialize(params)?;
+        Ok(self)
+    }
+
+    pub fn raw_params(mut self, params: RawBytes) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn value(mut self, value: TokenAmount) -> Self {
+        self.value = value;
+        self
+    }
+
+    pub fn method_num(&self) -> anyhow::Result<MethodNum> {
+        self.method
+            .resolve(self.actor.or_else(|| singleton_type(&self.to)))
+    }
+
+    /// Unsigned message, without nonce nor gas
+    pub fn message(&self, from: Address) -> anyhow::Result<Message> {
+        Ok(MessageBuilder::new(from, self.to)
+            .value(self.value.clone())
+            .method(self.method_num()?)
+            .params(self.params.clone())
+            .build())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use fvm_ipld_encoding::tuple::*;
+
+    use super::*;
+    use crate::{datacap, decode::METHOD_UNIVERSAL_RECEIVER_HOOK};
+
+    #[test]
+    fn test_method_hash() {
+        assert_eq!(method_hash("Constructor").unwrap(), 1);
+        assert_eq!(
+            method_hash("Receive").unwrap(),
+            METHOD_UNIVERSAL_RECEIVER_HOOK
+        );
+        assert_eq!(method_hash("InvokeEVM").unwrap(), 3844450837);
+        for (number, name) in [
+            (datacap::METHOD_NAME_EXPORTED, "Name"),
+            (datacap::METHOD_TRANSFER_EXPORTED, "Transfer"),
+            (datacap::METHOD_BALANCE_EXPORTED, "Balance"),
+            (datacap::METHOD_ALLOWANCE_EXPORTED, "Allowance"),
+            (datacap::METHOD_GRANULARITY_EXPORTED, "Granularity"),
+        ] {
+            assert_eq!(method_hash(name).unwrap(), number);
+        }
+        for name in ["", "transfer", "Transfer!", "Tr ansfer", "1Transfer"] {
+            assert!(method_hash(name).is_err(), "{}", name);
+        }
+        assert!(method_hash("_Private").is_ok());
+    }
+
+    #[derive(Serialize_tuple)]
+    struct Params {
+        a: u64,
+        b: String,
+    }
+
+    #[test]
+    fn test_invoke() {
+        let from = Address::new_id(1000);
+        let params = Params {
+            a: 1,
+            b: "x".to_string(),
+        };
+
+        // a user deployed actor
+        let message = Invoke::new(Address::new_id(2000), "Increment")
+            .params(&params)
+            .unwrap()
+            .value(TokenAmount::from_atto(5))
+            .message(from)
+            .unwrap();
+        assert_eq!(message.method_num, method_hash("Increment").unwrap());
+        assert_eq!(message.value, TokenAmount::from_atto(5));
+        assert_eq!(message.params, RawBytes::serialize(&params).unwrap());
+
+        // builtin names get their builtin number, singletons without a type
+        let propose = Invoke::new(Address::new_id(2000), "Propose")
+            .actor(ActorType::Multisig)
+            .message(from)
+            .unwrap();
+        assert_eq!(propose.method_num, 2);
+        assert!(propose.params.is_empty());
+        let balance = Invoke::new(Address::new_id(5), "AddBalance");
+        assert_eq!(balance.method_num().unwrap(), 2);
+        let send = Invoke::new(Address::new_id(2000), 0).message(from).unwrap();
+        assert_eq!(send.method_num, 0);
+
+        assert_eq!("42".parse::<MethodRef>().unwrap(), MethodRef::Number(42));
+        assert_eq!(
+            "Transfer".parse::<MethodRef>().unwrap(),
+            MethodRef::Name("Transfer".to_string())
+        );
+        assert!("transfer".parse::<MethodRef>().is_err());
+    }
+}
@@ -1,18 +1,48 @@
+pub mod actors;
+pub mod car;
+pub mod datacap;
+pub mod decode;
+pub mod evm;
 mod helpers;
+pub mod init;
+pub mod invoke;
+pub mod manifest;
+pub mod market;
 mod message;
+pub mod miner;
 mod models;
-mod multisig;
+pub mod multisig;
+pub mod offline;
+pub mod paych;
+pub mod policy;
+pub mod rpc;
+pub mod signer;
+pub mod state;
 mod types;
+pub mod verifreg;
 
 pub use bip39::{Language, MnemonicType};
 
+pub use helpers::entropy::{parse_coin_flips, parse_dice_rolls};
+pub use helpers::epochs::{
+    format_duration, format_utc, parse_duration, parse_epoch, parse_utc, ChainClock,
+};
+pub use helpers::units::{format_fil, parse_fil};
 use message::get_message_cid;
+pub use message::{
+    cancel_message, min_replacement_premium, replace_message, sign_message, CidJson,
+    MessageBuilder, MessageJson, ReplaceOptions, SignedMessage, SignedMessageJson,
+    DEFAULT_PREMIUM_BUMP_PERCENT,
+};
 pub use models::account::{FlairAccount, FlairAddress, FlairPrivate, FlairPublic};
+pub use models::address::AddressProtocol;
 pub use models::mnemonic::SecretPhrase;
+pub use models::send::{SendOptions, SendReceipt};
+pub use models::wallet::{FlairWallet, Keystore};
 use multisig::approve::approve_multisig_params;
-pub use multisig::construct::create_multisig_params;
+pub use multisig::construct::{create_multisig_message, create_multisig_params};
 use multisig::propose::propose_multisig_params;
-pub use types::{ChainType, WalletType};
+pub use types::{ChainType, SeedDerivation, WalletType};
 
 pub fn multisig_send_propose_params(to: String, value: String) -> String {
     propose_multisig_params(0, None, to, Some(value))
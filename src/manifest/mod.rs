@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+use cid::Cid;
+
+use crate::decode::{parse_actor_type, ActorType};
+
+/// Code CIDs of the builtin actors for a network version, keyed by actor
+/// name, as returned by StateActorCodeCIDs. Needed to create actors through
+/// Init Exec and to tell the type of an actor from its code.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    codes: HashMap<String, Cid>,
+}
+
+impl Manifest {
+    pub fn new(codes: HashMap<String, Cid>) -> Self {
+        Self { codes }
+    }
+
+    /// Code CID of `actor`, an error when the manifest doesn't have it
+    pub fn code(&self, actor: ActorType) -> anyhow::Result<Cid> {
+        self.codes
+            .get(actor.name())
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("no {} actor in the manifest", actor.name()))
+    }
+
+    /// Type of the builtin actor running `code`
+    pub fn actor_type(&self, code: &Cid) -> Option<ActorType> {
+        let (name, _) = self.codes.iter().find(|(_, c)| *c == code)?;
+        parse_actor_type(name).ok()
+    }
+}
+
+#[test]
+fn test_manifest() {
+    let paych: Cid = "bafk2bzaceduf3hayh63jnl4z2knxv7cnrdenoubni22fxersc4octlwpxpmy4"
+        .parse()
+        .unwrap();
+    let manifest = Manifest::new(HashMap::from([("paymentchannel".to_string(), paych)]));
+    assert_eq!(manifest.code(ActorType::PaymentChannel).unwrap(), paych);
+    assert!(manifest.code(ActorType::Multisig).is_err());
+    assert_eq!(manifest.actor_type(&paych), Some(ActorType::PaymentChannel));
+}
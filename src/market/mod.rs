@@ -0,0 +1,372 @@
+use std::fmt;
+
+use cid::Cid;
+use fvm_ipld_encoding::{tuple::*, RawBytes};
+use fvm_shared::{
+    address::Address, clock::ChainEpoch, commcid::cid_to_data_commitment_v1,
+    crypto::signature::Signature, econ::TokenAmount, message::Message, piece::PaddedPieceSize,
+    MethodNum, TOTAL_FILECOIN,
+};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    actors::{Policy, EPOCHS_IN_DAY, STORAGE_MARKET_ACTOR_ADDR},
+    message::MessageBuilder,
+    signer::{verify, MsgMeta, MsgType, Signer},
+};
+
+// storage market actor methods and params, as of actors v9
+
+/// params: the address whose escrow is credited
+pub const METHOD_ADD_BALANCE: MethodNum = 2;
+pub const METHOD_WITHDRAW_BALANCE: MethodNum = 3;
+pub const METHOD_PUBLISH_STORAGE_DEALS: MethodNum = 4;
+
+/// Deal durations the market actor accepts, for any piece size
+pub const DEAL_MIN_DURATION: ChainEpoch = 180 * EPOCHS_IN_DAY;
+pub const DEAL_MAX_DURATION: ChainEpoch = 540 * EPOCHS_IN_DAY;
+/// Longest label accepted, in bytes
+pub const DEAL_MAX_LABEL_SIZE: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct WithdrawBalanceParams {
+    pub provider_or_client: Address,
+    pub amount: TokenAmount,
+}
+
+/// Deposit `amount` to the escrow of `address`, a client or a provider
+pub fn add_balance_message(
+    from: Address,
+    address: Address,
+    amount: TokenAmount,
+) -> anyhow::Result<Message> {
+    if !amount.is_positive() {
+        return Err(anyhow::anyhow!("the deposit must be positive"));
+    }
+    Ok(MessageBuilder::new(from, STORAGE_MARKET_ACTOR_ADDR)
+        .value(amount)
+        .method(METHOD_ADD_BALANCE)
+        .params(RawBytes::serialize(address)?)
+        .build())
+}
+
+/// Withdraw unlocked escrow of `address`, sent by the client or the provider's
+/// owner or worker
+pub fn withdraw_balance_message(
+    from: Address,
+    address: Address,
+    amount: TokenAmount,
+) -> anyhow::Result<Message> {
+    if amount.is_negative() {
+        return Err(anyhow::anyhow!("negative withdrawal requested"));
+    }
+    let params = WithdrawBalanceParams {
+        provider_or_client: address,
+        amount,
+    };
+    Ok(MessageBuilder::new(from, STORAGE_MARKET_ACTOR_ADDR)
+        .method(METHOD_WITHDRAW_BALANCE)
+        .params(RawBytes::serialize(params)?)
+        .build())
+}
+
+/// Deal label, a UTF-8 string or raw bytes on chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Label {
+    String(String),
+    Bytes(Vec<u8>),
+}
+
+impl Label {
+    pub fn len(&self) -> usize {
+        match self {
+            Label::String(s) => s.len(),
+            Label::Bytes(b) => b.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for Label {
+    fn default() -> Self {
+        Label::String(String::new())
+    }
+}
+
+impl Serialize for Label {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Label::String(s) => serializer.serialize_str(s),
+            Label::Bytes(b) => serializer.serialize_bytes(b),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Label {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct LabelVisitor;
+
+        impl<'de> de::Visitor<'de> for LabelVisitor {
+            type Value = Label;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a string or bytes")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Label, E> {
+                Ok(Label::String(v.to_string()))
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Label, E> {
+                Ok(Label::Bytes(v.to_vec()))
+            }
+        }
+
+        deserializer.deserialize_any(LabelVisitor)
+    }
+}
+
+/// Storage deal between a client and a provider, proposed by the client
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct DealProposal {
+    pub piece_cid: Cid,
+    pub piece_size: PaddedPieceSize,
+    pub verified_deal: bool,
+    pub client: Address,
+    pub provider: Address,
+    pub label: Label,
+    pub start_epoch: ChainEpoch,
+    pub end_epoch: ChainEpoch,
+    pub storage_price_per_epoch: TokenAmount,
+    pub provider_collateral: TokenAmount,
+    pub client_collateral: TokenAmount,
+}
+
+impl DealProposal {
+    pub fn duration(&self) -> ChainEpoch {
+        self.end_epoch - self.start_epoch
+    }
+
+    /// Storage fee over the whole deal
+    pub fn total_storage_fee(&self) -> TokenAmount {
+        TokenAmount::from_atto(self.storage_price_per_epoch.atto() * self.duration())
+    }
+
+    /// Escrow the client locks when the deal is published
+    pub fn client_balance_requirement(&self) -> TokenAmount {
+        self.client_collateral.clone() + self.total_storage_fee()
+    }
+
+    /// Paid to the provider at every deal update, once the deal is active
+    pub fn payment_per_update(&self, policy: &Policy) -> TokenAmount {
+        TokenAmount::from_atto(self.storage_price_per_epoch.atto() * policy.deal_updates_interval)
+    }
+
+    /// Checks the market actor runs on publication that don't need the chain
+    /// state, `current_epoch` being the epoch it would land at the earliest.
+    /// Payments happen every `deal_updates_interval` epochs, a deal has to
+    /// last at least one interval to pay anything.
+    pub fn validate(&self, policy: &Policy, current_epoch: ChainEpoch) -> anyhow::Result<()> {
+        cid_to_data_commitment_v1(&self.piece_cid)
+            .map_err(|e| anyhow::anyhow!("invalid piece CID {}: {}", self.piece_cid, e))?;
+        self.piece_size
+            .validate()
+            .map_err(|e| anyhow::anyhow!("invalid piece size {}: {}", self.piece_size.0, e))?;
+        if self.label.len() > DEAL_MAX_LABEL_SIZE {
+            return Err(anyhow::anyhow!(
+                "label of {} bytes, at most {} allowed",
+                self.label.len(),
+                DEAL_MAX_LABEL_SIZE
+            ));
+        }
+        if self.start_epoch <= current_epoch {
+            return Err(anyhow::anyhow!(
+                "deal starts at epoch {}, not after the current epoch {}",
+                self.start_epoch,
+                current_epoch
+            ));
+        }
+        if self.end_epoch <= self.start_epoch {
+            return Err(anyhow::anyhow!("deal ends before it starts"));
+        }
+        let min = DEAL_MIN_DURATION.max(policy.deal_updates_interval);
+        if self.duration() < min || self.duration() > DEAL_MAX_DURATION {
+            return Err(anyhow::anyhow!(
+                "deal lasts {} epochs, not between {} and {}",
+                self.duration(),
+                min,
+                DEAL_MAX_DURATION
+            ));
+        }
+        for (name, amount) in [
+            ("storage price", &self.storage_price_per_epoch),
+            ("provider collateral", &self.provider_collateral),
+            ("client collateral", &self.client_collateral),
+        ] {
+            if amount.is_negative() || amount > &*TOTAL_FILECOIN {
+                return Err(anyhow::anyhow!("{} out of bounds", name));
+            }
+        }
+        if self.total_storage_fee() > *TOTAL_FILECOIN {
+            return Err(anyhow::anyhow!("total storage fee out of bounds"));
+        }
+        Ok(())
+    }
+
+    /// Validate and sign with the key of the client
+    pub fn sign(
+        self,
+        signer: &dyn Signer,
+        policy: &Policy,
+        current_epoch: ChainEpoch,
+    ) -> anyhow::Result<ClientDealProposal> {
+        self.validate(policy, current_epoch)?;
+        let bytes = fvm_ipld_encoding::to_vec(&self)?;
+        let meta = MsgMeta {
+            msg_type: MsgType::DealProposal,
+            extra: bytes.clone(),
+        };
+        let client_signature = signer.sign_bytes(&bytes, &meta)?;
+        Ok(ClientDealProposal {
+            proposal: self,
+            client_signature,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct ClientDealProposal {
+    pub proposal: DealProposal,
+    pub client_signature: Signature,
+}
+
+impl ClientDealProposal {
+    /// Check the signature, `client_key` being the key address of the client
+    pub fn verify(&self, client_key: &Address) -> anyhow::Result<()> {
+        let bytes = fvm_ipld_encoding::to_vec(&self.proposal)?;
+        verify(client_key, &bytes, &self.client_signature)
+            .map_err(|e| anyhow::anyhow!("deal proposal signature: {}", e))
+    }
+}
+
+/// Sent by the provider's worker or a control address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct PublishStorageDealsParams {
+    pub deals: Vec<ClientDealProposal>,
+}
+
+#[cfg(test)]
+mod test {
+    use fvm_shared::{bigint::BigInt, commcid::data_commitment_v1_to_cid};
+
+    use super::*;
+    use crate::{models::account::FlairAccount, types::WalletType};
+
+    fn proposal(client: Address) -> DealProposal {
+        DealProposal {
+            piece_cid: data_commitment_v1_to_cid(&[7; 32]).unwrap(),
+            piece_size: PaddedPieceSize(2048),
+            verified_deal: false,
+            client,
+            provider: Address::new_id(1000),
+            label: Label::String("bafyreib".to_string()),
+            start_epoch: 10_000,
+            end_epoch: 10_000 + DEAL_MIN_DURATION,
+            storage_price_per_epoch: TokenAmount::from_atto(10),
+            provider_collateral: TokenAmount::from_atto(1_000),
+            client_collateral: TokenAmount::from_atto(0),
+        }
+    }
+
+    #[test]
+    fn test_escrow_messages() {
+        let from = Address::new_id(1001);
+        let deposit = add_balance_message(from, from, TokenAmount::from_atto(5)).unwrap();
+        assert_eq!(deposit.to, STORAGE_MARKET_ACTOR_ADDR);
+        assert_eq!(deposit.method_num, METHOD_ADD_BALANCE);
+        assert_eq!(deposit.value, TokenAmount::from_atto(5));
+        assert_eq!(deposit.params.deserialize::<Address>().unwrap(), from);
+        assert!(add_balance_message(from, from, TokenAmount::from_atto(0)).is_err());
+
+        let withdraw = withdraw_balance_message(from, from, TokenAmount::from_atto(5)).unwrap();
+        assert_eq!(withdraw.method_num, METHOD_WITHDRAW_BALANCE);
+        assert!(withdraw.value.is_zero());
+        let params: WithdrawBalanceParams = withdraw.params.deserialize().unwrap();
+        assert_eq!(params.amount, TokenAmount::from_atto(5));
+    }
+
+    #[test]
+    fn test_deal_proposal() {
+        let policy = Policy::mainnet();
+        let account = FlairAccount::generate(WalletType::Secp256k1).unwrap();
+        let client: Address = account.get_address().try_into().unwrap();
+
+        let deal = proposal(client);
+        deal.validate(&policy, 9_000).unwrap();
+        assert_eq!(
+            deal.client_balance_requirement(),
+            TokenAmount::from_atto(BigInt::from(10 * DEAL_MIN_DURATION))
+        );
+        assert_eq!(
+            deal.payment_per_update(&policy),
+            TokenAmount::from_atto(10 * EPOCHS_IN_DAY)
+        );
+        // starting in the past, too short, not a power of two, not a piece CID
+        assert!(deal.validate(&policy, 10_000).is_err());
+        let invalid = [
+            DealProposal {
+                end_epoch: 10_000 + DEAL_MIN_DURATION - 1,
+                ..deal.clone()
+            },
+            DealProposal {
+                piece_size: PaddedPieceSize(2000),
+                ..deal.clone()
+            },
+            DealProposal {
+                piece_cid: "bafk2bzaceduf3hayh63jnl4z2knxv7cnrdenoubni22fxersc4octlwpxpmy4"
+                    .parse()
+                    .unwrap(),
+                ..deal.clone()
+            },
+            DealProposal {
+                label: Label::Bytes(vec![0; DEAL_MAX_LABEL_SIZE + 1]),
+                ..deal.clone()
+            },
+            DealProposal {
+                storage_price_per_epoch: TokenAmount::from_atto(-1),
+                ..deal.clone()
+            },
+        ];
+        for deal in invalid {
+            assert!(deal.validate(&policy, 0).is_err());
+        }
+
+        let signed = deal.clone().sign(&account, &policy, 9_000).unwrap();
+        signed.verify(&client).unwrap();
+        let other = FlairAccount::generate(WalletType::Secp256k1).unwrap();
+        assert!(signed
+            .verify(&other.get_address().try_into().unwrap())
+            .is_err());
+
+        // labels keep their CBOR type
+        let bytes = fvm_ipld_encoding::to_vec(&signed).unwrap();
+        assert_eq!(
+            fvm_ipld_encoding::from_slice::<ClientDealProposal>(&bytes).unwrap(),
+            signed
+        );
+        let labelled = DealProposal {
+            label: Label::Bytes(vec![1, 2]),
+            ..deal
+        };
+        let bytes = fvm_ipld_encoding::to_vec(&labelled).unwrap();
+        assert_eq!(
+            fvm_ipld_encoding::from_slice::<DealProposal>(&bytes)
+                .unwrap()
+                .label,
+            Label::Bytes(vec![1, 2])
+        );
+    }
+}
@@ -1,7 +1,23 @@
 use std::str::FromStr;
 
-use fvm_ipld_encoding::{Cbor, RawBytes};
-use fvm_shared::{address::Address, econ::TokenAmount, message::Message};
+use cid::Cid;
+use fvm_ipld_encoding::{tuple::*, Cbor, RawBytes};
+use fvm_shared::{
+    address::Address,
+    crypto::signature::{Signature, SignatureType},
+    econ::TokenAmount,
+    message::Message,
+    MethodNum,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::signer::{remote::SignatureJson, MsgMeta, Signer};
+
+mod replace;
+pub use replace::{
+    cancel_message, min_replacement_premium, replace_message, ReplaceOptions,
+    DEFAULT_PREMIUM_BUMP_PERCENT,
+};
 
 pub(crate) fn string_to_tokenamount(value: &str) -> TokenAmount {
     let value = fvm_shared::bigint::BigInt::from_str(value).unwrap();
@@ -28,24 +44,254 @@ pub fn get_message_cid(
     let gas_fee_cap = string_to_tokenamount(&gas_fee_cap);
     let gas_premium = string_to_tokenamount(&gas_premium);
 
-    let msg = Message {
-        version: 0,
-        from,
-        to,
-        sequence: nonce,
-        value,
-        method_num: method,
-        params,
-        gas_limit,
-        gas_fee_cap,
-        gas_premium,
-    };
+    let msg = MessageBuilder::new(from, to)
+        .nonce(nonce)
+        .value(value)
+        .method(method)
+        .params(params)
+        .gas(gas_limit, gas_fee_cap, gas_premium)
+        .build();
 
     let cid = msg.cid().unwrap();
     // base64::encode(cid)
     cid.to_string()
 }
 
+/// Builder for unsigned messages
+#[derive(Debug, Clone)]
+pub struct MessageBuilder {
+    message: Message,
+}
+
+impl MessageBuilder {
+    /// plain value transfer with zero gas values, set them before signing
+    pub fn new(from: Address, to: Address) -> Self {
+        Self {
+            message: Message {
+                version: 0,
+                from,
+                to,
+                sequence: 0,
+                value: TokenAmount::from_atto(0),
+                method_num: 0,
+                params: RawBytes::default(),
+                gas_limit: 0,
+                gas_fee_cap: TokenAmount::from_atto(0),
+                gas_premium: TokenAmount::from_atto(0),
+            },
+        }
+    }
+
+    pub fn to(mut self, to: Address) -> Self {
+        self.message.to = to;
+        self
+    }
+
+    pub fn nonce(mut self, nonce: u64) -> Self {
+        self.message.sequence = nonce;
+        self
+    }
+
+    pub fn value(mut self, value: TokenAmount) -> Self {
+        self.message.value = value;
+        self
+    }
+
+    pub fn method(mut self, method: MethodNum) -> Self {
+        self.message.method_num = method;
+        self
+    }
+
+    /// CBOR encoded method parameters, e.g. from the multisig helpers
+    pub fn params(mut self, params: RawBytes) -> Self {
+        self.message.params = params;
+        self
+    }
+
+    pub fn gas(
+        mut self,
+        gas_limit: i64,
+        gas_fee_cap: TokenAmount,
+        gas_premium: TokenAmount,
+    ) -> Self {
+        self.message.gas_limit = gas_limit;
+        self.message.gas_fee_cap = gas_fee_cap;
+        self.message.gas_premium = gas_premium;
+        self
+    }
+
+    pub fn build(self) -> Message {
+        self.message
+    }
+
+    /// build and sign the message
+    pub fn sign(self, signer: &dyn Signer) -> anyhow::Result<SignedMessage> {
+        sign_message(signer, self.build())
+    }
+}
+
+/// start from an existing message, e.g. to replace it
+impl From<Message> for MessageBuilder {
+    fn from(message: Message) -> Self {
+        Self { message }
+    }
+}
+
+/// Message with its signature, as pushed to the mpool
+#[derive(Debug, Clone, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct SignedMessage {
+    pub message: Message,
+    pub signature: Signature,
+}
+
+impl Cbor for SignedMessage {}
+
+impl SignedMessage {
+    /// on chain cid, BLS messages are included unsigned
+    pub fn cid(&self) -> anyhow::Result<Cid> {
+        match self.signature.signature_type() {
+            SignatureType::BLS => Ok(self.message.cid()?),
+            SignatureType::Secp256k1 => Ok(Cbor::cid(self)?),
+        }
+    }
+}
+
+/// Sign a message cid with the signer of its `from` address
+pub fn sign_message(signer: &dyn Signer, message: Message) -> anyhow::Result<SignedMessage> {
+    if signer.address().to_bytes() != message.from.to_bytes() {
+        return Err(anyhow::anyhow!(
+            "signer {} can't sign messages from {}",
+            signer.address(),
+            message.from
+        ));
+    }
+
+    let cid = message.cid()?;
+    let signature = signer.sign_bytes(
+        &cid.to_bytes(),
+        &MsgMeta::chain_msg(message.marshal_cbor()?),
+    )?;
+    Ok(SignedMessage { message, signature })
+}
+
+/// Lotus JSON form of a CID
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CidJson {
+    #[serde(rename = "/")]
+    pub cid: String,
+}
+
+impl From<&Cid> for CidJson {
+    fn from(cid: &Cid) -> Self {
+        Self {
+            cid: cid.to_string(),
+        }
+    }
+}
+
+/// Lotus JSON form of a message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MessageJson {
+    pub version: i64,
+    pub to: String,
+    pub from: String,
+    pub nonce: u64,
+    pub value: String,
+    pub gas_limit: i64,
+    pub gas_fee_cap: String,
+    pub gas_premium: String,
+    pub method: MethodNum,
+    /// base64, null when empty
+    pub params: Option<String>,
+    #[serde(rename = "CID", default, skip_serializing_if = "Option::is_none")]
+    pub cid: Option<CidJson>,
+}
+
+impl From<&Message> for MessageJson {
+    fn from(message: &Message) -> Self {
+        Self {
+            version: message.version,
+            to: message.to.to_string(),
+            from: message.from.to_string(),
+            nonce: message.sequence,
+            value: message.value.atto().to_string(),
+            gas_limit: message.gas_limit,
+            gas_fee_cap: message.gas_fee_cap.atto().to_string(),
+            gas_premium: message.gas_premium.atto().to_string(),
+            method: message.method_num,
+            params: (!message.params.is_empty()).then(|| base64::encode(message.params.bytes())),
+            cid: message.cid().ok().map(|cid| (&cid).into()),
+        }
+    }
+}
+
+impl TryFrom<MessageJson> for Message {
+    type Error = anyhow::Error;
+
+    fn try_from(message: MessageJson) -> anyhow::Result<Self> {
+        let amount = |value: &str| -> anyhow::Result<TokenAmount> {
+            let atto = fvm_shared::bigint::BigInt::from_str(value)
+                .map_err(|_| anyhow::anyhow!("invalid amount: {}", value))?;
+            Ok(TokenAmount::from_atto(atto))
+        };
+        let params = match &message.params {
+            Some(params) => base64::decode(params)?,
+            None => vec![],
+        };
+
+        let parsed = Message {
+            version: message.version,
+            from: Address::from_str(&message.from)?,
+            to: Address::from_str(&message.to)?,
+            sequence: message.nonce,
+            value: amount(&message.value)?,
+            method_num: message.method,
+            params: params.into(),
+            gas_limit: message.gas_limit,
+            gas_fee_cap: amount(&message.gas_fee_cap)?,
+            gas_premium: amount(&message.gas_premium)?,
+        };
+        if let Some(cid) = &message.cid {
+            if parsed.cid()?.to_string() != cid.cid {
+                return Err(anyhow::anyhow!("message doesn't match its CID {}", cid.cid));
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// Lotus JSON form of a signed message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SignedMessageJson {
+    pub message: MessageJson,
+    pub signature: SignatureJson,
+    #[serde(rename = "CID", default, skip_serializing_if = "Option::is_none")]
+    pub cid: Option<CidJson>,
+}
+
+impl From<&SignedMessage> for SignedMessageJson {
+    fn from(signed: &SignedMessage) -> Self {
+        Self {
+            message: (&signed.message).into(),
+            signature: (&signed.signature).into(),
+            cid: signed.cid().ok().map(|cid| (&cid).into()),
+        }
+    }
+}
+
+impl TryFrom<SignedMessageJson> for SignedMessage {
+    type Error = anyhow::Error;
+
+    fn try_from(signed: SignedMessageJson) -> anyhow::Result<Self> {
+        Ok(Self {
+            message: signed.message.try_into()?,
+            signature: signed.signature.try_into()?,
+        })
+    }
+}
+
 #[test]
 fn test_get_message_cid() {
     let cid = get_message_cid(
@@ -65,3 +311,66 @@ fn test_get_message_cid() {
         "bafy2bzacebyorewi7uvs2g3dcsibpgkdjdncbu3xrk3n3ncws76esyywqwtog"
     );
 }
+
+#[test]
+fn test_sign_message() {
+    use crate::{models::account::FlairAccount, types::WalletType};
+
+    let in_bls = "7b2254797065223a22626c73222c22507269766174654b6579223a2270657341657756666d382f6f7a574c736b6f767a7464677a62566d73677657695a70506f346d53367269493d227d";
+    let account = FlairAccount::import(in_bls).unwrap();
+    let from: Address = account.get_address().try_into().unwrap();
+
+    let signed = MessageBuilder::new(from, Address::new_id(3736))
+        .nonce(11)
+        .method(4)
+        .params(base64::decode("ggJA").unwrap().into())
+        .gas(
+            3044346,
+            TokenAmount::from_atto(100348),
+            TokenAmount::from_atto(99294),
+        )
+        .sign(&account)
+        .unwrap();
+    assert_eq!(signed.cid().unwrap(), signed.message.cid().unwrap());
+    assert_eq!(
+        base64::encode(signed.signature.bytes()),
+        account
+            .sign(signed.message.cid().unwrap().to_string())
+            .unwrap()
+    );
+
+    let bytes = signed.marshal_cbor().unwrap();
+    assert_eq!(SignedMessage::unmarshal_cbor(&bytes).unwrap(), signed);
+
+    let secp = FlairAccount::generate(WalletType::Secp256k1).unwrap();
+    let message = MessageBuilder::new(secp.get_address().try_into().unwrap(), from).build();
+    let signed = sign_message(&secp, message.clone()).unwrap();
+    assert_ne!(signed.cid().unwrap(), message.cid().unwrap());
+
+    // wrong signer
+    assert!(sign_message(&account, message).is_err());
+}
+
+#[test]
+fn test_message_json() {
+    let message = MessageBuilder::new(Address::new_id(1000), Address::new_id(1001))
+        .nonce(2)
+        .value(TokenAmount::from_atto(1000))
+        .params(vec![0x80].into())
+        .gas(1000, TokenAmount::from_atto(10), TokenAmount::from_atto(5))
+        .build();
+    let json = serde_json::to_value(MessageJson::from(&message)).unwrap();
+    assert_eq!(json["To"], "f01001");
+    assert_eq!(json["Value"], "1000");
+    assert_eq!(json["Params"], "gA==");
+    assert_eq!(json["CID"]["/"], message.cid().unwrap().to_string());
+
+    let parsed: MessageJson = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(Message::try_from(parsed).unwrap(), message);
+
+    // tampered message
+    let mut tampered = json;
+    tampered["Value"] = "1".into();
+    let parsed: MessageJson = serde_json::from_value(tampered).unwrap();
+    assert!(Message::try_from(parsed).is_err());
+}
@@ -0,0 +1,145 @@
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::{bigint::BigInt, econ::TokenAmount, message::Message};
+
+use super::MessageBuilder;
+use crate::helpers::units::format_fil;
+
+/// premium increase the Lotus mpool requires to replace a pending message
+pub const DEFAULT_PREMIUM_BUMP_PERCENT: u64 = 25;
+
+/// How a pending message gets replaced
+#[derive(Debug, Clone)]
+pub struct ReplaceOptions {
+    /// minimum premium increase, in percent
+    pub bump_percent: u64,
+    /// use this premium if higher than the minimum
+    pub gas_premium: Option<TokenAmount>,
+    /// cap on gas_limit * gas_fee_cap of the replacement
+    pub max_fee: Option<TokenAmount>,
+}
+
+impl Default for ReplaceOptions {
+    fn default() -> Self {
+        Self {
+            bump_percent: DEFAULT_PREMIUM_BUMP_PERCENT,
+            gas_premium: None,
+            max_fee: None,
+        }
+    }
+}
+
+/// Lowest premium accepted for a replacement, computed like Lotus' ComputeRBF
+pub fn min_replacement_premium(premium: &TokenAmount, bump_percent: u64) -> TokenAmount {
+    let premium = premium.atto();
+    TokenAmount::from_atto(premium + premium * BigInt::from(bump_percent) / 100 + 1)
+}
+
+/// Same message and nonce, with a premium high enough to replace `pending`
+pub fn replace_message(pending: &Message, options: &ReplaceOptions) -> anyhow::Result<Message> {
+    let min_premium = min_replacement_premium(&pending.gas_premium, options.bump_percent);
+    let premium = match &options.gas_premium {
+        Some(premium) if *premium > min_premium => premium.clone(),
+        _ => min_premium,
+    };
+
+    let mut fee_cap = if pending.gas_fee_cap > premium {
+        pending.gas_fee_cap.clone()
+    } else {
+        premium.clone()
+    };
+    if let Some(max_fee) = &options.max_fee {
+        let fee = |cap: &TokenAmount| TokenAmount::from_atto(cap.atto() * pending.gas_limit);
+        if fee(&premium) > *max_fee {
+            return Err(anyhow::anyhow!(
+                "replacing the message costs at least {}, above the max fee of {}",
+                format_fil(&fee(&premium)),
+                format_fil(max_fee)
+            ));
+        }
+        if fee(&fee_cap) > *max_fee {
+            fee_cap = TokenAmount::from_atto(max_fee.atto() / pending.gas_limit);
+        }
+    }
+
+    Ok(MessageBuilder::from(pending.clone())
+        .gas(pending.gas_limit, fee_cap, premium)
+        .build())
+}
+
+/// Zero value self-send with the nonce of `pending`, dropping it from the mpool
+pub fn cancel_message(pending: &Message, options: &ReplaceOptions) -> anyhow::Result<Message> {
+    let cancel = MessageBuilder::from(pending.clone())
+        .to(pending.from)
+        .value(TokenAmount::from_atto(0))
+        .method(0)
+        .params(RawBytes::default())
+        .build();
+    replace_message(&cancel, options)
+}
+
+#[test]
+fn test_replace_message() {
+    use fvm_shared::address::Address;
+
+    let pending = MessageBuilder::new(Address::new_id(1000), Address::new_id(1234))
+        .nonce(7)
+        .value(TokenAmount::from_atto(5))
+        .method(2)
+        .params(vec![1, 2].into())
+        .gas(
+            1_000,
+            TokenAmount::from_atto(1_100),
+            TokenAmount::from_atto(1_000),
+        )
+        .build();
+
+    let replaced = replace_message(&pending, &ReplaceOptions::default()).unwrap();
+    assert_eq!(replaced.sequence, 7);
+    assert_eq!(replaced.params, pending.params);
+    assert_eq!(replaced.gas_premium, TokenAmount::from_atto(1_251));
+    assert_eq!(replaced.gas_fee_cap, TokenAmount::from_atto(1_251));
+
+    // configurable bump, explicit premium only when higher
+    let options = ReplaceOptions {
+        bump_percent: 10,
+        gas_premium: Some(TokenAmount::from_atto(1_050)),
+        ..Default::default()
+    };
+    let replaced = replace_message(&pending, &options).unwrap();
+    assert_eq!(replaced.gas_premium, TokenAmount::from_atto(1_101));
+    assert_eq!(replaced.gas_fee_cap, TokenAmount::from_atto(1_101));
+    let options = ReplaceOptions {
+        gas_premium: Some(TokenAmount::from_atto(2_000)),
+        ..Default::default()
+    };
+    let replaced = replace_message(&pending, &options).unwrap();
+    assert_eq!(replaced.gas_premium, TokenAmount::from_atto(2_000));
+
+    // fee cap lowered under the max fee, but never under the premium
+    let pending = MessageBuilder::from(pending)
+        .gas(
+            1_000,
+            TokenAmount::from_atto(5_000),
+            TokenAmount::from_atto(1_000),
+        )
+        .build();
+    let options = ReplaceOptions {
+        max_fee: Some(TokenAmount::from_atto(2_000_000)),
+        ..Default::default()
+    };
+    let replaced = replace_message(&pending, &options).unwrap();
+    assert_eq!(replaced.gas_fee_cap, TokenAmount::from_atto(2_000));
+    let options = ReplaceOptions {
+        max_fee: Some(TokenAmount::from_atto(1_000_000)),
+        ..Default::default()
+    };
+    assert!(replace_message(&pending, &options).is_err());
+
+    let cancel = cancel_message(&pending, &ReplaceOptions::default()).unwrap();
+    assert_eq!(cancel.to, pending.from);
+    assert_eq!(cancel.sequence, 7);
+    assert_eq!(cancel.value, TokenAmount::from_atto(0));
+    assert_eq!(cancel.method_num, 0);
+    assert!(cancel.params.is_empty());
+    assert_eq!(cancel.gas_premium, TokenAmount::from_atto(1_251));
+}
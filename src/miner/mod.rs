@@ -0,0 +1,280 @@
+use fvm_ipld_encoding::{serde_bytes, tuple::*, BytesDe, RawBytes};
+use fvm_shared::{
+    address::{Address, Protocol},
+    clock::ChainEpoch,
+    econ::TokenAmount,
+    message::Message,
+    MethodNum,
+};
+use serde::Serialize;
+
+use crate::{
+    actors::Policy,
+    message::MessageBuilder,
+    multisig::{self, propose::ProposeParams},
+};
+
+// storage miner actor methods and params, as of actors v9
+
+pub const METHOD_CHANGE_WORKER_ADDRESS: MethodNum = 3;
+pub const METHOD_CHANGE_PEER_ID: MethodNum = 4;
+pub const METHOD_WITHDRAW_BALANCE: MethodNum = 16;
+pub const METHOD_CHANGE_MULTIADDRS: MethodNum = 18;
+pub const METHOD_CONFIRM_UPDATE_WORKER_KEY: MethodNum = 21;
+pub const METHOD_CHANGE_OWNER_ADDRESS: MethodNum = 23;
+pub const METHOD_CHANGE_BENEFICIARY: MethodNum = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct ChangeWorkerAddressParams {
+    pub new_worker: Address,
+    pub new_control_addresses: Vec<Address>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct ChangePeerIDParams {
+    #[serde(with = "serde_bytes")]
+    pub new_id: Vec<u8>,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple)]
+pub struct ChangeMultiaddrsParams {
+    pub new_multi_addrs: Vec<BytesDe>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct WithdrawBalanceParams {
+    pub amount_requested: TokenAmount,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct ChangeBeneficiaryParams {
+    pub new_beneficiary: Address,
+    pub new_quota: TokenAmount,
+    pub new_expiration: ChainEpoch,
+}
+
+/// A call to a miner actor, sent by the owner or worker key directly or
+/// proposed to the multisig owning the miner
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinerCall {
+    pub method: MethodNum,
+    pub params: RawBytes,
+}
+
+impl MinerCall {
+    fn new(method: MethodNum, params: impl Serialize) -> anyhow::Result<Self> {
+        Ok(Self {
+            method,
+            params: RawBytes::serialize(params)?,
+        })
+    }
+
+    /// Message from `from` to `miner`, gas and nonce left to set
+    pub fn message(&self, from: Address, miner: Address) -> Message {
+        MessageBuilder::new(from, miner)
+            .method(self.method)
+            .params(self.params.clone())
+            .build()
+    }
+
+    /// Message from a signer of `msig` proposing the call to `miner`
+    pub fn propose(&self, from: Address, msig: Address, miner: Address) -> anyhow::Result<Message> {
+        let propose = ProposeParams {
+            to: miner,
+            value: TokenAmount::from_atto(0),
+            method: self.method,
+            params: self.params.clone(),
+        };
+        Ok(MessageBuilder::new(from, msig)
+            .method(multisig::METHOD_PROPOSE)
+            .params(RawBytes::serialize(propose)?)
+            .build())
+    }
+}
+
+fn check_key_address(role: &str, address: &Address) -> anyhow::Result<()> {
+    // the actor resolves it to an account actor, never behind an f2 address
+    if address.protocol() == Protocol::Actor {
+        return Err(anyhow::anyhow!(
+            "{} {} must be an account, not an actor address",
+            role,
+            address
+        ));
+    }
+    Ok(())
+}
+
+/// Withdraw available balance to the beneficiary, sent by the owner or beneficiary
+pub fn withdraw_balance(amount: TokenAmount) -> anyhow::Result<MinerCall> {
+    if amount.is_negative() {
+        return Err(anyhow::anyhow!("negative withdrawal requested"));
+    }
+    MinerCall::new(
+        METHOD_WITHDRAW_BALANCE,
+        WithdrawBalanceParams {
+            amount_requested: amount,
+        },
+    )
+}
+
+/// Set the control addresses and start a worker key change, sent by the owner.
+/// A new worker takes effect after ConfirmUpdateWorkerKey, once the key change
+/// delay passed.
+pub fn change_worker_address(
+    policy: &Policy,
+    new_worker: Address,
+    new_control_addresses: Vec<Address>,
+) -> anyhow::Result<MinerCall> {
+    check_key_address("worker", &new_worker)?;
+    if new_control_addresses.len() > policy.max_control_addresses {
+        return Err(anyhow::anyhow!(
+            "{} control addresses, at most {} allowed",
+            new_control_addresses.len(),
+            policy.max_control_addresses
+        ));
+    }
+    for control in &new_control_addresses {
+        check_key_address("control address", control)?;
+    }
+    MinerCall::new(
+        METHOD_CHANGE_WORKER_ADDRESS,
+        ChangeWorkerAddressParams {
+            new_worker,
+            new_control_addresses,
+        },
+    )
+}
+
+/// Apply a pending worker key change, sent by the owner
+pub fn confirm_update_worker_key() -> MinerCall {
+    MinerCall {
+        method: METHOD_CONFIRM_UPDATE_WORKER_KEY,
+        params: RawBytes::default(),
+    }
+}
+
+/// Propose a new owner, an account or a multisig, sent by the current owner
+/// then confirmed by the new one sending the same call. Proposing the current
+/// owner cancels a pending change.
+pub fn change_owner_address(new_owner: Address) -> anyhow::Result<MinerCall> {
+    MinerCall::new(METHOD_CHANGE_OWNER_ADDRESS, new_owner)
+}
+
+/// Propose a beneficiary allowed to withdraw up to `new_quota` until
+/// `new_expiration`, sent by the owner and confirmed by the beneficiary
+pub fn change_beneficiary(
+    new_beneficiary: Address,
+    new_quota: TokenAmount,
+    new_expiration: ChainEpoch,
+) -> anyhow::Result<MinerCall> {
+    if new_quota.is_negative() {
+        return Err(anyhow::anyhow!("beneficiary quota can't be negative"));
+    }
+    if new_expiration < 0 {
+        return Err(anyhow::anyhow!("beneficiary expiration can't be negative"));
+    }
+    MinerCall::new(
+        METHOD_CHANGE_BENEFICIARY,
+        ChangeBeneficiaryParams {
+            new_beneficiary,
+            new_quota,
+            new_expiration,
+        },
+    )
+}
+
+/// Set the libp2p peer ID, sent by the owner, worker or a control address
+pub fn change_peer_id(policy: &Policy, new_id: Vec<u8>) -> anyhow::Result<MinerCall> {
+    if new_id.len() > policy.max_peer_id_length {
+        return Err(anyhow::anyhow!(
+            "peer ID of {} bytes, at most {} allowed",
+            new_id.len(),
+            policy.max_peer_id_length
+        ));
+    }
+    MinerCall::new(METHOD_CHANGE_PEER_ID, ChangePeerIDParams { new_id })
+}
+
+/// Set the binary multiaddrs, sent by the owner, worker or a control address
+pub fn change_multiaddrs(policy: &Policy, addrs: Vec<Vec<u8>>) -> anyhow::Result<MinerCall> {
+    if addrs.iter().any(|a| a.is_empty()) {
+        return Err(anyhow::anyhow!("invalid empty multiaddr"));
+    }
+    let size: usize = addrs.iter().map(|a| a.len()).sum();
+    if size > policy.max_multiaddr_data {
+        return Err(anyhow::anyhow!(
+            "{} bytes of multiaddrs, at most {} allowed",
+            size,
+            policy.max_multiaddr_data
+        ));
+    }
+    MinerCall::new(
+        METHOD_CHANGE_MULTIADDRS,
+        ChangeMultiaddrsParams {
+            new_multi_addrs: addrs.into_iter().map(BytesDe).collect(),
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_miner_calls() {
+        let policy = Policy::mainnet();
+        let worker: Address = "f1jkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi".parse().unwrap();
+
+        let call = withdraw_balance(TokenAmount::from_atto(10)).unwrap();
+        let message = call.message(worker, Address::new_id(1000));
+        assert_eq!(message.method_num, METHOD_WITHDRAW_BALANCE);
+        let params: WithdrawBalanceParams = message.params.deserialize().unwrap();
+        assert_eq!(params.amount_requested, TokenAmount::from_atto(10));
+        assert!(withdraw_balance(TokenAmount::from_atto(-1)).is_err());
+
+        let call = change_worker_address(&policy, worker, vec![Address::new_id(1001)]).unwrap();
+        let params: ChangeWorkerAddressParams = call.params.deserialize().unwrap();
+        assert_eq!(params.new_control_addresses, vec![Address::new_id(1001)]);
+        let too_many = vec![Address::new_id(1001); policy.max_control_addresses + 1];
+        assert!(change_worker_address(&policy, worker, too_many).is_err());
+        let actor = Address::new_actor(b"multisig");
+        assert!(change_worker_address(&policy, actor, vec![]).is_err());
+        assert!(confirm_update_worker_key().params.is_empty());
+
+        let call = change_owner_address(Address::new_id(2000)).unwrap();
+        assert_eq!(call.method, METHOD_CHANGE_OWNER_ADDRESS);
+        assert_eq!(
+            call.params.deserialize::<Address>().unwrap(),
+            Address::new_id(2000)
+        );
+
+        assert!(change_peer_id(&policy, vec![0; 128]).is_ok());
+        assert!(change_peer_id(&policy, vec![0; 129]).is_err());
+        assert!(change_multiaddrs(&policy, vec![vec![4, 127, 0, 0, 1]]).is_ok());
+        assert!(change_multiaddrs(&policy, vec![vec![]]).is_err());
+        assert!(change_multiaddrs(&policy, vec![vec![1; 512], vec![1; 513]]).is_err());
+
+        let call =
+            change_beneficiary(Address::new_id(3000), TokenAmount::from_atto(100), 5000).unwrap();
+        let params: ChangeBeneficiaryParams = call.params.deserialize().unwrap();
+        assert_eq!(params.new_expiration, 5000);
+        assert!(change_beneficiary(Address::new_id(3000), TokenAmount::from_atto(-1), 0).is_err());
+    }
+
+    #[test]
+    fn test_miner_call_through_multisig() {
+        let signer: Address = "f1jkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi".parse().unwrap();
+        let call = withdraw_balance(TokenAmount::from_atto(10)).unwrap();
+        let message = call
+            .propose(signer, Address::new_id(2000), Address::new_id(1000))
+            .unwrap();
+        assert_eq!(message.to, Address::new_id(2000));
+        assert_eq!(message.method_num, multisig::METHOD_PROPOSE);
+        assert_eq!(message.value, TokenAmount::from_atto(0));
+
+        let propose: ProposeParams = message.params.deserialize().unwrap();
+        assert_eq!(propose.to, Address::new_id(1000));
+        assert_eq!(propose.method, METHOD_WITHDRAW_BALANCE);
+        assert_eq!(propose.params, call.params);
+    }
+}
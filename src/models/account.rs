@@ -1,8 +1,7 @@
 use blake2b_simd::Params;
-use bls_signatures::{PrivateKey as BlsPrivate, Serialize as BlsSerialize};
-use fvm_shared::address::Address;
-use libsecp256k1::{Message as SecpMessage, SecretKey as SecpPrivate};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use subtle::ConstantTimeEq;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 pub fn blake2b_256(ingest: &[u8]) -> [u8; 32] {
     let digest = Params::new()
@@ -16,36 +15,48 @@ pub fn blake2b_256(ingest: &[u8]) -> [u8; 32] {
     ret
 }
 
+pub use super::address::FlairAddress;
 use crate::{
     helpers::accounts::{
         generate_account, generate_account_from_encoded_string, generate_account_from_private,
         generate_account_from_public,
     },
+    signer::{MsgMeta, Signer},
     types::WalletType,
 };
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+/// Private key, wiped from memory on drop
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
 pub struct FlairPrivate {
-    #[serde(rename = "Type")]
     wallet_type: String,
+    data: Vec<u8>,
+}
+
+/// Lotus KeyInfo, the serialized form of a private key
+#[derive(Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub(crate) struct KeyInfo {
+    #[serde(rename = "Type", default)]
+    pub(crate) wallet_type: Option<String>,
     #[serde(rename = "PrivateKey")]
-    private_key: String,
-    #[serde(skip_serializing)]
-    pub data: Vec<u8>,
+    pub(crate) private_key: String,
 }
 
 impl From<Vec<u8>> for FlairPrivate {
     fn from(d: Vec<u8>) -> Self {
         Self {
             wallet_type: "secp256k1".to_string(),
-            private_key: base64::encode(&d),
             data: d,
         }
     }
 }
 impl FlairPrivate {
-    pub fn to_vec(&self) -> Vec<u8> {
-        self.data.to_vec()
+    /// copy of the raw key, wiped when dropped
+    pub fn to_vec(&self) -> Zeroizing<Vec<u8>> {
+        Zeroizing::new(self.data.to_vec())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
     }
 
     pub fn set_type(&mut self, wallet_type: WalletType) {
@@ -53,6 +64,47 @@ impl FlairPrivate {
     }
 }
 
+impl std::fmt::Debug for FlairPrivate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlairPrivate")
+            .field("wallet_type", &self.wallet_type)
+            .field("data", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Compares keys in constant time
+impl PartialEq for FlairPrivate {
+    fn eq(&self, other: &Self) -> bool {
+        self.wallet_type == other.wallet_type && bool::from(self.data.ct_eq(&other.data))
+    }
+}
+impl Eq for FlairPrivate {}
+
+impl Serialize for FlairPrivate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let key_info = KeyInfo {
+            wallet_type: Some(self.wallet_type.clone()),
+            private_key: base64::encode(&self.data),
+        };
+        key_info.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for FlairPrivate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let key_info = KeyInfo::deserialize(deserializer)?;
+        let data = base64::decode(key_info.private_key.trim()).map_err(serde::de::Error::custom)?;
+        Ok(Self {
+            wallet_type: key_info
+                .wallet_type
+                .clone()
+                .unwrap_or_else(|| WalletType::Secp256k1.to_string()),
+            data,
+        })
+    }
+}
+
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct FlairPublic(Vec<u8>);
 impl From<Vec<u8>> for FlairPublic {
@@ -73,18 +125,10 @@ impl FlairPublic {
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
-pub struct FlairAddress(Address);
-impl From<Address> for FlairAddress {
-    fn from(a: Address) -> Self {
-        Self(a)
-    }
-}
-
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct FlairAccount {
     wallet_type: WalletType,
-    pub private: Option<FlairPrivate>,
+    pub(crate) private: Option<FlairPrivate>,
     public: Option<FlairPublic>,
     address: FlairAddress,
 }
@@ -140,15 +184,15 @@ impl FlairAccount {
 
     /// get wallet address
     pub fn display(&self) -> String {
-        self.address.0.to_string()
+        self.address.to_string()
     }
 
     /// export filecoin format private key
     pub fn export(&self) -> anyhow::Result<String> {
         if let Some(ref prv) = self.private {
-            let key_json = serde_json::to_string(prv)?;
+            let key_json = Zeroizing::new(serde_json::to_string(prv)?);
 
-            let out = hex::encode(key_json);
+            let out = hex::encode(key_json.as_bytes());
             Ok(out)
         } else {
             Err(anyhow::anyhow!("Private Key not found!"))
@@ -162,40 +206,54 @@ impl FlairAccount {
 
     /// sign message
     pub fn sign(&self, cid: String) -> anyhow::Result<String> {
-        match &self.private {
-            Some(private_key) => {
-                let private_key = private_key.to_vec();
-                let cid: cid::Cid = cid.parse()?;
-                let msg = cid.to_bytes().to_vec();
-
-                match self.wallet_type {
-                    WalletType::Bls => {
-                        let priv_key = BlsPrivate::from_bytes(&private_key)?;
-                        let sig = priv_key.sign(&msg).as_bytes();
-                        let out = base64::encode(sig);
-                        Ok(out)
-                    }
-                    WalletType::Secp256k1 => {
-                        let priv_key = SecpPrivate::parse_slice(&private_key)?;
-                        let msg_hash = blake2b_256(&msg);
-                        let message = SecpMessage::parse(&msg_hash);
-                        let (sig, recovery_id) = libsecp256k1::sign(&message, &priv_key);
-                        let mut new_bytes = [0; 65];
-                        new_bytes[..64].copy_from_slice(&sig.serialize());
-                        new_bytes[64] = recovery_id.serialize();
-
-                        let out = new_bytes.to_vec();
-                        let out = base64::encode(out);
-                        Ok(out)
-                    }
-                }
-            }
-            None => Err(anyhow::anyhow!("Not Authourized Account")),
-        }
+        let cid: cid::Cid = cid.parse()?;
+        let signature = self.sign_bytes(&cid.to_bytes(), &MsgMeta::unknown())?;
+        Ok(base64::encode(signature.bytes()))
     }
 }
 
 mod test {
+    #[test]
+    fn test_private_key_hygiene() {
+        let key = vec![0x42u8; 32];
+        let private_key: super::FlairPrivate = key.clone().into();
+        assert!(!format!("{:?}", private_key).contains("66"));
+        assert!(format!("{:?}", private_key).contains("<redacted>"));
+
+        let same: super::FlairPrivate = key.into();
+        let other: super::FlairPrivate = vec![0x43u8; 32].into();
+        assert_eq!(private_key, same);
+        assert_ne!(private_key, other);
+    }
+
+    #[test]
+    fn test_private_key_is_wiped_on_drop() {
+        use crypto_wallet_gen::wipe::freed_unwiped;
+
+        let secret: [u8; 32] = rand::random();
+        let private_key: super::FlairPrivate = secret.to_vec().into();
+        let copy = private_key.to_vec();
+        assert!(!freed_unwiped(&secret, move || drop(private_key)));
+        assert!(!freed_unwiped(&secret, move || drop(copy)));
+
+        let key_info = super::KeyInfo {
+            wallet_type: None,
+            private_key: hex::encode(secret),
+        };
+        assert!(!freed_unwiped(
+            hex::encode(secret).as_bytes(),
+            move || drop(key_info)
+        ));
+    }
+
+    #[test]
+    fn test_private_key_serde() {
+        let json = r#"{"Type":"bls","PrivateKey":"kCKR9iVksaZfrtkQ9y5n2iaXb1ryvm1M7csWEcR11Bg="}"#;
+        let private_key: super::FlairPrivate = serde_json::from_str(json).unwrap();
+        assert_eq!(private_key.as_bytes().len(), 32);
+        assert_eq!(serde_json::to_string(&private_key).unwrap(), json);
+    }
+
     // #[test]
     // fn test_account_from_encoded_bls_private() {
     //     let key = "SWIIbklHT09OwCJ2SnHq57RBLIJc5VtWsM3+SGZ+S7I=";
@@ -0,0 +1,516 @@
+use std::{fmt, str::FromStr};
+
+use data_encoding::BASE32_NOPAD;
+use fvm_shared::{
+    address::{
+        checksum, Address, Network, Protocol, BLS_PUB_LEN, CHECKSUM_HASH_LEN, PAYLOAD_HASH_LEN,
+    },
+    ActorID,
+};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+// Addresses of every protocol in their Lotus string form. fvm_shared 2 has no
+// delegated (f4) addresses, they are kept here until it does.
+
+/// Longest subaddress of a delegated address
+pub const MAX_SUBADDRESS_LEN: usize = 54;
+/// Protocol byte of delegated addresses
+const DELEGATED_PROTOCOL: u8 = 4;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AddressProtocol {
+    ID,
+    Secp256k1,
+    Actor,
+    Bls,
+    /// f4, an address managed by the actor `namespace`
+    Delegated,
+}
+
+impl AddressProtocol {
+    fn from_byte(b: u8) -> Option<Self> {
+        match b {
+            0 => Some(AddressProtocol::ID),
+            1 => Some(AddressProtocol::Secp256k1),
+            2 => Some(AddressProtocol::Actor),
+            3 => Some(AddressProtocol::Bls),
+            DELEGATED_PROTOCOL => Some(AddressProtocol::Delegated),
+            _ => None,
+        }
+    }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            AddressProtocol::ID => 0,
+            AddressProtocol::Secp256k1 => 1,
+            AddressProtocol::Actor => 2,
+            AddressProtocol::Bls => 3,
+            AddressProtocol::Delegated => DELEGATED_PROTOCOL,
+        }
+    }
+}
+
+impl fmt::Display for AddressProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_byte())
+    }
+}
+
+impl From<Protocol> for AddressProtocol {
+    fn from(protocol: Protocol) -> Self {
+        match protocol {
+            Protocol::ID => AddressProtocol::ID,
+            Protocol::Secp256k1 => AddressProtocol::Secp256k1,
+            Protocol::Actor => AddressProtocol::Actor,
+            Protocol::BLS => AddressProtocol::Bls,
+        }
+    }
+}
+
+/// Any Filecoin address, parsed from what a user pastes
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct FlairAddress(Inner);
+
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+enum Inner {
+    Fvm(Address),
+    Delegated {
+        network: Network,
+        namespace: ActorID,
+        length: u8,
+        subaddress: [u8; MAX_SUBADDRESS_LEN],
+    },
+}
+
+impl From<Address> for FlairAddress {
+    fn from(a: Address) -> Self {
+        Self(Inner::Fvm(a))
+    }
+}
+
+impl TryFrom<FlairAddress> for Address {
+    type Error = anyhow::Error;
+
+    fn try_from(a: FlairAddress) -> anyhow::Result<Self> {
+        match a.0 {
+            Inner::Fvm(address) => Ok(address),
+            Inner::Delegated { .. } => Err(anyhow::anyhow!(
+                "delegated address {} is not supported here",
+                a
+            )),
+        }
+    }
+}
+
+impl FlairAddress {
+    /// Delegated address of `subaddress` in the namespace of an address
+    /// manager actor, like the EAM for Ethereum addresses
+    pub fn new_delegated(namespace: ActorID, subaddress: &[u8]) -> anyhow::Result<Self> {
+        if subaddress.len() > MAX_SUBADDRESS_LEN {
+            return Err(anyhow::anyhow!(
+                "subaddress of {} bytes is longer than {}",
+                subaddress.len(),
+                MAX_SUBADDRESS_LEN
+            ));
+        }
+        let mut bytes = [0; MAX_SUBADDRESS_LEN];
+        bytes[..subaddress.len()].copy_from_slice(subaddress);
+        Ok(Self(Inner::Delegated {
+            network: Network::Mainnet,
+            namespace,
+            length: subaddress.len() as u8,
+            subaddress: bytes,
+        }))
+    }
+
+    /// From the protocol byte followed by the payload, as in CBOR
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        match bytes.first() {
+            None => Err(anyhow::anyhow!("empty address")),
+            Some(&DELEGATED_PROTOCOL) => {
+                let (namespace, subaddress) = read_leb128(&bytes[1..])
+                    .ok_or_else(|| anyhow::anyhow!("invalid delegated address namespace"))?;
+                Self::new_delegated(namespace, subaddress)
+            }
+            Some(_) => Ok(Address::from_bytes(bytes)
+                .map_err(|e| anyhow::anyhow!("invalid address bytes: {}", e))?
+                .into()),
+        }
+    }
+
+    pub fn protocol(&self) -> AddressProtocol {
+        match &self.0 {
+            Inner::Fvm(address) => address.protocol().into(),
+            Inner::Delegated { .. } => AddressProtocol::Delegated,
+        }
+    }
+
+    pub fn network(&self) -> Network {
+        match &self.0 {
+            Inner::Fvm(address) => address.network(),
+            Inner::Delegated { network, .. } => *network,
+        }
+    }
+
+    pub fn set_network(&mut self, network: Network) -> &mut Self {
+        match &mut self.0 {
+            Inner::Fvm(address) => {
+                address.set_network(network);
+            }
+            Inner::Delegated { network: n, .. } => *n = network,
+        }
+        self
+    }
+
+    /// Actor ID of an f0 address
+    pub fn id(&self) -> Option<ActorID> {
+        match &self.0 {
+            Inner::Fvm(address) => address.id().ok(),
+            Inner::Delegated { .. } => None,
+        }
+    }
+
+    /// Whether the address is a secp256k1 or BLS key, which can sign
+    pub fn is_key(&self) -> bool {
+        matches!(
+            self.protocol(),
+            AddressProtocol::Secp256k1 | AddressProtocol::Bls
+        )
+    }
+
+    /// Namespace of a delegated address, the ID of its address manager
+    pub fn namespace(&self) -> Option<ActorID> {
+        match &self.0 {
+            Inner::Delegated { namespace, .. } => Some(*namespace),
+            Inner::Fvm(_) => None,
+        }
+    }
+
+    pub fn subaddress(&self) -> Option<&[u8]> {
+        match &self.0 {
+            Inner::Delegated {
+                length, subaddress, ..
+            } => Some(&subaddress[..*length as usize]),
+            Inner::Fvm(_) => None,
+        }
+    }
+
+    /// Payload without the protocol: the LEB128 ID, the key or actor hash,
+    /// or the LEB128 namespace followed by the subaddress
+    pub fn payload_bytes(&self) -> Vec<u8> {
+        match &self.0 {
+            Inner::Fvm(address) => address.payload_bytes(),
+            Inner::Delegated {
+                namespace,
+                length,
+                subaddress,
+                ..
+            } => {
+                let mut bytes = leb128(*namespace);
+                bytes.extend_from_slice(&subaddress[..*length as usize]);
+                bytes
+            }
+        }
+    }
+
+    /// Protocol byte followed by the payload
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.protocol().to_byte()];
+        bytes.extend(self.payload_bytes());
+        bytes
+    }
+}
+
+fn leb128(mut n: u64) -> Vec<u8> {
+    let mut bytes = vec![];
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// A minimal LEB128 number and what follows it
+fn read_leb128(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let mut n: u64 = 0;
+    for (i, byte) in bytes.iter().enumerate().take(10) {
+        let bits = (*byte & 0x7f) as u64;
+        if i == 9 && bits > 1 {
+            return None;
+        }
+        n |= bits << (7 * i);
+        if byte & 0x80 == 0 {
+            if i > 0 && *byte == 0 {
+                return None;
+            }
+            return Some((n, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+fn network_prefix(network: Network) -> &'static str {
+    match network {
+        Network::Mainnet => "f",
+        Network::Testnet => "t",
+    }
+}
+
+fn encode_base32(bytes: &[u8]) -> String {
+    BASE32_NOPAD.encode(bytes).to_lowercase()
+}
+
+/// Payload and checksum of a key, actor or delegated address, checked
+/// against `ingest`, the bytes the checksum covers minus the payload
+fn decode_checked(raw: &str, ingest: &[u8]) -> anyhow::Result<Vec<u8>> {
+    if !raw.chars().all(|c| matches!(c, 'a'..='z' | '2'..='7')) {
+        return Err(anyhow::anyhow!(
+            "payload has characters outside of lower case base32"
+        ));
+    }
+    let mut payload = BASE32_NOPAD
+        .decode(raw.to_uppercase().as_bytes())
+        .map_err(|_| anyhow::anyhow!("payload is not valid base32"))?;
+    if payload.len() < CHECKSUM_HASH_LEN {
+        return Err(anyhow::anyhow!("payload is too short for a checksum"));
+    }
+    let sum = payload.split_off(payload.len() - CHECKSUM_HASH_LEN);
+    let mut ingest = ingest.to_vec();
+    ingest.extend_from_slice(&payload);
+    if checksum(&ingest) != sum {
+        return Err(anyhow::anyhow!(
+            "checksum mismatch, the address is mistyped"
+        ));
+    }
+    Ok(payload)
+}
+
+fn parse(s: &str) -> anyhow::Result<FlairAddress> {
+    let mut chars = s.chars();
+    let network = match chars.next() {
+        Some('f') => Network::Mainnet,
+        Some('t') => Network::Testnet,
+        Some(prefix) => {
+            return Err(anyhow::anyhow!(
+                "unknown network prefix {}, expected f or t",
+                prefix
+            ))
+        }
+        None => return Err(anyhow::anyhow!("empty address")),
+    };
+    let protocol = chars
+        .next()
+        .and_then(|c| c.to_digit(10))
+        .and_then(|d| AddressProtocol::from_byte(d as u8))
+        .ok_or_else(|| anyhow::anyhow!("unknown protocol, expected 0 to 4 after the network"))?;
+    let raw = chars.as_str();
+    let mut address = match protocol {
+        AddressProtocol::ID => {
+            if raw.is_empty() || !raw.chars().all(|c| c.is_ascii_digit()) {
+                return Err(anyhow::anyhow!("actor ID {} is not a number", raw));
+            }
+            let id = raw
+                .parse()
+                .map_err(|_| anyhow::anyhow!("actor ID {} is too large", raw))?;
+            FlairAddress::from(Address::new_id(id))
+        }
+        AddressProtocol::Secp256k1 | AddressProtocol::Actor | AddressProtocol::Bls => {
+            let payload = decode_checked(raw, &[protocol.to_byte()])?;
+            let expected = if protocol == AddressProtocol::Bls {
+                BLS_PUB_LEN
+            } else {
+                PAYLOAD_HASH_LEN
+            };
+            if payload.len() != expected {
+                return Err(anyhow::anyhow!(
+                    "payload of {} bytes, expected {}",
+                    payload.len(),
+                    expected
+                ));
+            }
+            let mut bytes = vec![protocol.to_byte()];
+            bytes.extend(payload);
+            FlairAddress::from_bytes(&bytes)?
+        }
+        AddressProtocol::Delegated => {
+            let (namespace, raw) = raw
+                .split_once('f')
+                .ok_or_else(|| anyhow::anyhow!("no f between the namespace and subaddress"))?;
+            if namespace.is_empty() || !namespace.chars().all(|c| c.is_ascii_digit()) {
+                return Err(anyhow::anyhow!(
+                    "namespace {} is not an actor ID",
+                    namespace
+                ));
+            }
+            let namespace: ActorID = namespace
+                .parse()
+                .map_err(|_| anyhow::anyhow!("namespace {} is too large", namespace))?;
+            let mut ingest = vec![DELEGATED_PROTOCOL];
+            ingest.extend(leb128(namespace));
+            let subaddress = decode_checked(raw, &ingest)?;
+            FlairAddress::new_delegated(namespace, &subaddress)?
+        }
+    };
+    address.set_network(network);
+    Ok(address)
+}
+
+impl FromStr for FlairAddress {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim();
+        parse(s).map_err(|e| anyhow::anyhow!("invalid address {}: {}", s, e))
+    }
+}
+
+impl fmt::Display for FlairAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Inner::Fvm(address) => write!(f, "{}", address),
+            Inner::Delegated {
+                network, namespace, ..
+            } => {
+                let mut data = self.subaddress().unwrap_or_default().to_vec();
+                data.extend(checksum(&self.to_bytes()));
+                write!(
+                    f,
+                    "{}{}{}f{}",
+                    network_prefix(*network),
+                    DELEGATED_PROTOCOL,
+                    namespace,
+                    encode_base32(&data)
+                )
+            }
+        }
+    }
+}
+
+/// Lotus string form
+impl Serialize for FlairAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for FlairAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SECP: &str = "f1jkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi";
+
+    #[test]
+    fn test_parse_protocols() {
+        let id: FlairAddress = "f01234".parse().unwrap();
+        assert_eq!(id.protocol(), AddressProtocol::ID);
+        assert_eq!(id.id(), Some(1234));
+        assert!(!id.is_key());
+        assert_eq!(id.payload_bytes(), vec![0xd2, 0x09]);
+
+        let actor = Address::new_actor(b"actor").to_string();
+        let bls = Address::new_bls(&[7; BLS_PUB_LEN]).unwrap().to_string();
+        for (s, protocol, key) in [
+            (SECP, AddressProtocol::Secp256k1, true),
+            (actor.as_str(), AddressProtocol::Actor, false),
+            (bls.as_str(), AddressProtocol::Bls, true),
+        ] {
+            let address: FlairAddress = s.parse().unwrap();
+            assert_eq!(address.protocol(), protocol);
+            assert_eq!(address.is_key(), key);
+            assert_eq!(address.to_string(), s);
+            assert_eq!(
+                Address::try_from(address).unwrap(),
+                Address::from_str(s).unwrap()
+            );
+            assert_eq!(
+                FlairAddress::from_bytes(&address.to_bytes()).unwrap(),
+                address
+            );
+        }
+
+        let testnet: FlairAddress = "t01234".parse().unwrap();
+        assert_eq!(testnet.network(), Network::Testnet);
+        assert_eq!(testnet.to_string(), "t01234");
+        assert_eq!(" f01234\n".parse::<FlairAddress>().unwrap(), id);
+    }
+
+    #[test]
+    fn test_delegated() {
+        // Ethereum address 0x0000...00ff in the EAM namespace
+        let mut eth = [0u8; 20];
+        eth[19] = 0xff;
+        let address = FlairAddress::new_delegated(10, &eth).unwrap();
+        assert_eq!(address.protocol(), AddressProtocol::Delegated);
+        assert_eq!(address.namespace(), Some(10));
+        assert_eq!(address.subaddress(), Some(&eth[..]));
+        let mut bytes = vec![4, 10];
+        bytes.extend(eth);
+        assert_eq!(address.to_bytes(), bytes);
+        assert_eq!(FlairAddress::from_bytes(&bytes).unwrap(), address);
+
+        let s = address.to_string();
+        assert!(s.starts_with("f410f"), "{}", s);
+        assert_eq!(s.parse::<FlairAddress>().unwrap(), address);
+        assert!(Address::try_from(address).is_err());
+
+        // the checksum covers the namespace
+        let other = s.replacen("f410f", "f411f", 1);
+        assert!(other.parse::<FlairAddress>().is_err());
+
+        let known: FlairAddress = "f410fkkld55ioe7qg24wvt7fu6pbknb56ht7pt4zamxa"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            hex::encode(known.subaddress().unwrap()),
+            "52963ef50e27e06d72d59fcb4f3c2a687be3cfef"
+        );
+
+        // namespaces past one LEB128 byte
+        let wide = FlairAddress::new_delegated(300, b"sub").unwrap();
+        assert_eq!(wide.to_string().parse::<FlairAddress>().unwrap(), wide);
+        assert!(FlairAddress::new_delegated(10, &[0; 55]).is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |s: &str| s.parse::<FlairAddress>().unwrap_err().to_string();
+        assert!(error("").contains("empty"));
+        assert!(error("x01234").contains("network"));
+        assert!(error("f91234").contains("protocol"));
+        assert!(error("f0").contains("not a number"));
+        assert!(error("f0-1").contains("not a number"));
+        assert!(error("f099999999999999999999").contains("too large"));
+        assert!(error("f1a").contains("base32"));
+        assert!(error("f1aa").contains("too short"));
+        assert!(error(&SECP.to_uppercase()).contains("network"));
+        assert!(error("é01234").contains("network"));
+        assert!(error("f1kkzcn2xstealyngllhdjmeygrp6b5amvzhvklbi").contains("checksum"));
+        assert!(error("f1jkzcn2xstealyngllhdjmeygrp6b5amvzhvkl").contains("base32"));
+        assert!(error("f410").contains("no f"));
+        assert!(error("f4xf").contains("namespace"));
+        assert!(error(&SECP.replacen("f1", "f3", 1)).contains("checksum"));
+    }
+
+    #[test]
+    fn test_serde() {
+        let address: FlairAddress = SECP.parse().unwrap();
+        let json = serde_json::to_string(&address).unwrap();
+        assert_eq!(json, format!("\"{}\"", SECP));
+        assert_eq!(
+            serde_json::from_str::<FlairAddress>(&json).unwrap(),
+            address
+        );
+        assert!(serde_json::from_str::<FlairAddress>("\"f1abc\"").is_err());
+    }
+}
@@ -1,40 +1,119 @@
 use bip39::{Language, Mnemonic, MnemonicType};
+use crypto_wallet_gen::{MnemonicFactory, ScryptMnemonic, ScryptParams};
+use rand::RngCore;
+use zeroize::Zeroize;
 
 use crate::{
-    helpers::mnemonic::{gen_account, gen_account_bls},
-    FlairAccount, WalletType,
+    helpers::{
+        entropy::{coin_flips_to_entropy, dice_to_entropy},
+        mnemonic::{
+            gen_account, gen_account_bls, gen_account_bls_from_seed, gen_account_from_seed,
+        },
+    },
+    FlairAccount, SeedDerivation, WalletType,
 };
 
-#[derive(Debug)]
+/// Secret phrase, wiped from memory on drop
 pub struct SecretPhrase {
     mnemonic: Mnemonic,
     phrase: Vec<String>,
+    derivation: SeedDerivation,
+}
+
+impl std::fmt::Debug for SecretPhrase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretPhrase")
+            .field("words", &self.phrase.len())
+            .field("derivation", &self.derivation)
+            .finish()
+    }
+}
+
+// Mnemonic zeroizes itself on drop
+impl Drop for SecretPhrase {
+    fn drop(&mut self) {
+        self.phrase.zeroize();
+    }
 }
 
 impl SecretPhrase {
     /// generate new secret phrase in given language and length
     pub fn generate(language: Language, length: MnemonicType) -> Self {
         let mnemonic = Mnemonic::new(length, language);
-        let phrase = mnemonic
-            .phrase()
-            .split(' ')
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>();
-        Self { mnemonic, phrase }
+        Self::from_mnemonic(mnemonic)
+    }
+
+    /// generate new secret phrase with entropy mixed from the OS, ChaCha20 and CPU jitter generators
+    pub fn generate_secure(language: Language, length: MnemonicType) -> anyhow::Result<Self> {
+        let mut rng = crypto_wallet_gen::secure_rng()?;
+        let mut entropy = vec![0u8; length.entropy_bits() / 8];
+        rng.try_fill_bytes(&mut entropy)?;
+        Self::from_entropy(&entropy, language)
     }
 
     /// generate secret from given phrase(in Englist)
     pub fn generate_from_phrase(phrase: &str) -> anyhow::Result<Self> {
         let mnemonic = Mnemonic::from_phrase(phrase, Language::English)?;
+        Ok(Self::from_mnemonic(mnemonic))
+    }
+
+    /// generate secret from caller supplied entropy (16, 20, 24, 28 or 32 bytes)
+    pub fn from_entropy(entropy: &[u8], language: Language) -> anyhow::Result<Self> {
+        let mnemonic = Mnemonic::from_entropy(entropy, language)?;
+        Ok(Self::from_mnemonic(mnemonic))
+    }
 
+    /// generate secret from rolls (`1..=sides`) of a fair die
+    pub fn from_dice_rolls(
+        rolls: &[u8],
+        sides: u8,
+        language: Language,
+        length: MnemonicType,
+    ) -> anyhow::Result<Self> {
+        let entropy = dice_to_entropy(rolls, sides, length.entropy_bits())?;
+        Self::from_entropy(&entropy, language)
+    }
+
+    /// generate secret from flips (heads = true) of a fair coin
+    pub fn from_coin_flips(
+        flips: &[bool],
+        language: Language,
+        length: MnemonicType,
+    ) -> anyhow::Result<Self> {
+        let entropy = coin_flips_to_entropy(flips, length.entropy_bits())?;
+        Self::from_entropy(&entropy, language)
+    }
+
+    fn from_mnemonic(mnemonic: Mnemonic) -> Self {
         let phrase = mnemonic
             .phrase()
             .split(' ')
             .map(|s| s.to_string())
             .collect::<Vec<String>>();
-        Ok(Self { mnemonic, phrase })
+        Self {
+            mnemonic,
+            phrase,
+            derivation: SeedDerivation::default(),
+        }
+    }
+
+    /// use the given seed derivation for accounts derived from this phrase
+    pub fn with_derivation(mut self, derivation: SeedDerivation) -> Self {
+        self.derivation = derivation;
+        self
+    }
+
+    /// seed derivation used for accounts derived from this phrase
+    pub fn derivation(&self) -> SeedDerivation {
+        self.derivation
     }
 
+    /// entropy the secret phrase encodes
+    pub fn entropy(&self) -> &[u8] {
+        self.mnemonic.entropy()
+    }
+
+    /// copy of the words, the caller is responsible for wiping them
     pub fn phrase_vec(&self) -> Vec<String> {
         self.phrase.clone()
     }
@@ -47,12 +126,34 @@ impl SecretPhrase {
         &self,
         wallet_type: WalletType,
         password: Option<&str>,
+    ) -> anyhow::Result<FlairAccount> {
+        self.derive_account_with_progress(wallet_type, password, &mut |_, _| {})
+    }
+
+    /// derive account, reporting (done, total) steps of a scrypt seed derivation to `progress`
+    pub fn derive_account_with_progress(
+        &self,
+        wallet_type: WalletType,
+        password: Option<&str>,
+        progress: &mut dyn FnMut(u64, u64),
     ) -> anyhow::Result<FlairAccount> {
         let password = password.unwrap_or("");
 
-        let account = match wallet_type {
-            WalletType::Bls => gen_account_bls(&self.mnemonic, password)?,
-            WalletType::Secp256k1 => gen_account(self.phrase(), password)?,
+        let account = match self.derivation {
+            SeedDerivation::Bip39 => match wallet_type {
+                WalletType::Bls => gen_account_bls(&self.mnemonic, password)?,
+                WalletType::Secp256k1 => gen_account(self.phrase(), password)?,
+            },
+            SeedDerivation::Scrypt { log_n, r, p } => {
+                let params = ScryptParams::new(log_n, r, p)
+                    .map_err(|e| anyhow::anyhow!("invalid scrypt params: {}", e))?;
+                let mnemonic = ScryptMnemonic::from_phrase(self.phrase())?;
+                let seed = mnemonic.to_seed(password, &params, progress);
+                match wallet_type {
+                    WalletType::Bls => gen_account_bls_from_seed(seed.to_bytes())?,
+                    WalletType::Secp256k1 => gen_account_from_seed(seed.to_bytes())?,
+                }
+            }
         };
 
         Ok(account)
@@ -68,11 +169,9 @@ fn test_generate_account() {
         .derive_account(WalletType::Secp256k1, None)
         .unwrap();
     println!("Secp256k1 account: {}", account_secp.display());
-    println!("Secp256k1 private key: {}", account_secp.export().unwrap());
 
     let account_bls = secret_phrase.derive_account(WalletType::Bls, None).unwrap();
     println!("Bls account: {}", account_bls.display());
-    println!("Bls private key: {}", account_bls.export().unwrap());
 
     // secret_phrase: betray ribbon visit topple release angle inspire soul private bottom face buddy
     // Secp256k1 account: f1ihd67zlxq6zbvbtnox4xsjn6htps34ys3mfjv6a
@@ -97,3 +196,110 @@ fn test_generate_account_from_phrase() {
     // Secp256k1 account: f1ihd67zlxq6zbvbtnox4xsjn6htps34ys3mfjv6a
     // Bls account: f3uvn2j4mgp2tz3oiinh3jwnepy6zhhsd76ngec6dgwf5e2wdnfl3ylu7bgqa6jkbqv3e4mua6ectzbleflmtq
 }
+
+#[test]
+fn test_entropy_roundtrip() {
+    let secret_phrase = SecretPhrase::generate_from_phrase(
+        "betray ribbon visit topple release angle inspire soul private bottom face buddy",
+    )
+    .unwrap();
+    let entropy = secret_phrase.entropy().to_vec();
+    assert_eq!(entropy.len(), 16);
+
+    let restored = SecretPhrase::from_entropy(&entropy, Language::English).unwrap();
+    assert_eq!(restored.phrase(), secret_phrase.phrase());
+
+    // all zero entropy is the well known "abandon ... about" phrase
+    let zeroes = SecretPhrase::from_entropy(&[0u8; 16], Language::English).unwrap();
+    assert_eq!(
+        zeroes.phrase(),
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"
+    );
+    assert!(SecretPhrase::from_entropy(&[0u8; 15], Language::English).is_err());
+}
+
+#[test]
+fn test_generate_secure() {
+    let secret_phrase =
+        SecretPhrase::generate_secure(Language::English, MnemonicType::Words24).unwrap();
+    assert_eq!(secret_phrase.phrase_vec().len(), 24);
+    assert_eq!(secret_phrase.entropy().len(), 32);
+}
+
+#[test]
+fn test_from_physical_sources() {
+    let rolls: Vec<u8> = (0..100).map(|i| (i % 6 + 1) as u8).collect();
+    let from_dice =
+        SecretPhrase::from_dice_rolls(&rolls, 6, Language::English, MnemonicType::Words24).unwrap();
+    assert_eq!(from_dice.phrase_vec().len(), 24);
+
+    let flips: Vec<bool> = (0..128).map(|i| i % 3 == 0 || i % 3 == 1).collect();
+    assert!(
+        SecretPhrase::from_coin_flips(&flips, Language::English, MnemonicType::Words12).is_err()
+    );
+    let flips: Vec<bool> = (0..128).map(|i| i % 4 < 2).collect();
+    let from_coins =
+        SecretPhrase::from_coin_flips(&flips, Language::English, MnemonicType::Words12).unwrap();
+    assert_eq!(from_coins.entropy(), &[0b1100_1100; 16]);
+}
+
+#[test]
+fn test_scrypt_derivation() {
+    let phrase = "lunch blanket cruise chair question good market allow blue celery little void";
+    let bip39 = SecretPhrase::generate_from_phrase(phrase).unwrap();
+    let scrypt = SecretPhrase::generate_from_phrase(phrase)
+        .unwrap()
+        .with_derivation(SeedDerivation::Scrypt {
+            log_n: 12,
+            r: 1,
+            p: 1,
+        });
+
+    let mut last = (0, 0);
+    let account_secp = scrypt
+        .derive_account_with_progress(WalletType::Secp256k1, None, &mut |done, total| {
+            last = (done, total)
+        })
+        .unwrap();
+    assert_eq!(last, (1 << 13, 1 << 13));
+
+    let bip39_secp = bip39.derive_account(WalletType::Secp256k1, None).unwrap();
+    assert_ne!(account_secp.display(), bip39_secp.display());
+
+    // same phrase and params give the same accounts
+    let again = scrypt.derive_account(WalletType::Secp256k1, None).unwrap();
+    assert_eq!(account_secp.display(), again.display());
+    let account_bls = scrypt.derive_account(WalletType::Bls, None).unwrap();
+    let bip39_bls = bip39.derive_account(WalletType::Bls, None).unwrap();
+    assert_ne!(account_bls.display(), bip39_bls.display());
+
+    let invalid = SecretPhrase::generate_from_phrase(phrase)
+        .unwrap()
+        .with_derivation(SeedDerivation::Scrypt {
+            log_n: 12,
+            r: 0,
+            p: 1,
+        });
+    assert!(invalid.derive_account(WalletType::Bls, None).is_err());
+}
+
+#[test]
+fn test_seed_derivation_serde() {
+    let json = serde_json::to_string(&SeedDerivation::scrypt_default()).unwrap();
+    assert_eq!(json, r#"{"Type":"Scrypt","LogN":21,"R":8,"P":8}"#);
+    let parsed: SeedDerivation = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, SeedDerivation::scrypt_default());
+    assert_eq!(
+        serde_json::to_string(&SeedDerivation::Bip39).unwrap(),
+        r#"{"Type":"Bip39"}"#
+    );
+}
+
+#[test]
+fn test_secret_phrase_debug_is_redacted() {
+    let phrase = "lunch blanket cruise chair question good market allow blue celery little void";
+    let secret_phrase = SecretPhrase::generate_from_phrase(phrase).unwrap();
+    let debug = format!("{:?}", secret_phrase);
+    assert!(!debug.contains("lunch"));
+    assert!(debug.contains("words: 12"));
+}
@@ -1,3 +1,5 @@
 pub mod account;
+pub mod address;
 pub mod mnemonic;
+pub mod send;
 pub mod wallet;
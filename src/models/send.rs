@@ -0,0 +1,397 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use cid::Cid;
+use fvm_shared::{address::Address, econ::TokenAmount, message::Message};
+
+use super::{account::FlairAddress, wallet::FlairWallet};
+use crate::{
+    helpers::units::format_fil,
+    message::{
+        cancel_message, replace_message, sign_message, MessageBuilder, ReplaceOptions,
+        SignedMessage,
+    },
+    rpc::{
+        client::{LotusClient, MsgLookup},
+        RpcTransport,
+    },
+};
+
+// the node's mpool reports pushed messages within a few seconds, a local
+// reservation older than this was dropped or never propagated
+const RESERVATION_TTL: Duration = Duration::from_secs(60);
+
+/// Next nonce of every sender, covering messages pushed by this wallet that
+/// the node's mpool may not report yet
+#[derive(Debug, Default)]
+pub(crate) struct NonceTracker {
+    next: Mutex<HashMap<Vec<u8>, Reservation>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Reservation {
+    next: u64,
+    at: Instant,
+}
+
+impl Clone for NonceTracker {
+    fn clone(&self) -> Self {
+        let next = self
+            .next
+            .lock()
+            .map(|next| next.clone())
+            .unwrap_or_default();
+        Self {
+            next: Mutex::new(next),
+        }
+    }
+}
+
+impl NonceTracker {
+    /// Reserve the next nonce, `chain` being the one reported by the node
+    fn reserve(&self, address: &Address, chain: u64) -> anyhow::Result<u64> {
+        self.reserve_at(address, chain, Instant::now())
+    }
+
+    fn reserve_at(&self, address: &Address, chain: u64, now: Instant) -> anyhow::Result<u64> {
+        let mut next = self
+            .next
+            .lock()
+            .map_err(|_| anyhow::anyhow!("nonce tracker lock poisoned"))?;
+        // local reservations ahead of the node only count while recent,
+        // otherwise the node's nonce is the truth
+        let nonce = match next.get(&address.to_bytes()) {
+            Some(local) if local.next > chain && now.duration_since(local.at) < RESERVATION_TTL => {
+                local.next
+            }
+            _ => chain,
+        };
+        next.insert(
+            address.to_bytes(),
+            Reservation {
+                next: nonce + 1,
+                at: now,
+            },
+        );
+        Ok(nonce)
+    }
+
+    /// Give back a nonce that was never pushed, when it is the last reserved
+    fn release(&self, address: &Address, nonce: u64) -> anyhow::Result<()> {
+        let mut next = self
+            .next
+            .lock()
+            .map_err(|_| anyhow::anyhow!("nonce tracker lock poisoned"))?;
+        if let Some(entry) = next.get_mut(&address.to_bytes()) {
+            if entry.next == nonce + 1 {
+                entry.next = nonce;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SendOptions {
+    /// cap on gas_limit * gas_fee_cap, the node default when None
+    pub max_fee: Option<TokenAmount>,
+    /// wait for the receipt with this many confirmations
+    pub wait: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SendReceipt {
+    pub cid: Cid,
+    pub message: SignedMessage,
+    /// set when the receipt was waited for
+    pub lookup: Option<MsgLookup>,
+}
+
+impl FlairWallet {
+    /// Send FIL from an account of the wallet: resolve the nonce, estimate gas,
+    /// check the balance, sign, push and optionally wait for the receipt
+    pub fn send<T: RpcTransport>(
+        &self,
+        client: &LotusClient<T>,
+        from: &FlairAddress,
+        to: &Address,
+        amount: TokenAmount,
+        options: &SendOptions,
+    ) -> anyhow::Result<SendReceipt> {
+        let from_address = Address::try_from(*from)?;
+        let account = self
+            .get(from)
+            .ok_or_else(|| anyhow::anyhow!("{} is not in the wallet", from_address))?;
+
+        let message = MessageBuilder::new(from_address, *to).value(amount).build();
+        let nonce = self
+            .nonces
+            .reserve(&from_address, client.mpool_get_nonce(&from_address)?)?;
+
+        let pushed = (|| -> anyhow::Result<(Cid, SignedMessage)> {
+            let message = Message {
+                sequence: nonce,
+                ..client.gas_estimate_message_gas(&message, options.max_fee.as_ref())?
+            };
+            check_balance(&client.wallet_balance(&from_address)?, &message)?;
+            let signed = sign_message(account, message)?;
+            Ok((client.mpool_push(&signed)?, signed))
+        })();
+        let (cid, signed) = match pushed {
+            Ok(pushed) => pushed,
+            Err(e) => {
+                self.nonces.release(&from_address, nonce)?;
+                return Err(e);
+            }
+        };
+
+        let lookup = match options.wait {
+            Some(confidence) => Some(client.state_wait_msg(&cid, confidence)?),
+            None => None,
+        };
+        Ok(SendReceipt {
+            cid,
+            message: signed,
+            lookup,
+        })
+    }
+
+    /// Replace a pending message of the wallet with a higher premium
+    pub fn replace<T: RpcTransport>(
+        &self,
+        client: &LotusClient<T>,
+        pending: &Message,
+        options: &ReplaceOptions,
+    ) -> anyhow::Result<SendReceipt> {
+        self.push_replacement(client, replace_message(pending, options)?)
+    }
+
+    /// Drop a pending message of the wallet with a zero value self-send
+    pub fn cancel<T: RpcTransport>(
+        &self,
+        client: &LotusClient<T>,
+        pending: &Message,
+        options: &ReplaceOptions,
+    ) -> anyhow::Result<SendReceipt> {
+        self.push_replacement(client, cancel_message(pending, options)?)
+    }
+
+    fn push_replacement<T: RpcTransport>(
+        &self,
+        client: &LotusClient<T>,
+        message: Message,
+    ) -> anyhow::Result<SendReceipt> {
+        let account = self
+            .get(&message.from.into())
+            .ok_or_else(|| anyhow::anyhow!("{} is not in the wallet", message.from))?;
+        let signed = sign_message(account, message)?;
+        Ok(SendReceipt {
+            cid: client.mpool_push(&signed)?,
+            message: signed,
+            lookup: None,
+        })
+    }
+}
+
+/// The balance has to cover the value and the highest fee the message may pay
+fn check_balance(balance: &TokenAmount, message: &Message) -> anyhow::Result<()> {
+    let max_fee = TokenAmount::from_atto(message.gas_fee_cap.atto() * message.gas_limit);
+    let required = message.value.clone() + max_fee;
+    if *balance < required {
+        return Err(anyhow::anyhow!(
+            "insufficient balance: {} available, {} needed",
+            format_fil(balance),
+            format_fil(&required)
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use fvm_shared::bigint::BigInt;
+
+    use super::*;
+    use crate::{models::account::FlairAccount, rpc::mock::MockLotus, types::WalletType};
+
+    fn fil(amount: u64) -> TokenAmount {
+        TokenAmount::from_atto(BigInt::from(amount) * BigInt::from(10u64.pow(18)))
+    }
+
+    #[test]
+    fn test_send() {
+        let lotus = MockLotus::new();
+        let client = LotusClient::new(&lotus);
+        let mut wallet = FlairWallet::new();
+        let from = wallet.add(FlairAccount::generate(WalletType::Secp256k1).unwrap());
+        lotus.add_account(from.try_into().unwrap(), fil(10));
+        let to = Address::new_id(1234);
+
+        // sends before the receipts come back get consecutive nonces
+        let options = SendOptions::default();
+        let first = wallet.send(&client, &from, &to, fil(1), &options).unwrap();
+        let second = wallet.send(&client, &from, &to, fil(2), &options).unwrap();
+        assert_eq!(first.message.message.sequence, 0);
+        assert_eq!(second.message.message.sequence, 1);
+        assert!(first.lookup.is_none());
+        assert_eq!(lotus.pending().len(), 2);
+
+        let waited = SendOptions {
+            wait: Some(1),
+            ..Default::default()
+        };
+        let third = wallet.send(&client, &from, &to, fil(3), &waited).unwrap();
+        assert_eq!(third.message.message.sequence, 2);
+        assert!(third.lookup.unwrap().success());
+        assert_eq!(client.wallet_balance(&to).unwrap(), fil(6));
+
+        // value + max fee above the balance, the nonce is given back
+        let err = wallet
+            .send(&client, &from, &to, fil(4), &options)
+            .unwrap_err();
+        assert!(err.to_string().contains("insufficient balance"));
+        let fourth = wallet.send(&client, &from, &to, fil(3), &options).unwrap();
+        assert_eq!(fourth.message.message.sequence, 3);
+
+        let unknown = FlairAccount::generate(WalletType::Bls)
+            .unwrap()
+            .get_address();
+        assert!(wallet
+            .send(&client, &unknown, &to, fil(1), &options)
+            .is_err());
+    }
+
+    #[test]
+    fn test_send_concurrent() {
+        let lotus = MockLotus::new();
+        let mut wallet = FlairWallet::new();
+        let from = wallet.add(FlairAccount::generate(WalletType::Bls).unwrap());
+        lotus.add_account(from.try_into().unwrap(), fil(100));
+
+        let mut nonces: Vec<u64> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    s.spawn(|| {
+                        let client = LotusClient::new(&lotus);
+                        wallet
+                            .send(
+                                &client,
+                                &from,
+                                &Address::new_id(1234),
+                                fil(1),
+                                &SendOptions::default(),
+                            )
+                            .unwrap()
+                            .message
+                            .message
+                            .sequence
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        nonces.sort();
+        assert_eq!(nonces, (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_replace_stuck_message() {
+        let lotus = MockLotus::new();
+        let client = LotusClient::new(&lotus);
+        let mut wallet = FlairWallet::new();
+        let from = wallet.add(FlairAccount::generate(WalletType::Secp256k1).unwrap());
+        lotus.add_account(from.try_into().unwrap(), fil(10));
+        let to = Address::new_id(1234);
+
+        let stuck = wallet
+            .send(&client, &from, &to, fil(1), &SendOptions::default())
+            .unwrap();
+        let pending = client.chain_get_message(&stuck.cid).unwrap();
+        assert_eq!(pending, stuck.message.message);
+
+        // the mpool refuses a bump under 25%
+        let low = ReplaceOptions {
+            bump_percent: 10,
+            ..Default::default()
+        };
+        assert!(wallet.replace(&client, &pending, &low).is_err());
+
+        let replaced = wallet
+            .replace(&client, &pending, &ReplaceOptions::default())
+            .unwrap();
+        assert_eq!(replaced.message.message.sequence, pending.sequence);
+        assert_eq!(lotus.pending(), vec![replaced.message.clone()]);
+
+        let cancelled = wallet
+            .cancel(
+                &client,
+                &replaced.message.message,
+                &ReplaceOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(lotus.pending(), vec![cancelled.message.clone()]);
+
+        // waiting on the stuck message finds the message that replaced it
+        let lookup = client.state_wait_msg(&stuck.cid, 1).unwrap();
+        assert_eq!(lookup.message.cid, cancelled.cid.to_string());
+        assert_eq!(client.wallet_balance(&to).unwrap(), fil(0));
+        assert_eq!(
+            client.wallet_balance(&from.try_into().unwrap()).unwrap(),
+            fil(10)
+        );
+    }
+
+    #[test]
+    fn test_nonce_tracker() {
+        let tracker = NonceTracker::default();
+        let address = Address::new_id(1);
+        assert_eq!(tracker.reserve(&address, 5).unwrap(), 5);
+        // the node doesn't see the pushed message yet
+        assert_eq!(tracker.reserve(&address, 5).unwrap(), 6);
+        tracker.release(&address, 6).unwrap();
+        assert_eq!(tracker.reserve(&address, 5).unwrap(), 6);
+        // the node is ahead, e.g. messages sent from elsewhere
+        assert_eq!(tracker.reserve(&address, 10).unwrap(), 10);
+    }
+
+    #[test]
+    fn test_nonce_tracker_resyncs() {
+        let tracker = NonceTracker::default();
+        let address = Address::new_id(1);
+        let start = Instant::now();
+        assert_eq!(tracker.reserve_at(&address, 5, start).unwrap(), 5);
+        assert_eq!(tracker.reserve_at(&address, 5, start).unwrap(), 6);
+        // the reserved messages never reached the mpool, the node still
+        // reports 5 once the reservations are stale
+        let later = start + RESERVATION_TTL;
+        assert_eq!(tracker.reserve_at(&address, 5, later).unwrap(), 5);
+        assert_eq!(tracker.reserve_at(&address, 5, later).unwrap(), 6);
+    }
+
+    #[test]
+    fn test_wallet_eq_ignores_nonces() {
+        let lotus = MockLotus::new();
+        let client = LotusClient::new(&lotus);
+        let mut wallet = FlairWallet::new();
+        let from = wallet.add(FlairAccount::generate(WalletType::Secp256k1).unwrap());
+        lotus.add_account(from.try_into().unwrap(), fil(10));
+        let copy = wallet.clone();
+        wallet
+            .send(
+                &client,
+                &from,
+                &Address::new_id(1234),
+                fil(1),
+                &SendOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(wallet, copy);
+
+        let mut other = copy.clone();
+        other.add(FlairAccount::generate(WalletType::Bls).unwrap());
+        assert_ne!(wallet, other);
+    }
+}
@@ -1,28 +1,222 @@
-use std::collections::VecDeque;
+use std::{
+    collections::VecDeque,
+    fs,
+    path::{Path, PathBuf},
+};
 
-use super::account::FlairAddress;
+use fvm_shared::address::Network;
+use zeroize::Zeroizing;
 
-#[derive(Clone, PartialEq, Debug, Eq)]
+use super::{
+    account::{FlairAccount, FlairAddress},
+    send::NonceTracker,
+};
+use crate::helpers::accounts::parse_private_key_string;
+
+#[derive(Clone, Debug)]
 pub struct FlairWallet {
-    accounts: VecDeque<FlairAddress>,
+    accounts: VecDeque<FlairAccount>,
+    pub(crate) nonces: NonceTracker,
+}
+
+// the nonce tracker is a cache, it doesn't make two wallets different
+impl PartialEq for FlairWallet {
+    fn eq(&self, other: &Self) -> bool {
+        self.accounts == other.accounts
+    }
 }
 
+impl Eq for FlairWallet {}
+
 impl Default for FlairWallet {
     fn default() -> Self {
         Self::new()
     }
 }
 
+// addresses are compared without their network prefix
+fn same_address(a: &FlairAddress, b: &FlairAddress) -> bool {
+    a.to_bytes() == b.to_bytes()
+}
+
 impl FlairWallet {
     /// Create an empty wallet
     pub fn new() -> Self {
         Self {
             accounts: VecDeque::new(),
+            nonces: NonceTracker::default(),
         }
     }
 
     /// List all addresses stored in the wallet
-    pub fn _list(&self) -> anyhow::Result<Vec<FlairAddress>> {
-        Ok(self.accounts.to_owned().into())
+    pub fn list(&self) -> anyhow::Result<Vec<FlairAddress>> {
+        Ok(self.accounts.iter().map(|a| a.get_address()).collect())
+    }
+
+    /// Add an account, replacing the one with the same address
+    pub fn add(&mut self, account: FlairAccount) -> FlairAddress {
+        let address = account.get_address();
+        match self
+            .accounts
+            .iter_mut()
+            .find(|a| same_address(&a.get_address(), &address))
+        {
+            Some(existing) => *existing = account,
+            None => self.accounts.push_back(account),
+        }
+        address
+    }
+
+    pub fn has(&self, address: &FlairAddress) -> bool {
+        self.get(address).is_some()
+    }
+
+    pub fn get(&self, address: &FlairAddress) -> Option<&FlairAccount> {
+        self.accounts
+            .iter()
+            .find(|a| same_address(&a.get_address(), address))
+    }
+
+    /// Remove an account from the wallet
+    pub fn remove(&mut self, address: &FlairAddress) -> Option<FlairAccount> {
+        let index = self
+            .accounts
+            .iter()
+            .position(|a| same_address(&a.get_address(), address))?;
+        self.accounts.remove(index)
+    }
+}
+
+/// Directory of private keys, one `wallet-<address>` file holding the
+/// Lotus KeyInfo JSON per account.
+#[derive(Clone, Debug)]
+pub struct Keystore {
+    dir: PathBuf,
+}
+
+impl Keystore {
+    pub fn open(dir: impl AsRef<Path>) -> anyhow::Result<Self> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+        })
+    }
+
+    // files are named after the mainnet form, so both forms find the key
+    fn path(&self, address: &FlairAddress) -> PathBuf {
+        let mut address = *address;
+        address.set_network(Network::Mainnet);
+        self.dir.join(format!("wallet-{}", address))
     }
+
+    /// Load every key of the keystore
+    pub fn load(&self) -> anyhow::Result<FlairWallet> {
+        let mut wallet = FlairWallet::new();
+        let mut entries: Vec<PathBuf> = fs::read_dir(&self.dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with("wallet-"))
+            })
+            .collect();
+        entries.sort();
+
+        for path in entries {
+            let key_info = Zeroizing::new(fs::read_to_string(&path)?);
+            let account = parse_private_key_string(&key_info)
+                .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+            wallet.add(account);
+        }
+        Ok(wallet)
+    }
+
+    /// Write the private key of an account, readable by the owner only
+    pub fn put(&self, account: &FlairAccount) -> anyhow::Result<()> {
+        let private = account
+            .private
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Private Key not found!"))?;
+        let key_info = Zeroizing::new(serde_json::to_string(private)?);
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(self.path(&account.get_address()))?;
+        std::io::Write::write_all(&mut file, key_info.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn delete(&self, address: &FlairAddress) -> anyhow::Result<()> {
+        let path = self.path(address);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_wallet_accounts() {
+    use crate::types::WalletType;
+
+    let mut wallet = FlairWallet::new();
+    let secp = FlairAccount::generate(WalletType::Secp256k1).unwrap();
+    let bls = FlairAccount::generate(WalletType::Bls).unwrap();
+    wallet.add(secp.clone());
+    wallet.add(bls.clone());
+    wallet.add(secp.clone());
+    assert_eq!(
+        wallet.list().unwrap(),
+        vec![secp.get_address(), bls.get_address()]
+    );
+
+    // testnet form of the same address
+    let testnet: FlairAddress = secp.display().replacen('f', "t", 1).parse().unwrap();
+    assert!(wallet.has(&testnet));
+    assert_eq!(wallet.remove(&testnet), Some(secp));
+    assert_eq!(wallet.list().unwrap(), vec![bls.get_address()]);
+}
+
+#[test]
+fn test_keystore() {
+    use crate::types::WalletType;
+
+    let dir = std::env::temp_dir().join(format!("fil-wallet-keystore-{}", std::process::id()));
+    let keystore = Keystore::open(&dir).unwrap();
+    let secp = FlairAccount::generate(WalletType::Secp256k1).unwrap();
+    let bls = FlairAccount::generate(WalletType::Bls).unwrap();
+    keystore.put(&secp).unwrap();
+    keystore.put(&bls).unwrap();
+
+    let wallet = keystore.load().unwrap();
+    assert_eq!(wallet.get(&secp.get_address()), Some(&secp));
+    assert_eq!(wallet.get(&bls.get_address()), Some(&bls));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(keystore.path(&secp.get_address()))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    keystore.delete(&secp.get_address()).unwrap();
+    assert_eq!(
+        keystore.load().unwrap().list().unwrap(),
+        vec![bls.get_address()]
+    );
+
+    // a calibnet node deletes by the testnet form
+    let testnet: FlairAddress = bls.display().replacen('f', "t", 1).parse().unwrap();
+    keystore.delete(&testnet).unwrap();
+    assert!(!keystore.path(&bls.get_address()).exists());
+    assert!(keystore.load().unwrap().list().unwrap().is_empty());
+    fs::remove_dir_all(dir).unwrap();
 }
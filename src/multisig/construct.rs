@@ -1,13 +1,8 @@
 use std::str::FromStr;
 
-use cid::Cid;
 use fvm_ipld_encoding::tuple::*;
-use fvm_ipld_encoding::RawBytes;
-#[derive(Serialize_tuple, Deserialize_tuple)]
-pub struct ExecParams {
-    pub code_cid: Cid,
-    pub constructor_params: RawBytes,
-}
+
+pub use crate::init::ExecParams;
 
 /// Constructor parameters for multisig actor.
 #[derive(Serialize_tuple, Deserialize_tuple)]
@@ -19,9 +14,31 @@ pub struct ConstructorParams {
     pub start_epoch: ChainEpoch,
 }
 
-use fvm_shared::{address::Address, clock::ChainEpoch};
+use cid::Cid;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::{address::Address, clock::ChainEpoch, econ::TokenAmount, message::Message};
+
+/// Message creating a multisig wallet through the init actor, `code` is the
+/// multisig code CID of the network (see `Manifest::code`)
+pub fn create_multisig_message(
+    from: Address,
+    code: Cid,
+    signers: Vec<Address>,
+    threshold: u64,
+    unlock_duration: ChainEpoch,
+    start_epoch: ChainEpoch,
+    value: TokenAmount,
+) -> anyhow::Result<Message> {
+    let params = ConstructorParams {
+        signers,
+        num_approvals_threshold: threshold,
+        unlock_duration,
+        start_epoch,
+    };
+    crate::init::exec_message(from, code, RawBytes::serialize(params)?, value)
+}
 
-// return params string
+// return params string, with the code CID of an early mainnet multisig actor
 pub fn create_multisig_params(
     addresses: Vec<String>,
     threshold: u64,
@@ -1,3 +1,17 @@
+use fvm_shared::MethodNum;
+
 pub(crate) mod approve;
 pub(crate) mod construct;
 pub(crate) mod propose;
+pub(crate) mod signers;
+
+// multisig actor methods, as of actors v9
+
+pub const METHOD_PROPOSE: MethodNum = 2;
+pub const METHOD_APPROVE: MethodNum = 3;
+pub const METHOD_CANCEL: MethodNum = 4;
+pub const METHOD_ADD_SIGNER: MethodNum = 5;
+pub const METHOD_REMOVE_SIGNER: MethodNum = 6;
+pub const METHOD_SWAP_SIGNER: MethodNum = 7;
+pub const METHOD_CHANGE_THRESHOLD: MethodNum = 8;
+pub const METHOD_LOCK_BALANCE: MethodNum = 9;
@@ -0,0 +1,32 @@
+use fvm_ipld_encoding::tuple::*;
+use fvm_shared::{address::Address, clock::ChainEpoch, econ::TokenAmount};
+
+#[derive(Serialize_tuple, Deserialize_tuple)]
+pub struct AddSignerParams {
+    pub signer: Address,
+    pub increase: bool,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple)]
+pub struct RemoveSignerParams {
+    pub signer: Address,
+    pub decrease: bool,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple)]
+pub struct SwapSignerParams {
+    pub from: Address,
+    pub to: Address,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple)]
+pub struct ChangeNumApprovalsThresholdParams {
+    pub new_threshold: u64,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple)]
+pub struct LockBalanceParams {
+    pub start_epoch: ChainEpoch,
+    pub unlock_duration: ChainEpoch,
+    pub amount: TokenAmount,
+}
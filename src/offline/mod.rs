@@ -0,0 +1,449 @@
+pub mod qr;
+
+use std::str::FromStr;
+
+use fvm_ipld_encoding::Cbor;
+use fvm_shared::{address::Address, bigint::BigInt, econ::TokenAmount, message::Message};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actors::INIT_ACTOR_ADDR,
+    helpers::units::format_fil,
+    init,
+    message::{sign_message, MessageJson, SignedMessage, SignedMessageJson},
+    multisig::{
+        self,
+        approve::TxnIDParams,
+        construct::{ConstructorParams, ExecParams},
+        propose::ProposeParams,
+    },
+    signer::{verify, Signer},
+    types::ChainType,
+};
+
+// Files moved between an online machine, which knows nonces and gas, and an
+// air-gapped one holding the keys. The signer never trusts the summary it is
+// given: it is rebuilt from the message and must match.
+
+pub const TX_FILE_VERSION: u32 = 1;
+
+/// types of the QR frames
+pub const QR_UNSIGNED: &str = "FIL-TX";
+pub const QR_SIGNED: &str = "FIL-SIGNED-TX";
+
+/// What a message does to a multisig wallet, checked against its params
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "Action")]
+pub enum MultisigContext {
+    Create {
+        #[serde(rename = "Signers")]
+        signers: Vec<String>,
+        #[serde(rename = "Threshold")]
+        threshold: u64,
+    },
+    Propose {
+        #[serde(rename = "Msig")]
+        msig: String,
+        #[serde(rename = "To")]
+        to: String,
+        /// attoFIL
+        #[serde(rename = "Value")]
+        value: String,
+    },
+    Approve {
+        #[serde(rename = "Msig")]
+        msig: String,
+        #[serde(rename = "TxnId")]
+        txn_id: i64,
+    },
+}
+
+fn same_address(a: &Address, b: &Address) -> bool {
+    a.to_bytes() == b.to_bytes()
+}
+
+fn parse_address(address: &str) -> anyhow::Result<Address> {
+    Address::from_str(address).map_err(|e| anyhow::anyhow!("invalid address {}: {}", address, e))
+}
+
+impl MultisigContext {
+    /// Fail unless the message does what the context says
+    pub fn check(&self, message: &Message) -> anyhow::Result<()> {
+        let mismatch = || anyhow::anyhow!("message doesn't match its multisig context");
+        match self {
+            MultisigContext::Create { signers, threshold } => {
+                if !same_address(&message.to, &INIT_ACTOR_ADDR)
+                    || message.method_num != init::METHOD_EXEC
+                {
+                    return Err(mismatch());
+                }
+                let exec: ExecParams = fvm_ipld_encoding::from_slice(message.params.bytes())?;
+                let params: ConstructorParams =
+                    fvm_ipld_encoding::from_slice(exec.constructor_params.bytes())?;
+                let expected = signers
+                    .iter()
+                    .map(|s| parse_address(s))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                if params.num_approvals_threshold != *threshold
+                    || params.signers.len() != expected.len()
+                    || !params
+                        .signers
+                        .iter()
+                        .zip(&expected)
+                        .all(|(a, b)| same_address(a, b))
+                {
+                    return Err(mismatch());
+                }
+            }
+            MultisigContext::Propose { msig, to, value } => {
+                if !same_address(&message.to, &parse_address(msig)?)
+                    || message.method_num != multisig::METHOD_PROPOSE
+                {
+                    return Err(mismatch());
+                }
+                let params: ProposeParams = fvm_ipld_encoding::from_slice(message.params.bytes())?;
+                if !same_address(&params.to, &parse_address(to)?)
+                    || params.value.atto().to_string() != *value
+                {
+                    return Err(mismatch());
+                }
+            }
+            MultisigContext::Approve { msig, txn_id } => {
+                if !same_address(&message.to, &parse_address(msig)?)
+                    || message.method_num != multisig::METHOD_APPROVE
+                {
+                    return Err(mismatch());
+                }
+                let params: TxnIDParams = fvm_ipld_encoding::from_slice(message.params.bytes())?;
+                if params.id.0 != *txn_id {
+                    return Err(mismatch());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Human readable description of a message
+pub fn summarize(
+    network: ChainType,
+    message: &Message,
+    multisig: Option<&MultisigContext>,
+) -> String {
+    let show = |address: &Address| {
+        let mut address = *address;
+        address.set_network(network.network());
+        address.to_string()
+    };
+    let show_str = |address: &str| {
+        parse_address(address)
+            .map(|a| show(&a))
+            .unwrap_or_else(|_| address.to_string())
+    };
+    let max_fee = TokenAmount::from_atto(message.gas_fee_cap.atto() * message.gas_limit);
+
+    let action = match multisig {
+        Some(MultisigContext::Create { signers, threshold }) => format!(
+            "create a {} of {} multisig holding {}",
+            threshold,
+            signers.len(),
+            format_fil(&message.value)
+        ),
+        Some(MultisigContext::Propose { msig, to, value }) => format!(
+            "propose sending {} from multisig {} to {}",
+            value
+                .parse::<BigInt>()
+                .map(|v| format_fil(&TokenAmount::from_atto(v)))
+                .unwrap_or_else(|_| value.clone()),
+            show_str(msig),
+            show_str(to)
+        ),
+        Some(MultisigContext::Approve { msig, txn_id }) => {
+            format!(
+                "approve transaction {} of multisig {}",
+                txn_id,
+                show_str(msig)
+            )
+        }
+        None if message.method_num == 0 => format!(
+            "send {} to {}",
+            format_fil(&message.value),
+            show(&message.to)
+        ),
+        None => format!(
+            "call method {} of {} with {}",
+            message.method_num,
+            show(&message.to),
+            format_fil(&message.value)
+        ),
+    };
+    format!(
+        "{} {}, nonce {}, max fee {}",
+        show(&message.from),
+        action,
+        message.sequence,
+        format_fil(&max_fee)
+    )
+}
+
+/// Message waiting to be signed offline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct UnsignedTransaction {
+    pub version: u32,
+    pub network: ChainType,
+    pub summary: String,
+    pub message: MessageJson,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multisig: Option<MultisigContext>,
+}
+
+impl UnsignedTransaction {
+    pub fn new(
+        network: ChainType,
+        message: &Message,
+        multisig: Option<MultisigContext>,
+    ) -> anyhow::Result<Self> {
+        if let Some(context) = &multisig {
+            context.check(message)?;
+        }
+        let mut message = message.clone();
+        message.from.set_network(network.network());
+        message.to.set_network(network.network());
+        Ok(Self {
+            version: TX_FILE_VERSION,
+            network,
+            summary: summarize(network, &message, multisig.as_ref()),
+            message: (&message).into(),
+            multisig,
+        })
+    }
+
+    /// Parse the message, checking the file is consistent
+    pub fn message(&self) -> anyhow::Result<Message> {
+        if self.version != TX_FILE_VERSION {
+            return Err(anyhow::anyhow!(
+                "unsupported transaction file version {}",
+                self.version
+            ));
+        }
+        let message = Message::try_from(self.message.clone())?;
+        for address in [&message.from, &message.to] {
+            if address.network() != self.network.network() {
+                return Err(anyhow::anyhow!(
+                    "{} is not a {:?} address",
+                    address,
+                    self.network
+                ));
+            }
+        }
+        if let Some(context) = &self.multisig {
+            context.check(&message)?;
+        }
+        if summarize(self.network, &message, self.multisig.as_ref()) != self.summary {
+            return Err(anyhow::anyhow!("summary doesn't describe the message"));
+        }
+        Ok(message)
+    }
+
+    /// Sign on the offline machine
+    pub fn sign(&self, signer: &dyn Signer) -> anyhow::Result<SignedTransaction> {
+        let signed = sign_message(signer, self.message()?)?;
+        Ok(SignedTransaction {
+            version: TX_FILE_VERSION,
+            network: self.network,
+            summary: self.summary.clone(),
+            signed_message: (&signed).into(),
+        })
+    }
+
+    pub fn to_qr(&self, fragment_len: usize) -> anyhow::Result<Vec<String>> {
+        qr::encode(QR_UNSIGNED, &serde_json::to_vec(self)?, fragment_len)
+    }
+}
+
+/// Signed message coming back from the offline machine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SignedTransaction {
+    pub version: u32,
+    pub network: ChainType,
+    pub summary: String,
+    pub signed_message: SignedMessageJson,
+}
+
+impl SignedTransaction {
+    /// Parse the signed message, checking its signature
+    pub fn signed_message(&self) -> anyhow::Result<SignedMessage> {
+        if self.version != TX_FILE_VERSION {
+            return Err(anyhow::anyhow!(
+                "unsupported transaction file version {}",
+                self.version
+            ));
+        }
+        let signed = SignedMessage::try_from(self.signed_message.clone())?;
+        let cid = signed.message.cid()?;
+        verify(&signed.message.from, &cid.to_bytes(), &signed.signature)?;
+        Ok(signed)
+    }
+
+    pub fn to_qr(&self, fragment_len: usize) -> anyhow::Result<Vec<String>> {
+        qr::encode(QR_SIGNED, &serde_json::to_vec(self)?, fragment_len)
+    }
+}
+
+/// Transaction file read back, as JSON or as QR frames one per line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionFile {
+    Unsigned(UnsignedTransaction),
+    Signed(SignedTransaction),
+}
+
+impl FromStr for TransactionFile {
+    type Err = anyhow::Error;
+
+    fn from_str(content: &str) -> anyhow::Result<Self> {
+        let content = content.trim();
+        if content.to_uppercase().starts_with(qr::PREFIX) {
+            let (kind, payload) = qr::decode(content.lines().filter(|l| !l.trim().is_empty()))?;
+            return match kind.as_str() {
+                QR_UNSIGNED => Ok(Self::Unsigned(serde_json::from_slice(&payload)?)),
+                QR_SIGNED => Ok(Self::Signed(serde_json::from_slice(&payload)?)),
+                _ => Err(anyhow::anyhow!("unexpected frame type {}", kind)),
+            };
+        }
+
+        let value: serde_json::Value = serde_json::from_str(content)?;
+        if value.get("SignedMessage").is_some() {
+            Ok(Self::Signed(serde_json::from_value(value)?))
+        } else {
+            Ok(Self::Unsigned(serde_json::from_value(value)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use fvm_shared::address::Network;
+
+    use super::*;
+    use crate::{
+        message::MessageBuilder, models::account::FlairAccount, multisig_approve_params,
+        multisig_send_propose_params, types::WalletType,
+    };
+
+    fn testnet(address: Address) -> Address {
+        let mut address = address;
+        address.set_network(Network::Testnet);
+        address
+    }
+
+    #[test]
+    fn test_offline_signing() {
+        let account = FlairAccount::generate(WalletType::Secp256k1).unwrap();
+        let from = testnet(account.get_address().try_into().unwrap());
+        let message = MessageBuilder::new(from, testnet(Address::new_id(1234)))
+            .nonce(7)
+            .value(TokenAmount::from_atto(1_500_000_000_000_000_000u64))
+            .gas(
+                1_000_000,
+                TokenAmount::from_atto(100_000),
+                TokenAmount::from_atto(1_000),
+            )
+            .build();
+
+        let unsigned = UnsignedTransaction::new(ChainType::Calibnet, &message, None).unwrap();
+        assert!(unsigned
+            .summary
+            .ends_with("send 1.5 FIL to t01234, nonce 7, max fee 0.0000001 FIL"));
+
+        // through QR frames to the offline machine and back
+        let frames = unsigned.to_qr(120).unwrap();
+        assert!(frames.len() > 1);
+        let received: TransactionFile = frames.join("\n").parse().unwrap();
+        assert_eq!(received, TransactionFile::Unsigned(unsigned.clone()));
+
+        let signed = unsigned.sign(&account).unwrap();
+        let json = serde_json::to_string(&signed).unwrap();
+        let returned = match json.parse::<TransactionFile>().unwrap() {
+            TransactionFile::Signed(signed) => signed,
+            _ => panic!("expected a signed transaction"),
+        };
+        let signed_message = returned.signed_message().unwrap();
+        assert_eq!(signed_message.message, message);
+
+        // tampered files are refused
+        let mut tampered = unsigned.clone();
+        tampered.summary = tampered.summary.replace("1.5 FIL", "0.15 FIL");
+        assert!(tampered.sign(&account).is_err());
+
+        let mut tampered = unsigned.clone();
+        tampered.network = ChainType::Mainnet;
+        assert!(tampered.message().is_err());
+
+        let mut tampered = returned;
+        tampered.signed_message.message.nonce = 8;
+        tampered.signed_message.message.cid = None;
+        assert!(tampered.signed_message().is_err());
+
+        // not the key of the sender
+        let other = FlairAccount::generate(WalletType::Bls).unwrap();
+        assert!(unsigned.sign(&other).is_err());
+    }
+
+    #[test]
+    fn test_offline_multisig_context() {
+        let account = FlairAccount::generate(WalletType::Bls).unwrap();
+        let from: Address = account.get_address().try_into().unwrap();
+        let msig = Address::new_id(2000);
+        let to = Address::new_id(1234);
+
+        let propose = MessageBuilder::new(from, msig)
+            .method(multisig::METHOD_PROPOSE)
+            .params(
+                base64::decode(multisig_send_propose_params(
+                    to.to_string(),
+                    "10000000000000000000".to_string(),
+                ))
+                .unwrap()
+                .into(),
+            )
+            .build();
+        let context = MultisigContext::Propose {
+            msig: msig.to_string(),
+            to: to.to_string(),
+            value: "10000000000000000000".to_string(),
+        };
+        let unsigned =
+            UnsignedTransaction::new(ChainType::Mainnet, &propose, Some(context)).unwrap();
+        assert!(unsigned
+            .summary
+            .contains("propose sending 10 FIL from multisig f02000 to f01234"));
+        unsigned.sign(&account).unwrap();
+
+        // the context has to describe the params
+        let mut lying = unsigned.clone();
+        lying.multisig = Some(MultisigContext::Propose {
+            msig: msig.to_string(),
+            to: to.to_string(),
+            value: "1".to_string(),
+        });
+        lying.summary = summarize(ChainType::Mainnet, &propose, lying.multisig.as_ref());
+        assert!(lying.sign(&account).is_err());
+
+        let approve = MessageBuilder::new(from, msig)
+            .method(multisig::METHOD_APPROVE)
+            .params(base64::decode(multisig_approve_params(4)).unwrap().into())
+            .build();
+        let context = MultisigContext::Approve {
+            msig: msig.to_string(),
+            txn_id: 4,
+        };
+        UnsignedTransaction::new(ChainType::Mainnet, &approve, Some(context)).unwrap();
+        let context = MultisigContext::Approve {
+            msig: msig.to_string(),
+            txn_id: 5,
+        };
+        assert!(UnsignedTransaction::new(ChainType::Mainnet, &approve, Some(context)).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+
+use data_encoding::BASE32_NOPAD;
+
+// Multi-part frames for moving data through QR codes:
+// `FILQR:<TYPE>/<seq>-<total>/<checksum>/<data>`, or `FILQR:<TYPE>/<data>`
+// when everything fits in one frame. This is our own format, not Blockchain
+// Commons UR. Data is base32 and everything is upper case, so frames fit the
+// dense QR alphanumeric mode.
+
+/// scheme every frame starts with
+pub const PREFIX: &str = "FILQR:";
+
+/// default data characters per frame, small enough for a phone camera
+pub const DEFAULT_FRAGMENT_LEN: usize = 200;
+
+fn checksum(payload: &[u8]) -> String {
+    hex::encode_upper(
+        blake2b_simd::Params::new()
+            .hash_length(4)
+            .hash(payload)
+            .as_bytes(),
+    )
+}
+
+/// Split `payload` into frames of at most `fragment_len` data characters
+pub fn encode(kind: &str, payload: &[u8], fragment_len: usize) -> anyhow::Result<Vec<String>> {
+    let kind = kind.to_uppercase();
+    if kind.is_empty()
+        || !kind
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return Err(anyhow::anyhow!("invalid frame type: {}", kind));
+    }
+    // 8 base32 characters hold 5 bytes, keep fragments on that boundary
+    let fragment_len = fragment_len / 8 * 8;
+    if fragment_len == 0 {
+        return Err(anyhow::anyhow!("fragment length must be at least 8"));
+    }
+
+    let data = BASE32_NOPAD.encode(payload);
+    if data.len() <= fragment_len {
+        return Ok(vec![format!("{}{}/{}", PREFIX, kind, data)]);
+    }
+    let fragments: Vec<&str> = data
+        .as_bytes()
+        .chunks(fragment_len)
+        .map(|c| std::str::from_utf8(c).unwrap())
+        .collect();
+    let check = checksum(payload);
+    Ok(fragments
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            format!(
+                "{}{}/{}-{}/{}/{}",
+                PREFIX,
+                kind,
+                i + 1,
+                fragments.len(),
+                check,
+                f
+            )
+        })
+        .collect())
+}
+
+/// Collects frames in any order, repeated frames are ignored
+#[derive(Debug, Default)]
+pub struct Decoder {
+    kind: Option<String>,
+    total: usize,
+    check: String,
+    fragments: BTreeMap<usize, String>,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a scanned frame
+    pub fn receive(&mut self, frame: &str) -> anyhow::Result<()> {
+        let frame = frame.trim().to_uppercase();
+        let rest = frame
+            .strip_prefix(PREFIX)
+            .ok_or_else(|| anyhow::anyhow!("not a QR frame"))?;
+        let parts: Vec<&str> = rest.split('/').collect();
+        let (kind, seq, total, check, data) = match parts[..] {
+            [kind, data] => (kind, 1, 1, String::new(), data),
+            [kind, seq, check, data] => {
+                let (seq, total) = seq
+                    .split_once('-')
+                    .ok_or_else(|| anyhow::anyhow!("invalid frame sequence: {}", seq))?;
+                let (seq, total): (usize, usize) = (seq.parse()?, total.parse()?);
+                if seq == 0 || seq > total {
+                    return Err(anyhow::anyhow!("invalid frame sequence: {}-{}", seq, total));
+                }
+                (kind, seq, total, check.to_string(), data)
+            }
+            _ => return Err(anyhow::anyhow!("malformed QR frame")),
+        };
+
+        match &self.kind {
+            None => {
+                self.kind = Some(kind.to_string());
+                self.total = total;
+                self.check = check;
+            }
+            Some(t) if *t != kind || self.total != total || self.check != check => {
+                return Err(anyhow::anyhow!("frame belongs to another transfer"));
+            }
+            Some(_) => {}
+        }
+        self.fragments.insert(seq, data.to_string());
+        Ok(())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.total > 0 && self.fragments.len() == self.total
+    }
+
+    /// received and expected number of frames
+    pub fn progress(&self) -> (usize, usize) {
+        (self.fragments.len(), self.total)
+    }
+
+    /// frame type and payload, once every frame has been received
+    pub fn result(&self) -> anyhow::Result<(String, Vec<u8>)> {
+        if !self.is_complete() {
+            let (received, total) = self.progress();
+            return Err(anyhow::anyhow!("received {} of {} frames", received, total));
+        }
+        let data: String = self.fragments.values().map(String::as_str).collect();
+        let payload = BASE32_NOPAD
+            .decode(data.as_bytes())
+            .map_err(|e| anyhow::anyhow!("invalid frame data: {}", e))?;
+        if self.total > 1 && checksum(&payload) != self.check {
+            return Err(anyhow::anyhow!("frame checksum mismatch"));
+        }
+        Ok((self.kind.clone().unwrap_or_default(), payload))
+    }
+}
+
+/// Decode every frame of a single transfer
+pub fn decode<'a>(frames: impl IntoIterator<Item = &'a str>) -> anyhow::Result<(String, Vec<u8>)> {
+    let mut decoder = Decoder::new();
+    for frame in frames {
+        decoder.receive(frame)?;
+    }
+    decoder.result()
+}
+
+#[test]
+fn test_qr_frames() {
+    let payload: Vec<u8> = (0..=255).collect();
+    let frames = encode("fil-tx", &payload, 100).unwrap();
+    assert_eq!(frames.len(), 5);
+    assert!(frames[0].starts_with("FILQR:FIL-TX/1-5/"));
+    assert!(frames.iter().all(|f| f
+        .chars()
+        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || "-:/".contains(c))));
+
+    // out of order, with repeats, like an animated QR code
+    let mut decoder = Decoder::new();
+    for i in [3, 0, 3, 4, 1] {
+        decoder.receive(&frames[i]).unwrap();
+    }
+    assert_eq!(decoder.progress(), (4, 5));
+    assert!(decoder.result().is_err());
+    decoder.receive(&frames[2].to_lowercase()).unwrap();
+    assert_eq!(
+        decoder.result().unwrap(),
+        ("FIL-TX".to_string(), payload.clone())
+    );
+
+    let single = encode("fil-tx", b"hello", 100).unwrap();
+    assert_eq!(single, vec!["FILQR:FIL-TX/NBSWY3DP"]);
+    assert_eq!(
+        decode(single.iter().map(String::as_str)).unwrap().1,
+        b"hello"
+    );
+
+    let other = encode("fil-tx", &payload[1..], 100).unwrap();
+    let mut decoder = Decoder::new();
+    decoder.receive(&frames[0]).unwrap();
+    assert!(decoder.receive(&other[1]).is_err());
+    assert!(decoder.receive("FILQR:FIL-TX/0-5/00/AA").is_err());
+    assert!(decode(["not a frame"]).is_err());
+}
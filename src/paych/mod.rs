@@ -0,0 +1,324 @@
+use cid::Cid;
+use fvm_ipld_encoding::{serde_bytes, tuple::*, RawBytes};
+use fvm_shared::{
+    address::Address, clock::ChainEpoch, crypto::signature::Signature, econ::TokenAmount,
+    message::Message, MethodNum,
+};
+
+use crate::{
+    init::{exec_message, ExecParams},
+    message::MessageBuilder,
+    models::account::blake2b_256,
+    signer::{verify, MsgMeta, Signer},
+};
+
+// payment channel actor methods and params, as of actors v9
+
+pub const METHOD_UPDATE_CHANNEL_STATE: MethodNum = 2;
+pub const METHOD_SETTLE: MethodNum = 3;
+pub const METHOD_COLLECT: MethodNum = 4;
+
+/// created through the init actor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct ConstructorParams {
+    pub from: Address,
+    pub to: Address,
+}
+
+/// Method the actor calls to validate a voucher, with `data` and the proof
+/// given to UpdateChannelState
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct ModVerifyParams {
+    pub actor: Address,
+    pub method: MethodNum,
+    pub data: RawBytes,
+}
+
+/// Lane whose nonce is raised to `nonce` when the voucher is redeemed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct Merge {
+    pub lane: u64,
+    pub nonce: u64,
+}
+
+/// Off chain payment from the channel `from` to its `to`, `amount` being the
+/// total owed on the lane so far
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct SignedVoucher {
+    pub channel_addr: Address,
+    /// valid from this epoch, 0 for no lower bound
+    pub time_lock_min: ChainEpoch,
+    /// valid until this epoch, 0 for no upper bound
+    pub time_lock_max: ChainEpoch,
+    /// blake2b-256 of the secret the recipient has to reveal, empty for none
+    #[serde(with = "serde_bytes")]
+    pub secret_pre_image: Vec<u8>,
+    pub extra: Option<ModVerifyParams>,
+    pub lane: u64,
+    pub nonce: u64,
+    pub amount: TokenAmount,
+    /// the channel can't settle before this epoch once the voucher is redeemed
+    pub min_settle_height: ChainEpoch,
+    pub merges: Vec<Merge>,
+    pub signature: Option<Signature>,
+}
+
+impl SignedVoucher {
+    /// unsigned voucher without time locks, secret or merges
+    pub fn new(channel: Address, lane: u64, nonce: u64, amount: TokenAmount) -> Self {
+        Self {
+            channel_addr: channel,
+            time_lock_min: 0,
+            time_lock_max: 0,
+            secret_pre_image: vec![],
+            extra: None,
+            lane,
+            nonce,
+            amount,
+            min_settle_height: 0,
+            merges: vec![],
+            signature: None,
+        }
+    }
+
+    pub fn time_lock(mut self, min: ChainEpoch, max: ChainEpoch) -> Self {
+        self.time_lock_min = min;
+        self.time_lock_max = max;
+        self
+    }
+
+    /// lock the voucher until `secret` is given to UpdateChannelState
+    pub fn secret(mut self, secret: &[u8]) -> Self {
+        self.secret_pre_image = blake2b_256(secret).to_vec();
+        self
+    }
+
+    pub fn extra(mut self, extra: ModVerifyParams) -> Self {
+        self.extra = Some(extra);
+        self
+    }
+
+    pub fn min_settle_height(mut self, height: ChainEpoch) -> Self {
+        self.min_settle_height = height;
+        self
+    }
+
+    pub fn merge(mut self, lane: u64, nonce: u64) -> Self {
+        self.merges.push(Merge { lane, nonce });
+        self
+    }
+
+    /// CBOR of the voucher without its signature, what gets signed
+    pub fn signing_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let unsigned = SignedVoucher {
+            signature: None,
+            ..self.clone()
+        };
+        Ok(fvm_ipld_encoding::to_vec(&unsigned)?)
+    }
+
+    /// Sign with the key of the channel's `from`
+    pub fn sign(mut self, signer: &dyn Signer) -> anyhow::Result<Self> {
+        if self.lane > i64::MAX as u64 {
+            return Err(anyhow::anyhow!("lane {} is too large", self.lane));
+        }
+        let signature = signer.sign_bytes(&self.signing_bytes()?, &MsgMeta::unknown())?;
+        self.signature = Some(signature);
+        Ok(self)
+    }
+
+    /// Check a voucher received on `channel`, `signer` being the key address
+    /// of the channel's `from`. Doesn't check it against the channel state
+    /// (lane nonces and redeemed amounts) nor the time locks.
+    pub fn verify(&self, channel: &Address, signer: &Address) -> anyhow::Result<()> {
+        if self.channel_addr.to_bytes() != channel.to_bytes() {
+            return Err(anyhow::anyhow!(
+                "voucher is for channel {}, not {}",
+                self.channel_addr,
+                channel
+            ));
+        }
+        if self.time_lock_max != 0 && self.time_lock_max < self.time_lock_min {
+            return Err(anyhow::anyhow!("voucher time locks can never be met"));
+        }
+        if self.amount.is_negative() {
+            return Err(anyhow::anyhow!("voucher amount is negative"));
+        }
+        if self.merges.iter().any(|m| m.lane == self.lane) {
+            return Err(anyhow::anyhow!("voucher can't merge its own lane"));
+        }
+        let signature = self
+            .signature
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("voucher is not signed"))?;
+        verify(signer, &self.signing_bytes()?, signature)
+            .map_err(|e| anyhow::anyhow!("voucher signature: {}", e))
+    }
+
+    /// Whether `secret` unlocks the voucher
+    pub fn check_secret(&self, secret: &[u8]) -> bool {
+        self.secret_pre_image.is_empty() || self.secret_pre_image == blake2b_256(secret)
+    }
+
+    /// base64 of the CBOR encoding, the form `lotus paych voucher` uses
+    pub fn encode(&self) -> anyhow::Result<String> {
+        Ok(base64::encode_config(
+            fvm_ipld_encoding::to_vec(self)?,
+            base64::URL_SAFE_NO_PAD,
+        ))
+    }
+
+    pub fn decode(voucher: &str) -> anyhow::Result<Self> {
+        let bytes = base64::decode_config(voucher.trim(), base64::URL_SAFE_NO_PAD)
+            .map_err(|_| anyhow::anyhow!("invalid voucher encoding"))?;
+        Ok(fvm_ipld_encoding::from_slice(&bytes)?)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct UpdateChannelStateParams {
+    pub sv: SignedVoucher,
+    #[serde(with = "serde_bytes")]
+    pub secret: Vec<u8>,
+}
+
+/// Init Exec params creating a channel from `from` to `to`, `code` being the
+/// payment channel code CID of the network's manifest
+pub fn create_paych_params(code: Cid, from: Address, to: Address) -> anyhow::Result<RawBytes> {
+    let constructor = RawBytes::serialize(ConstructorParams { from, to })?;
+    Ok(RawBytes::serialize(ExecParams {
+        code_cid: code,
+        constructor_params: constructor,
+    })?)
+}
+
+/// Message creating a channel funded with `amount`
+pub fn create_paych_message(
+    code: Cid,
+    from: Address,
+    to: Address,
+    amount: TokenAmount,
+) -> anyhow::Result<Message> {
+    exec_message(
+        from,
+        code,
+        RawBytes::serialize(ConstructorParams { from, to })?,
+        amount,
+    )
+}
+
+/// Message redeeming a voucher, sent by the channel's `to`
+pub fn update_channel_state_message(
+    from: Address,
+    voucher: &SignedVoucher,
+    secret: &[u8],
+) -> anyhow::Result<Message> {
+    let params = UpdateChannelStateParams {
+        sv: voucher.clone(),
+        secret: secret.to_vec(),
+    };
+    Ok(MessageBuilder::new(from, voucher.channel_addr)
+        .method(METHOD_UPDATE_CHANNEL_STATE)
+        .params(RawBytes::serialize(params)?)
+        .build())
+}
+
+/// Message starting the settling period of `channel`
+pub fn settle_message(from: Address, channel: Address) -> Message {
+    MessageBuilder::new(from, channel)
+        .method(METHOD_SETTLE)
+        .build()
+}
+
+/// Message paying out a settled `channel`
+pub fn collect_message(from: Address, channel: Address) -> Message {
+    MessageBuilder::new(from, channel)
+        .method(METHOD_COLLECT)
+        .build()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{actors::INIT_ACTOR_ADDR, init, models::account::FlairAccount, types::WalletType};
+
+    fn code() -> Cid {
+        "bafk2bzaceduf3hayh63jnl4z2knxv7cnrdenoubni22fxersc4octlwpxpmy4"
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_create_paych() {
+        let from = Address::new_id(1000);
+        let to = Address::new_id(1001);
+        let message = create_paych_message(code(), from, to, TokenAmount::from_atto(5)).unwrap();
+        assert_eq!(message.to, INIT_ACTOR_ADDR);
+        assert_eq!(message.method_num, init::METHOD_EXEC);
+        assert_eq!(message.value, TokenAmount::from_atto(5));
+
+        let exec: ExecParams = message.params.deserialize().unwrap();
+        assert_eq!(exec.code_cid, code());
+        let constructor: ConstructorParams = exec.constructor_params.deserialize().unwrap();
+        assert_eq!(constructor, ConstructorParams { from, to });
+    }
+
+    #[test]
+    fn test_voucher() {
+        for wallet_type in [WalletType::Secp256k1, WalletType::Bls] {
+            let account = FlairAccount::generate(wallet_type).unwrap();
+            let key: Address = account.get_address().try_into().unwrap();
+            let channel = Address::new_id(1234);
+
+            let voucher = SignedVoucher::new(channel, 2, 7, TokenAmount::from_atto(100))
+                .time_lock(10, 20)
+                .secret(b"preimage")
+                .min_settle_height(30)
+                .merge(1, 4)
+                .sign(&account)
+                .unwrap();
+            voucher.verify(&channel, &key).unwrap();
+            assert!(voucher.check_secret(b"preimage"));
+            assert!(!voucher.check_secret(b"other"));
+
+            let decoded = SignedVoucher::decode(&voucher.encode().unwrap()).unwrap();
+            assert_eq!(decoded, voucher);
+
+            // wrong channel, tampered amount, someone else's key
+            assert!(voucher.verify(&Address::new_id(1), &key).is_err());
+            let mut tampered = voucher.clone();
+            tampered.amount = TokenAmount::from_atto(1000);
+            assert!(tampered.verify(&channel, &key).is_err());
+            let other = FlairAccount::generate(wallet_type).unwrap();
+            assert!(voucher
+                .verify(&channel, &other.get_address().try_into().unwrap())
+                .is_err());
+            let unsigned = SignedVoucher::new(channel, 2, 7, TokenAmount::from_atto(100));
+            assert!(unsigned.verify(&channel, &key).is_err());
+        }
+    }
+
+    #[test]
+    fn test_voucher_encoding() {
+        let voucher = SignedVoucher::new(Address::new_id(1234), 1, 2, TokenAmount::from_atto(3));
+        // 11 fields, the signature being null before signing
+        assert_eq!(
+            hex::encode(voucher.signing_bytes().unwrap()),
+            "8b4300d209000040f601024200030080f6"
+        );
+
+        let message =
+            update_channel_state_message(Address::new_id(1001), &voucher, b"secret").unwrap();
+        assert_eq!(message.method_num, METHOD_UPDATE_CHANNEL_STATE);
+        assert_eq!(message.to, voucher.channel_addr);
+        let params: UpdateChannelStateParams = message.params.deserialize().unwrap();
+        assert_eq!(params.sv, voucher);
+        assert_eq!(params.secret, b"secret");
+
+        let settle = settle_message(Address::new_id(1001), Address::new_id(1234));
+        assert_eq!(settle.method_num, METHOD_SETTLE);
+        assert!(settle.params.is_empty());
+        let collect = collect_message(Address::new_id(1001), Address::new_id(1234));
+        assert_eq!(collect.method_num, METHOD_COLLECT);
+    }
+}
@@ -0,0 +1,803 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    io::Write,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use fvm_ipld_encoding::Cbor;
+use fvm_shared::{
+    address::Address, crypto::signature::Signature, econ::TokenAmount, message::Message, MethodNum,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    decode::{method_number, parse_actor_type, singleton_type, ActorType},
+    models::account::FlairAddress,
+    multisig::{self, propose::ProposeParams},
+    signer::{MsgMeta, MsgType, Signer},
+    types::WalletType,
+};
+
+const DAY_SECONDS: u64 = 24 * 60 * 60;
+
+/// A method, by number for any actor, or by name as `<actor>.<method>`
+/// (e.g. `multisig.Approve`, `miner.ChangeWorkerAddress`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MethodRule {
+    Number(MethodNum),
+    Name(String),
+}
+
+// a method rule, with the actor it applies to when named
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MethodMatch {
+    actor: Option<ActorType>,
+    method: MethodNum,
+}
+
+impl MethodRule {
+    fn resolve(&self) -> anyhow::Result<MethodMatch> {
+        let name = match self {
+            MethodRule::Number(method) => {
+                return Ok(MethodMatch {
+                    actor: None,
+                    method: *method,
+                })
+            }
+            MethodRule::Name(name) => name,
+        };
+        // every actor accepts plain sends
+        if name == "Send" {
+            return Ok(MethodMatch {
+                actor: None,
+                method: 0,
+            });
+        }
+
+        let (actor, method) = name.split_once('.').ok_or_else(|| {
+            anyhow::anyhow!("method {} needs an actor type, e.g. miner.{}", name, name)
+        })?;
+        let actor = parse_actor_type(actor)?;
+        let method = method_number(actor, method)
+            .ok_or_else(|| anyhow::anyhow!("unknown {} method: {}", actor.name(), method))?;
+        Ok(MethodMatch {
+            actor: Some(actor),
+            method,
+        })
+    }
+}
+
+impl MethodMatch {
+    // None when the rule names a method of an actor type and the type of the
+    // recipient is unknown
+    fn matches(&self, call: &Call) -> Option<bool> {
+        if self.method != call.method {
+            return Some(false);
+        }
+        match (self.actor, call.actor) {
+            (None, _) => Some(true),
+            (Some(rule), Some(actor)) => Some(rule == actor),
+            (Some(_), None) => None,
+        }
+    }
+}
+
+/// Rules a signing request must satisfy, amounts are in attoFIL.
+///
+/// Empty allow lists allow anything.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SigningPolicy {
+    /// kinds of data that may be signed
+    pub allowed_types: Vec<MsgType>,
+    pub allowed_recipients: Vec<String>,
+    pub allowed_methods: Vec<MethodRule>,
+    pub blocked_methods: Vec<MethodRule>,
+    /// actor types of recipients by address (e.g. "f01234": "multisig"),
+    /// named method rules only allow calls to actors of known type
+    pub actor_types: BTreeMap<String, String>,
+    /// value of a single message, including the value of a multisig proposal
+    pub max_value: Option<String>,
+    /// value signed over the last 24 hours
+    pub max_daily_value: Option<String>,
+    /// gas limit times gas fee cap
+    pub max_fee: Option<String>,
+}
+
+impl Default for SigningPolicy {
+    /// chain messages only, without limits
+    fn default() -> Self {
+        Self {
+            allowed_types: vec![MsgType::ChainMsg],
+            allowed_recipients: vec![],
+            allowed_methods: vec![],
+            blocked_methods: vec![],
+            actor_types: BTreeMap::new(),
+            max_value: None,
+            max_daily_value: None,
+            max_fee: None,
+        }
+    }
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// unix time in seconds
+    pub time: u64,
+    pub signer: String,
+    #[serde(rename = "type")]
+    pub msg_type: MsgType,
+    pub cid: Option<String>,
+    pub to: Option<String>,
+    pub value: Option<String>,
+    pub method: Option<MethodNum>,
+    pub approved: bool,
+    pub reason: Option<String>,
+}
+
+// a decoded call the policy applies to
+struct Call {
+    to: Address,
+    actor: Option<ActorType>,
+    value: TokenAmount,
+    method: MethodNum,
+}
+
+/// Approved request, its value is reserved from the signer's daily cap and
+/// given back with `PolicyEngine::release` when signing fails
+#[derive(Debug)]
+#[must_use]
+pub struct Approval {
+    signer: Vec<u8>,
+    time: u64,
+    value: TokenAmount,
+}
+
+// signed values, by time
+type Spending = VecDeque<(u64, TokenAmount)>;
+
+/// Enforces a SigningPolicy and keeps track of what was signed
+pub struct PolicyEngine {
+    allowed_types: Vec<MsgType>,
+    allowed_recipients: Vec<Address>,
+    allowed_methods: Vec<MethodMatch>,
+    blocked_methods: Vec<MethodMatch>,
+    actor_types: Vec<(Address, ActorType)>,
+    max_value: Option<TokenAmount>,
+    max_daily_value: Option<TokenAmount>,
+    max_fee: Option<TokenAmount>,
+    // signed values of the last 24 hours by signer, by time
+    spent: Mutex<HashMap<Vec<u8>, Spending>>,
+    audit: Mutex<Option<Box<dyn Write + Send>>>,
+}
+
+fn parse_amount(value: &Option<String>) -> anyhow::Result<Option<TokenAmount>> {
+    value
+        .as_ref()
+        .map(|v| {
+            let atto = fvm_shared::bigint::BigInt::from_str(v.trim())
+                .map_err(|_| anyhow::anyhow!("invalid attoFIL amount: {}", v))?;
+            Ok(TokenAmount::from_atto(atto))
+        })
+        .transpose()
+}
+
+fn same_address(a: &Address, b: &Address) -> bool {
+    a.to_bytes() == b.to_bytes()
+}
+
+impl PolicyEngine {
+    pub fn new(policy: &SigningPolicy) -> anyhow::Result<Self> {
+        let resolve = |rules: &[MethodRule]| -> anyhow::Result<Vec<MethodMatch>> {
+            rules.iter().map(MethodRule::resolve).collect()
+        };
+        let parse_address =
+            |a: &str| Address::from_str(a).map_err(|e| anyhow::anyhow!("{}: {}", a, e));
+        let allowed_recipients = policy
+            .allowed_recipients
+            .iter()
+            .map(|a| parse_address(a))
+            .collect::<anyhow::Result<_>>()?;
+        let actor_types = policy
+            .actor_types
+            .iter()
+            .map(|(a, t)| Ok((parse_address(a)?, parse_actor_type(t)?)))
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self {
+            allowed_types: policy.allowed_types.clone(),
+            allowed_recipients,
+            allowed_methods: resolve(&policy.allowed_methods)?,
+            blocked_methods: resolve(&policy.blocked_methods)?,
+            actor_types,
+            max_value: parse_amount(&policy.max_value)?,
+            max_daily_value: parse_amount(&policy.max_daily_value)?,
+            max_fee: parse_amount(&policy.max_fee)?,
+            spent: Mutex::new(HashMap::new()),
+            audit: Mutex::new(None),
+        })
+    }
+
+    /// write an AuditRecord JSON line for every request
+    pub fn with_audit_log(self, log: Box<dyn Write + Send>) -> Self {
+        *self.audit.lock().expect("audit lock poisoned") = Some(log);
+        self
+    }
+
+    /// Approve or deny signing `data` for `signer`
+    pub fn authorize(
+        &self,
+        signer: &FlairAddress,
+        data: &[u8],
+        meta: &MsgMeta,
+    ) -> anyhow::Result<Approval> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        self.authorize_at(signer, data, meta, now)
+    }
+
+    pub(crate) fn authorize_at(
+        &self,
+        signer: &FlairAddress,
+        data: &[u8],
+        meta: &MsgMeta,
+        now: u64,
+    ) -> anyhow::Result<Approval> {
+        let mut record = AuditRecord {
+            time: now,
+            signer: signer.to_string(),
+            msg_type: meta.msg_type,
+            cid: None,
+            to: None,
+            value: None,
+            method: None,
+            approved: false,
+            reason: None,
+        };
+
+        let result = self.evaluate(signer, data, meta, now, &mut record);
+        if let Err(e) = &result {
+            record.reason = Some(e.to_string());
+        }
+        record.approved = result.is_ok();
+        self.audit(&record)?;
+        Ok(Approval {
+            signer: signer.to_bytes(),
+            time: now,
+            value: result?,
+        })
+    }
+
+    /// give back the value of a request that wasn't signed
+    pub fn release(&self, approval: Approval) {
+        if approval.value.is_zero() {
+            return;
+        }
+        let mut spent = self.spent.lock().expect("spent lock poisoned");
+        if let Some(spent) = spent.get_mut(&approval.signer) {
+            if let Some(index) = spent
+                .iter()
+                .rposition(|(time, value)| *time == approval.time && *value == approval.value)
+            {
+                spent.remove(index);
+            }
+        }
+    }
+
+    fn evaluate(
+        &self,
+        signer: &FlairAddress,
+        data: &[u8],
+        meta: &MsgMeta,
+        now: u64,
+        record: &mut AuditRecord,
+    ) -> anyhow::Result<TokenAmount> {
+        if !self.allowed_types.contains(&meta.msg_type) {
+            return Err(anyhow::anyhow!(
+                "signing {:?} data is not allowed",
+                meta.msg_type
+            ));
+        }
+        if meta.msg_type != MsgType::ChainMsg {
+            return Ok(TokenAmount::default());
+        }
+
+        meta.check(data)?;
+        let message = meta.message()?;
+        record.cid = Some(message.cid()?.to_string());
+        record.to = Some(message.to.to_string());
+        record.value = Some(message.value.atto().to_string());
+        record.method = Some(message.method_num);
+
+        if message.from.to_bytes() != signer.to_bytes() {
+            return Err(anyhow::anyhow!("message isn't from the signer"));
+        }
+
+        let calls = self.decode_calls(&message)?;
+        for call in &calls {
+            if !self.allowed_recipients.is_empty()
+                && !self
+                    .allowed_recipients
+                    .iter()
+                    .any(|a| same_address(a, &call.to))
+            {
+                return Err(anyhow::anyhow!("recipient {} is not allowed", call.to));
+            }
+            // a rule for a method of an unknown recipient blocks, but doesn't allow
+            if self
+                .blocked_methods
+                .iter()
+                .any(|rule| rule.matches(call) != Some(false))
+                || (!self.allowed_methods.is_empty()
+                    && !self
+                        .allowed_methods
+                        .iter()
+                        .any(|rule| rule.matches(call) == Some(true)))
+            {
+                return Err(anyhow::anyhow!("method {} is not allowed", call.method));
+            }
+            self.check_multisig_vote(call)?;
+        }
+
+        let value: TokenAmount = calls.iter().map(|c| c.value.clone()).sum();
+        if let Some(max) = &self.max_value {
+            if &value > max {
+                return Err(anyhow::anyhow!(
+                    "value {} exceeds the per message cap {}",
+                    value.atto(),
+                    max.atto()
+                ));
+            }
+        }
+        if let Some(max) = &self.max_fee {
+            let fee = message.gas_fee_cap.clone() * message.gas_limit;
+            if &fee > max {
+                return Err(anyhow::anyhow!(
+                    "fee {} exceeds the cap {}",
+                    fee.atto(),
+                    max.atto()
+                ));
+            }
+        }
+
+        let mut spent = self.spent.lock().expect("spent lock poisoned");
+        let spent = spent.entry(signer.to_bytes()).or_default();
+        while spent
+            .front()
+            .is_some_and(|(time, _)| time + DAY_SECONDS <= now)
+        {
+            spent.pop_front();
+        }
+        if let Some(max) = &self.max_daily_value {
+            let total: TokenAmount =
+                spent.iter().map(|(_, v)| v.clone()).sum::<TokenAmount>() + &value;
+            if &total > max {
+                return Err(anyhow::anyhow!(
+                    "value {} exceeds the daily cap {}",
+                    total.atto(),
+                    max.atto()
+                ));
+            }
+        }
+        // reserved under the lock, so concurrent requests can't all pass the cap
+        if !value.is_zero() {
+            spent.push_back((now, value.clone()));
+        }
+        Ok(value)
+    }
+
+    // type of the actor at `address`, when known
+    fn actor_type(&self, address: &Address) -> Option<ActorType> {
+        singleton_type(address).or_else(|| {
+            self.actor_types
+                .iter()
+                .find(|(a, _)| same_address(a, address))
+                .map(|(_, t)| *t)
+        })
+    }
+
+    // The message itself, and the proposed call of a multisig proposal.
+    // Proposals to multisigs must decode, params of method 2 to recipients of
+    // unknown type are checked as a proposal too when they decode as one.
+    fn decode_calls(&self, message: &Message) -> anyhow::Result<Vec<Call>> {
+        let actor = self.actor_type(&message.to);
+        let mut calls = vec![Call {
+            to: message.to,
+            actor,
+            value: message.value.clone(),
+            method: message.method_num,
+        }];
+        if message.method_num != multisig::METHOD_PROPOSE
+            || actor.is_some_and(|a| a != ActorType::Multisig)
+        {
+            return Ok(calls);
+        }
+
+        match fvm_ipld_encoding::from_slice::<ProposeParams>(message.params.bytes()) {
+            Ok(proposal) => calls.push(Call {
+                to: proposal.to,
+                actor: self.actor_type(&proposal.to),
+                value: proposal.value,
+                method: proposal.method,
+            }),
+            Err(e) if actor == Some(ActorType::Multisig) => {
+                return Err(anyhow::anyhow!("invalid multisig proposal: {}", e))
+            }
+            Err(_) => {}
+        }
+        Ok(calls)
+    }
+
+    // Approving or cancelling a multisig transaction executes or drops a call
+    // the signer can't see, so it needs a rule allowing it explicitly. Method 3
+    // and 4 of recipients of unknown type may be such votes.
+    fn check_multisig_vote(&self, call: &Call) -> anyhow::Result<()> {
+        if !matches!(
+            call.method,
+            multisig::METHOD_APPROVE | multisig::METHOD_CANCEL
+        ) || call.actor.is_some_and(|a| a != ActorType::Multisig)
+        {
+            return Ok(());
+        }
+        let allowed = self.allowed_methods.iter().any(|rule| {
+            rule.method == call.method && rule.actor.is_none_or(|a| a == ActorType::Multisig)
+        });
+        if !allowed {
+            return Err(anyhow::anyhow!(
+                "method {} to {} may vote on a multisig transaction, it must be allowed explicitly",
+                call.method,
+                call.to
+            ));
+        }
+        Ok(())
+    }
+
+    fn audit(&self, record: &AuditRecord) -> anyhow::Result<()> {
+        if let Some(log) = self.audit.lock().expect("audit lock poisoned").as_mut() {
+            writeln!(log, "{}", serde_json::to_string(record)?)?;
+            log.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Signer refusing whatever the policy doesn't approve
+pub struct PolicySigner<S: Signer> {
+    inner: S,
+    engine: Arc<PolicyEngine>,
+}
+
+impl<S: Signer> PolicySigner<S> {
+    pub fn new(inner: S, engine: Arc<PolicyEngine>) -> Self {
+        Self { inner, engine }
+    }
+}
+
+impl<S: Signer> Signer for PolicySigner<S> {
+    fn address(&self) -> FlairAddress {
+        self.inner.address()
+    }
+
+    fn wallet_type(&self) -> WalletType {
+        self.inner.wallet_type()
+    }
+
+    fn sign_bytes(&self, data: &[u8], meta: &MsgMeta) -> anyhow::Result<Signature> {
+        let approval = self.engine.authorize(&self.inner.address(), data, meta)?;
+        self.inner
+            .sign_bytes(data, meta)
+            .inspect_err(|_| self.engine.release(approval))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use fvm_ipld_encoding::RawBytes;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{models::account::FlairAccount, MessageBuilder};
+
+    /// audit log readable by the test
+    #[derive(Clone, Default)]
+    struct SharedLog(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedLog {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedLog {
+        fn records(&self) -> Vec<AuditRecord> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|l| serde_json::from_str(l).unwrap())
+                .collect()
+        }
+    }
+
+    fn meta(message: &Message) -> (Vec<u8>, MsgMeta) {
+        (
+            message.cid().unwrap().to_bytes(),
+            MsgMeta::chain_msg(message.marshal_cbor().unwrap()),
+        )
+    }
+
+    fn policy() -> SigningPolicy {
+        serde_json::from_str(
+            r#"{
+                "allowed_recipients": ["f01000", "t01001"],
+                "blocked_methods": ["miner.ChangeOwnerAddress", 30],
+                "actor_types": {"f01000": "miner", "f01001": "multisig"},
+                "max_value": "100",
+                "max_daily_value": "250",
+                "max_fee": "1000000"
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_policy_rules() {
+        let log = SharedLog::default();
+        let engine = PolicyEngine::new(&policy())
+            .unwrap()
+            .with_audit_log(Box::new(log.clone()));
+        let from = Address::new_id(42);
+        let signer: FlairAddress = from.into();
+        let check = |message: Message, now: u64| {
+            let (data, meta) = meta(&message);
+            engine
+                .authorize_at(&signer, &data, &meta, now)
+                .map(|_approval| ())
+        };
+        let send = |to: u64, value: u64| {
+            MessageBuilder::new(from, Address::new_id(to))
+                .value(TokenAmount::from_atto(value))
+                .gas(1000, TokenAmount::from_atto(100), TokenAmount::from_atto(1))
+        };
+
+        check(send(1000, 100).build(), 0).unwrap();
+        assert!(check(send(1001, 101).build(), 0).is_err());
+        assert!(check(send(2000, 1).build(), 0).is_err());
+        assert!(check(send(1000, 1).method(23).build(), 0).is_err());
+        assert!(check(send(1000, 1).method(30).build(), 0).is_err());
+        assert!(check(
+            send(1000, 1)
+                .gas(
+                    100_000,
+                    TokenAmount::from_atto(100),
+                    TokenAmount::from_atto(1)
+                )
+                .build(),
+            0
+        )
+        .is_err());
+        // message from another account
+        assert!(check(
+            MessageBuilder::new(Address::new_id(7), Address::new_id(1000)).build(),
+            0
+        )
+        .is_err());
+
+        // daily cap: 100 already spent
+        check(send(1001, 100).build(), 10).unwrap();
+        assert!(check(send(1000, 51).build(), 20).is_err());
+        check(send(1000, 50).build(), 20).unwrap();
+        // the first spend leaves the window
+        check(send(1000, 100).build(), DAY_SECONDS).unwrap();
+
+        let records = log.records();
+        assert_eq!(records.len(), 11);
+        assert!(records[0].approved);
+        assert_eq!(records[0].to, Some("f01000".to_string()));
+        assert_eq!(records[0].value, Some("100".to_string()));
+        assert!(!records[1].approved);
+        assert!(records[1]
+            .reason
+            .as_ref()
+            .unwrap()
+            .contains("per message cap"));
+        assert!(records[3].reason.as_ref().unwrap().contains("method 23"));
+        assert!(records[8].reason.as_ref().unwrap().contains("daily cap"));
+    }
+
+    #[test]
+    fn test_policy_multisig_proposal() {
+        let engine = PolicyEngine::new(&policy()).unwrap();
+        let from = Address::new_id(42);
+        let propose = |to: u64, value: u64, method: MethodNum| {
+            let params = ProposeParams {
+                to: Address::new_id(to),
+                value: TokenAmount::from_atto(value),
+                method,
+                params: RawBytes::default(),
+            };
+            MessageBuilder::new(from, Address::new_id(1001))
+                .method(multisig::METHOD_PROPOSE)
+                .params(RawBytes::serialize(params).unwrap())
+                .build()
+        };
+        let check = |message: Message| {
+            let (data, meta) = meta(&message);
+            engine
+                .authorize_at(&from.into(), &data, &meta, 0)
+                .map(|_approval| ())
+        };
+
+        check(propose(1000, 50, 0)).unwrap();
+        assert!(check(propose(3000, 50, 0)).is_err());
+        assert!(check(propose(1000, 500, 0)).is_err());
+        assert!(check(propose(1000, 0, 23)).is_err());
+
+        // proposals that don't decode are refused, method 2 of other actors isn't a proposal
+        let garbage = |to: u64| {
+            MessageBuilder::new(from, Address::new_id(to))
+                .method(multisig::METHOD_PROPOSE)
+                .params(RawBytes::new(vec![0xff]))
+                .build()
+        };
+        let err = check(garbage(1001)).unwrap_err();
+        assert!(err.to_string().contains("invalid multisig proposal"));
+        check(garbage(1000)).unwrap();
+    }
+
+    #[test]
+    fn test_policy_method_names() {
+        let engine = |policy: serde_json::Value| {
+            let mut policy: SigningPolicy = serde_json::from_value(policy).unwrap();
+            policy.actor_types = [("f01000", "miner"), ("f01001", "multisig")]
+                .iter()
+                .map(|(a, t)| (a.to_string(), t.to_string()))
+                .collect();
+            PolicyEngine::new(&policy)
+        };
+        let from = Address::new_id(42);
+        let call = |engine: &PolicyEngine, to: u64, method: MethodNum| {
+            let message = MessageBuilder::new(from, Address::new_id(to))
+                .method(method)
+                .build();
+            let (data, meta) = meta(&message);
+            engine
+                .authorize_at(&from.into(), &data, &meta, 0)
+                .map(|_approval| ())
+        };
+
+        // ChangeWorkerAddress and Approve are both method 3
+        let workers = engine(json!({"allowed_methods": ["miner.ChangeWorkerAddress"]})).unwrap();
+        call(&workers, 1000, 3).unwrap();
+        assert!(call(&workers, 1001, 3).is_err());
+        assert!(call(&workers, 1002, 3).is_err());
+        let blocked = engine(json!({"blocked_methods": ["multisig.Cancel"]})).unwrap();
+        call(&blocked, 1000, 4).unwrap();
+        assert!(call(&blocked, 1001, 4).is_err());
+        // unknown recipients might be multisigs
+        assert!(call(&blocked, 1002, 4).is_err());
+
+        // votes on multisig transactions must be allowed explicitly
+        let err = call(&blocked, 1001, 3).unwrap_err();
+        assert!(err.to_string().contains("allowed explicitly"));
+        let votes = engine(json!({"allowed_methods": ["Send", "msig.Approve"]})).unwrap();
+        call(&votes, 1001, 3).unwrap();
+        call(&votes, 1001, 0).unwrap();
+        assert!(call(&votes, 1001, 4).is_err());
+        assert!(call(&votes, 1000, 3).is_err());
+
+        assert!(engine(json!({"blocked_methods": ["ChangeOwnerAddress"]})).is_err());
+        assert!(engine(json!({"blocked_methods": ["miner.Steal"]})).is_err());
+        assert!(PolicyEngine::new(
+            &serde_json::from_value(json!({"actor_types": {"f01000": "wallet"}})).unwrap()
+        )
+        .is_err());
+    }
+
+    /// signer whose device is gone
+    struct Unplugged(FlairAddress);
+
+    impl Signer for Unplugged {
+        fn address(&self) -> FlairAddress {
+            self.0
+        }
+
+        fn wallet_type(&self) -> WalletType {
+            WalletType::Secp256k1
+        }
+
+        fn sign_bytes(&self, _: &[u8], _: &MsgMeta) -> anyhow::Result<Signature> {
+            Err(anyhow::anyhow!("device not found"))
+        }
+    }
+
+    #[test]
+    fn test_policy_daily_cap_per_signer() {
+        let engine = Arc::new(PolicyEngine::new(&policy()).unwrap());
+        let account = FlairAccount::generate(WalletType::Secp256k1).unwrap();
+        let other = FlairAccount::generate(WalletType::Secp256k1).unwrap();
+        let send = |signer: &dyn Signer, value: u64| {
+            let message =
+                MessageBuilder::new(signer.address().try_into().unwrap(), Address::new_id(1000))
+                    .value(TokenAmount::from_atto(value))
+                    .build();
+            crate::sign_message(signer, message)
+        };
+
+        // failed signatures don't count
+        let unplugged = PolicySigner::new(Unplugged(account.get_address()), engine.clone());
+        for _ in 0..5 {
+            assert!(send(&unplugged, 100).is_err());
+        }
+        let signer = PolicySigner::new(account, engine.clone());
+        send(&signer, 100).unwrap();
+        send(&signer, 100).unwrap();
+        let err = send(&signer, 100).unwrap_err();
+        assert!(err.to_string().contains("daily cap"));
+
+        // every signer has its own cap
+        let other = PolicySigner::new(other, engine);
+        send(&other, 100).unwrap();
+    }
+
+    #[test]
+    fn test_policy_daily_cap_concurrent() {
+        let engine = PolicyEngine::new(&policy()).unwrap();
+        let from = Address::new_id(42);
+        let message = MessageBuilder::new(from, Address::new_id(1000))
+            .value(TokenAmount::from_atto(100))
+            .build();
+        let (data, meta) = meta(&message);
+
+        // 250 a day lets two of them through, whatever the interleaving
+        let barrier = std::sync::Barrier::new(8);
+        let approvals: Vec<_> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    s.spawn(|| {
+                        barrier.wait();
+                        engine.authorize_at(&from.into(), &data, &meta, 0).ok()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .filter_map(|h| h.join().unwrap())
+                .collect()
+        });
+        assert_eq!(approvals.len(), 2);
+
+        // a released approval frees its part of the cap
+        let mut approvals = approvals.into_iter();
+        engine.release(approvals.next().unwrap());
+        let _approval = engine.authorize_at(&from.into(), &data, &meta, 0).unwrap();
+        assert!(engine.authorize_at(&from.into(), &data, &meta, 0).is_err());
+    }
+
+    #[test]
+    fn test_policy_signer() {
+        let account = FlairAccount::generate(WalletType::Secp256k1).unwrap();
+        let engine = Arc::new(PolicyEngine::new(&policy()).unwrap());
+        let signer = PolicySigner::new(account, engine);
+
+        // only chain messages by default
+        assert!(signer.sign_bytes(b"data", &MsgMeta::unknown()).is_err());
+        let message =
+            MessageBuilder::new(signer.address().try_into().unwrap(), Address::new_id(1000))
+                .value(TokenAmount::from_atto(10))
+                .build();
+        assert!(crate::sign_message(&signer, message).is_ok());
+
+        assert!(PolicyEngine::new(&SigningPolicy {
+            blocked_methods: vec![MethodRule::Name("Steal".to_string())],
+            ..Default::default()
+        })
+        .is_err());
+    }
+}
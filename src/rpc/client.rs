@@ -0,0 +1,281 @@
+use std::{collections::HashMap, str::FromStr};
+
+use cid::Cid;
+use fvm_shared::{
+    address::Address, bigint::BigInt, clock::ChainEpoch, econ::TokenAmount, message::Message,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[cfg(feature = "rpc")]
+use crate::signer::remote::HttpTransport;
+use crate::{
+    manifest::Manifest,
+    message::{CidJson, MessageJson, SignedMessage, SignedMessageJson},
+    signer::remote::RpcTransport,
+};
+
+/// Tipset as returned by ChainHead, without its block headers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TipSet {
+    pub cids: Vec<CidJson>,
+    pub height: ChainEpoch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MessageReceipt {
+    pub exit_code: i64,
+    /// base64, null when empty
+    #[serde(rename = "Return")]
+    pub return_data: Option<String>,
+    pub gas_used: i64,
+}
+
+/// Result of StateWaitMsg
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MsgLookup {
+    /// CID of the executed message, differs from the waited one when it was replaced
+    pub message: CidJson,
+    pub receipt: MessageReceipt,
+    pub tip_set: Vec<CidJson>,
+    pub height: ChainEpoch,
+}
+
+impl MsgLookup {
+    pub fn success(&self) -> bool {
+        self.receipt.exit_code == 0
+    }
+}
+
+fn parse_cid(cid: &CidJson) -> anyhow::Result<Cid> {
+    Cid::from_str(&cid.cid).map_err(|e| anyhow::anyhow!("invalid CID {}: {}", cid.cid, e))
+}
+
+/// Typed client for the chain state part of the Lotus API
+pub struct LotusClient<T: RpcTransport> {
+    transport: T,
+}
+
+#[cfg(feature = "rpc")]
+impl LotusClient<HttpTransport> {
+    /// `endpoint` like http://127.0.0.1:1234/rpc/v0, `token` from `lotus auth create-token`
+    pub fn connect(endpoint: &str, token: Option<String>) -> Self {
+        Self::new(HttpTransport::new(endpoint, token))
+    }
+}
+
+impl<T: RpcTransport> LotusClient<T> {
+    pub fn new(transport: T) -> Self {
+        Self { transport }
+    }
+
+    fn call<R: DeserializeOwned>(&self, method: &str, params: Value) -> anyhow::Result<R> {
+        let result = self.transport.call(method, params)?;
+        serde_json::from_value(result)
+            .map_err(|e| anyhow::anyhow!("{} returned an unexpected result: {}", method, e))
+    }
+
+    fn call_address(&self, method: &str, address: &Address) -> anyhow::Result<Address> {
+        let address: String = self.call(method, json!([address.to_string(), null]))?;
+        Address::from_str(&address).map_err(|e| anyhow::anyhow!("invalid address: {}", e))
+    }
+
+    /// Next nonce of `address`, counting its messages waiting in the mpool
+    pub fn mpool_get_nonce(&self, address: &Address) -> anyhow::Result<u64> {
+        self.call("Filecoin.MpoolGetNonce", json!([address.to_string()]))
+    }
+
+    /// Fill in the gas fields left at zero, keeping the fee under `max_fee`
+    pub fn gas_estimate_message_gas(
+        &self,
+        message: &Message,
+        max_fee: Option<&TokenAmount>,
+    ) -> anyhow::Result<Message> {
+        let mut json = MessageJson::from(message);
+        json.cid = None;
+        let spec = json!({
+            "MaxFee": max_fee.map_or("0".to_string(), |f| f.atto().to_string()),
+        });
+        let estimated: MessageJson =
+            self.call("Filecoin.GasEstimateMessageGas", json!([json, spec, null]))?;
+        Message::try_from(estimated)
+    }
+
+    pub fn wallet_balance(&self, address: &Address) -> anyhow::Result<TokenAmount> {
+        let balance: String = self.call("Filecoin.WalletBalance", json!([address.to_string()]))?;
+        let atto = BigInt::from_str(&balance)
+            .map_err(|_| anyhow::anyhow!("invalid balance: {}", balance))?;
+        Ok(TokenAmount::from_atto(atto))
+    }
+
+    /// ID address of an actor
+    pub fn state_lookup_id(&self, address: &Address) -> anyhow::Result<Address> {
+        self.call_address("Filecoin.StateLookupID", address)
+    }
+
+    /// Public key address behind an account actor
+    pub fn state_account_key(&self, address: &Address) -> anyhow::Result<Address> {
+        self.call_address("Filecoin.StateAccountKey", address)
+    }
+
+    /// Push a signed message to the mpool, returns its CID
+    pub fn mpool_push(&self, message: &SignedMessage) -> anyhow::Result<Cid> {
+        let mut json = SignedMessageJson::from(message);
+        json.cid = None;
+        json.message.cid = None;
+        let cid: CidJson = self.call("Filecoin.MpoolPush", json!([json]))?;
+        parse_cid(&cid)
+    }
+
+    /// Wait until the message is executed and `confidence` epochs passed
+    pub fn state_wait_msg(&self, cid: &Cid, confidence: u64) -> anyhow::Result<MsgLookup> {
+        self.call(
+            "Filecoin.StateWaitMsg",
+            json!([CidJson::from(cid), confidence]),
+        )
+    }
+
+    /// Message by CID, including messages still in the mpool
+    pub fn chain_get_message(&self, cid: &Cid) -> anyhow::Result<Message> {
+        let message: MessageJson =
+            self.call("Filecoin.ChainGetMessage", json!([CidJson::from(cid)]))?;
+        Message::try_from(message)
+    }
+
+    /// Name of the network, "mainnet" or "calibrationnet" for the public ones
+    pub fn state_network_name(&self) -> anyhow::Result<String> {
+        self.call("Filecoin.StateNetworkName", json!([]))
+    }
+
+    /// Network version at the head of the chain
+    pub fn state_network_version(&self) -> anyhow::Result<u32> {
+        self.call("Filecoin.StateNetworkVersion", json!([null]))
+    }
+
+    /// Builtin actor code CIDs of a network version
+    pub fn state_actor_code_cids(&self, version: u32) -> anyhow::Result<Manifest> {
+        let codes: HashMap<String, CidJson> =
+            self.call("Filecoin.StateActorCodeCIDs", json!([version]))?;
+        let codes = codes
+            .iter()
+            .map(|(name, cid)| Ok((name.clone(), parse_cid(cid)?)))
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Manifest::new(codes))
+    }
+
+    pub fn chain_head(&self) -> anyhow::Result<TipSet> {
+        self.call("Filecoin.ChainHead", json!([]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use fvm_ipld_encoding::Cbor;
+
+    use super::*;
+    use crate::{
+        message::MessageBuilder,
+        models::account::FlairAccount,
+        rpc::mock::MockLotus,
+        signer::{MsgMeta, Signer},
+        types::WalletType,
+    };
+
+    fn fil(amount: u64) -> TokenAmount {
+        TokenAmount::from_atto(BigInt::from(amount) * BigInt::from(10u64.pow(18)))
+    }
+
+    #[test]
+    fn test_lotus_send() {
+        let lotus = MockLotus::new();
+        let account = FlairAccount::generate(WalletType::Secp256k1).unwrap();
+        let from: Address = account.get_address().try_into().unwrap();
+        let id = lotus.add_account(from, fil(10));
+        let to: Address = FlairAccount::generate(WalletType::Bls)
+            .unwrap()
+            .get_address()
+            .try_into()
+            .unwrap();
+
+        let client = LotusClient::new(&lotus);
+        assert_eq!(client.state_lookup_id(&from).unwrap(), id);
+        assert_eq!(
+            client.state_account_key(&id).unwrap().to_bytes(),
+            from.to_bytes()
+        );
+        assert!(client.state_lookup_id(&to).is_err());
+        assert_eq!(client.state_network_name().unwrap(), "mainnet");
+        let manifest = client
+            .state_actor_code_cids(client.state_network_version().unwrap())
+            .unwrap();
+        let paych = manifest
+            .code(crate::decode::ActorType::PaymentChannel)
+            .unwrap();
+        assert_eq!(
+            manifest.actor_type(&paych),
+            Some(crate::decode::ActorType::PaymentChannel)
+        );
+        assert_eq!(client.wallet_balance(&from).unwrap(), fil(10));
+        let head = client.chain_head().unwrap();
+
+        // a full send
+        let message = MessageBuilder::new(from, to)
+            .nonce(client.mpool_get_nonce(&from).unwrap())
+            .value(fil(1))
+            .build();
+        let message = client
+            .gas_estimate_message_gas(&message, Some(&TokenAmount::from_atto(10_000_000_000u64)))
+            .unwrap();
+        assert!(message.gas_limit > 0);
+        assert!(message.gas_fee_cap.atto() * message.gas_limit <= BigInt::from(10_000_000_000u64));
+
+        let signed = crate::message::sign_message(&account, message).unwrap();
+        let cid = client.mpool_push(&signed).unwrap();
+        assert_eq!(cid, signed.cid().unwrap());
+        assert_eq!(client.mpool_get_nonce(&from).unwrap(), 1);
+        assert_eq!(lotus.pending(), vec![signed.clone()]);
+
+        let lookup = client.state_wait_msg(&cid, 1).unwrap();
+        assert!(lookup.success());
+        assert!(lookup.height > head.height);
+        assert!(lotus.pending().is_empty());
+        assert_eq!(client.wallet_balance(&to).unwrap(), fil(1));
+        assert_eq!(client.wallet_balance(&from).unwrap(), fil(9));
+
+        // a message signed by another key is refused
+        let other = FlairAccount::generate(WalletType::Secp256k1).unwrap();
+        let mut forged = signed;
+        forged.message.sequence = 1;
+        forged.signature = other
+            .sign_bytes(
+                &forged.message.cid().unwrap().to_bytes(),
+                &MsgMeta::unknown(),
+            )
+            .unwrap();
+        assert!(client.mpool_push(&forged).is_err());
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn test_lotus_http_token() {
+        let lotus = std::sync::Arc::new(MockLotus::new());
+        let account = FlairAccount::generate(WalletType::Bls).unwrap();
+        lotus.add_account(account.get_address().try_into().unwrap(), fil(1));
+        let endpoint = lotus.clone().serve_http("secret");
+
+        let client = LotusClient::connect(&endpoint, Some("secret".to_string()));
+        assert_eq!(
+            client
+                .wallet_balance(&account.get_address().try_into().unwrap())
+                .unwrap(),
+            fil(1)
+        );
+        let client = LotusClient::connect(&endpoint, Some("wrong".to_string()));
+        assert!(client.chain_head().is_err());
+        let client = LotusClient::connect(&endpoint, None);
+        assert!(client.chain_head().is_err());
+    }
+}
@@ -0,0 +1,317 @@
+use std::{collections::HashMap, str::FromStr, sync::Mutex};
+
+use fvm_ipld_encoding::Cbor;
+use fvm_shared::{address::Address, bigint::BigInt, econ::TokenAmount, message::Message};
+use serde_json::{json, Value};
+
+use crate::{
+    decode::ACTOR_TYPES,
+    message::{CidJson, MessageJson, SignedMessage, SignedMessageJson},
+    signer::{remote::RpcTransport, verify},
+};
+
+// In-memory chain answering the Lotus methods the client uses. Every
+// StateWaitMsg mines a block including the pending messages.
+
+const FIRST_ACTOR_ID: u64 = 1000;
+const NETWORK_VERSION: u32 = 17;
+const DEFAULT_GAS_LIMIT: i64 = 1_000_000;
+const DEFAULT_GAS_FEE_CAP: u64 = 100_000;
+const DEFAULT_GAS_PREMIUM: u64 = 1_000;
+
+struct Actor {
+    id: u64,
+    key: Option<Address>,
+    balance: TokenAmount,
+    nonce: u64,
+}
+
+#[derive(Default)]
+struct Chain {
+    height: i64,
+    actors: Vec<Actor>,
+    mpool: Vec<SignedMessage>,
+    // every pushed message by cid
+    messages: HashMap<String, Message>,
+    // replaced message cid -> cid of the replacement
+    replaced: HashMap<String, String>,
+    // message cid -> (height, exit code)
+    executed: HashMap<String, (i64, i64)>,
+}
+
+impl Chain {
+    fn actor(&self, address: &Address) -> Option<usize> {
+        self.actors.iter().position(|a| {
+            address.id().ok() == Some(a.id)
+                || a.key.is_some_and(|k| k.to_bytes() == address.to_bytes())
+        })
+    }
+
+    fn actor_or_create(&mut self, address: &Address) -> usize {
+        if let Some(index) = self.actor(address) {
+            return index;
+        }
+        self.actors.push(Actor {
+            id: FIRST_ACTOR_ID + self.actors.len() as u64,
+            key: Some(*address),
+            balance: TokenAmount::from_atto(0),
+            nonce: 0,
+        });
+        self.actors.len() - 1
+    }
+
+    fn next_nonce(&self, address: &Address) -> u64 {
+        let Some(index) = self.actor(address) else {
+            return 0;
+        };
+        let mut nonce = self.actors[index].nonce;
+        while self.pending(index, nonce).is_some() {
+            nonce += 1;
+        }
+        nonce
+    }
+
+    fn pending(&self, actor: usize, nonce: u64) -> Option<usize> {
+        self.mpool
+            .iter()
+            .position(|m| self.actor(&m.message.from) == Some(actor) && m.message.sequence == nonce)
+    }
+
+    fn push(&mut self, signed: SignedMessage) -> anyhow::Result<()> {
+        let index = self
+            .actor(&signed.message.from)
+            .ok_or_else(|| anyhow::anyhow!("actor not found"))?;
+        let key = self.actors[index]
+            .key
+            .ok_or_else(|| anyhow::anyhow!("sender is not an account"))?;
+        verify(&key, &signed.message.cid()?.to_bytes(), &signed.signature)?;
+        if signed.message.sequence < self.actors[index].nonce {
+            return Err(anyhow::anyhow!(
+                "minimum expected nonce is {}",
+                self.actors[index].nonce
+            ));
+        }
+        match self.pending(index, signed.message.sequence) {
+            // replace by fee needs 25% more premium
+            Some(existing) => {
+                let old = self.mpool[existing].message.gas_premium.atto().clone();
+                if signed.message.gas_premium.atto() * 4 < old * 5 {
+                    return Err(anyhow::anyhow!("replace by fee has too low GasPremium"));
+                }
+                self.replaced.insert(
+                    self.mpool[existing].cid()?.to_string(),
+                    signed.cid()?.to_string(),
+                );
+                self.mpool[existing] = signed;
+            }
+            None => self.mpool.push(signed),
+        }
+        Ok(())
+    }
+
+    fn mine(&mut self) {
+        self.height += 1;
+        loop {
+            let next = (0..self.actors.len())
+                .find_map(|index| self.pending(index, self.actors[index].nonce));
+            let Some(next) = next else { break };
+            let signed = self.mpool.remove(next);
+            let message = &signed.message;
+
+            let from = self.actor(&message.from).expect("pushed by a known actor");
+            let to = self.actor_or_create(&message.to);
+            self.actors[from].nonce += 1;
+            let exit_code = if self.actors[from].balance < message.value {
+                // SysErrInsufficientFunds
+                6
+            } else {
+                self.actors[from].balance -= message.value.clone();
+                self.actors[to].balance += message.value.clone();
+                0
+            };
+            self.executed.insert(
+                signed.cid().expect("cid").to_string(),
+                (self.height, exit_code),
+            );
+        }
+    }
+
+    fn call(&mut self, method: &str, params: &Value) -> anyhow::Result<Value> {
+        let address = |index: usize| -> anyhow::Result<Address> {
+            let address = params[index].as_str().unwrap_or_default();
+            Address::from_str(address).map_err(|e| anyhow::anyhow!("{}", e))
+        };
+        match method {
+            "Filecoin.ChainHead" => Ok(json!({
+                "Cids": [{"/": format!("bafy2bzacetipset{}", self.height)}],
+                "Blocks": [],
+                "Height": self.height,
+            })),
+            "Filecoin.StateNetworkName" => Ok(json!("mainnet")),
+            "Filecoin.StateNetworkVersion" => Ok(json!(NETWORK_VERSION)),
+            // identity CIDs of the actor names, like the code CIDs before v8
+            "Filecoin.StateActorCodeCIDs" => Ok(ACTOR_TYPES
+                .iter()
+                .map(|actor| {
+                    let name = format!("fil/9/{}", actor.name());
+                    let hash = cid::multihash::Multihash::wrap(0, name.as_bytes())?;
+                    let cid = cid::Cid::new_v1(0x55, hash);
+                    Ok((actor.name().to_string(), json!(CidJson::from(&cid))))
+                })
+                .collect::<anyhow::Result<serde_json::Map<_, _>>>()?
+                .into()),
+            "Filecoin.MpoolGetNonce" => Ok(json!(self.next_nonce(&address(0)?))),
+            "Filecoin.WalletBalance" => Ok(json!(self.actor(&address(0)?).map_or(
+                "0".to_string(),
+                |i| self.actors[i].balance.atto().to_string()
+            ))),
+            "Filecoin.StateLookupID" => {
+                let index = self
+                    .actor(&address(0)?)
+                    .ok_or_else(|| anyhow::anyhow!("actor not found"))?;
+                Ok(json!(Address::new_id(self.actors[index].id).to_string()))
+            }
+            "Filecoin.StateAccountKey" => {
+                let index = self
+                    .actor(&address(0)?)
+                    .ok_or_else(|| anyhow::anyhow!("actor not found"))?;
+                let key = self.actors[index]
+                    .key
+                    .ok_or_else(|| anyhow::anyhow!("not an account actor"))?;
+                Ok(json!(key.to_string()))
+            }
+            "Filecoin.GasEstimateMessageGas" => {
+                let mut message: Message =
+                    serde_json::from_value::<MessageJson>(params[0].clone())?.try_into()?;
+                if message.gas_limit == 0 {
+                    message.gas_limit = DEFAULT_GAS_LIMIT;
+                }
+                if message.gas_premium.is_zero() {
+                    message.gas_premium = TokenAmount::from_atto(DEFAULT_GAS_PREMIUM);
+                }
+                if message.gas_fee_cap.is_zero() {
+                    message.gas_fee_cap = TokenAmount::from_atto(DEFAULT_GAS_FEE_CAP);
+                }
+                let max_fee = params[1]["MaxFee"]
+                    .as_str()
+                    .and_then(|f| BigInt::from_str(f).ok())
+                    .unwrap_or_default();
+                if max_fee > BigInt::from(0)
+                    && message.gas_fee_cap.atto() * message.gas_limit > max_fee
+                {
+                    message.gas_fee_cap = TokenAmount::from_atto(max_fee / message.gas_limit);
+                    if message.gas_premium > message.gas_fee_cap {
+                        message.gas_premium = message.gas_fee_cap.clone();
+                    }
+                }
+                Ok(json!(MessageJson::from(&message)))
+            }
+            "Filecoin.ChainGetMessage" => {
+                let cid = params[0]["/"].as_str().unwrap_or_default();
+                let message = self
+                    .messages
+                    .get(cid)
+                    .ok_or_else(|| anyhow::anyhow!("message {} not found", cid))?;
+                Ok(json!(MessageJson::from(message)))
+            }
+            "Filecoin.MpoolPush" => {
+                let signed: SignedMessage =
+                    serde_json::from_value::<SignedMessageJson>(params[0].clone())?.try_into()?;
+                let cid = signed.cid()?;
+                let message = signed.message.clone();
+                self.push(signed)?;
+                self.messages.insert(cid.to_string(), message);
+                Ok(json!(CidJson::from(&cid)))
+            }
+            "Filecoin.StateWaitMsg" => {
+                let mut cid = params[0]["/"].as_str().unwrap_or_default().to_string();
+                if !self.executed.contains_key(&cid) {
+                    self.mine();
+                }
+                while let Some(replacement) = self.replaced.get(&cid) {
+                    cid = replacement.clone();
+                }
+                let (height, exit_code) = *self
+                    .executed
+                    .get(&cid)
+                    .ok_or_else(|| anyhow::anyhow!("message {} not found", cid))?;
+                Ok(json!({
+                    "Message": {"/": cid},
+                    "Receipt": {"ExitCode": exit_code, "Return": null, "GasUsed": DEFAULT_GAS_LIMIT / 2},
+                    "ReturnDec": null,
+                    "TipSet": [{"/": format!("bafy2bzacetipset{}", height)}],
+                    "Height": height,
+                }))
+            }
+            _ => Err(anyhow::anyhow!("method not found")),
+        }
+    }
+}
+
+/// Fake Lotus node for tests
+#[derive(Default)]
+pub(crate) struct MockLotus {
+    chain: Mutex<Chain>,
+}
+
+impl MockLotus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an account actor, returns its ID address
+    pub fn add_account(&self, key: Address, balance: TokenAmount) -> Address {
+        let mut chain = self.chain.lock().unwrap();
+        let index = chain.actor_or_create(&key);
+        chain.actors[index].balance = balance;
+        Address::new_id(chain.actors[index].id)
+    }
+
+    /// Messages waiting in the mpool
+    pub fn pending(&self) -> Vec<SignedMessage> {
+        self.chain.lock().unwrap().mpool.clone()
+    }
+
+    fn response(&self, method: &str, params: &Value) -> Value {
+        match self.chain.lock().unwrap().call(method, params) {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": 1, "result": result}),
+            Err(e) => {
+                json!({"jsonrpc": "2.0", "id": 1, "error": {"code": 1, "message": e.to_string()}})
+            }
+        }
+    }
+
+    /// Serve the API over HTTP on a free local port, returns the endpoint
+    #[cfg(feature = "server")]
+    pub fn serve_http(self: std::sync::Arc<Self>, token: &str) -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/rpc/v0", server.server_addr().to_ip().unwrap());
+        let authorization = format!("Bearer {}", token);
+
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let authorized = request
+                    .headers()
+                    .iter()
+                    .any(|h| h.field.equiv("Authorization") && h.value.as_str() == authorization);
+                if !authorized {
+                    let _ = request.respond(tiny_http::Response::empty(401));
+                    continue;
+                }
+                let mut body = vec![];
+                let _ = std::io::Read::read_to_end(request.as_reader(), &mut body);
+                let body: Value = serde_json::from_slice(&body).unwrap_or_default();
+                let method = body["method"].as_str().unwrap_or_default();
+                let response = self.response(method, &body["params"]);
+                let _ = request.respond(tiny_http::Response::from_string(response.to_string()));
+            }
+        });
+        endpoint
+    }
+}
+
+impl RpcTransport for &MockLotus {
+    fn call(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        crate::signer::remote::parse_response(method, self.response(method, &params))
+    }
+}
@@ -0,0 +1,9 @@
+pub mod client;
+#[cfg(test)]
+pub(crate) mod mock;
+pub mod server;
+
+#[cfg(feature = "rpc")]
+pub use crate::signer::remote::HttpTransport;
+pub use crate::signer::remote::{parse_response, RpcTransport};
+pub use client::LotusClient;
@@ -0,0 +1,661 @@
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use serde_json::{json, Value};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+use crate::{
+    helpers::accounts::parse_private_key_string,
+    models::{
+        account::{FlairAccount, FlairAddress},
+        wallet::{FlairWallet, Keystore},
+    },
+    policy::PolicyEngine,
+    signer::{
+        remote::{MsgMetaJson, SignatureJson},
+        MsgMeta, MsgType, Signer,
+    },
+    types::WalletType,
+};
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// Lotus reports handler errors with code 1
+const HANDLER_ERROR: i64 = 1;
+const UNAUTHORIZED: i64 = 401;
+
+// Largest request body accepted, messages with big params stay well below it
+#[cfg(feature = "server")]
+const MAX_BODY: u64 = 1 << 20;
+
+/// API token permissions, following the Lotus ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Permission {
+    /// create, list and query keys
+    Write,
+    /// sign with the keys
+    Sign,
+    /// export, import and delete keys
+    Admin,
+}
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl ToString) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::new(HANDLER_ERROR, e)
+    }
+}
+
+/// Lotus remote wallet (`lotus daemon --wallet-api`) backed by a FlairWallet.
+///
+/// Requests need an `Authorization: Bearer <token>` header with a token
+/// registered through `add_token`.
+pub struct WalletServer {
+    wallet: Mutex<FlairWallet>,
+    keystore: Option<Keystore>,
+    tokens: Vec<(Zeroizing<String>, Permission)>,
+    policy: Option<Arc<PolicyEngine>>,
+}
+
+impl WalletServer {
+    /// in memory wallet, keys are lost on exit
+    pub fn new(wallet: FlairWallet) -> Self {
+        Self {
+            wallet: Mutex::new(wallet),
+            keystore: None,
+            tokens: vec![],
+            policy: None,
+        }
+    }
+
+    /// wallet persisted in a keystore directory
+    pub fn with_keystore(keystore: Keystore) -> anyhow::Result<Self> {
+        let mut server = Self::new(keystore.load()?);
+        server.keystore = Some(keystore);
+        Ok(server)
+    }
+
+    pub fn add_token(&mut self, token: &str, permission: Permission) {
+        self.tokens
+            .push((Zeroizing::new(token.to_string()), permission));
+    }
+
+    /// every signing request has to be approved by the policy
+    pub fn set_policy(&mut self, policy: Arc<PolicyEngine>) {
+        self.policy = Some(policy);
+    }
+
+    fn authorize(&self, authorization: Option<&str>) -> Option<Permission> {
+        let token = authorization?.strip_prefix("Bearer ")?.trim();
+        self.tokens
+            .iter()
+            .filter(|(t, _)| bool::from(t.as_bytes().ct_eq(token.as_bytes())))
+            .map(|(_, p)| *p)
+            .max()
+    }
+
+    /// Handle a JSON-RPC request body, returning the HTTP status and response
+    pub fn handle(&self, authorization: Option<&str>, body: &[u8]) -> (u16, Value) {
+        let request: Value = match serde_json::from_slice(body) {
+            Ok(request) => request,
+            Err(e) => {
+                return (
+                    400,
+                    error_response(Value::Null, RpcError::new(PARSE_ERROR, e)),
+                )
+            }
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+
+        let method = match request.get("method").and_then(|m| m.as_str()) {
+            Some(method) => method,
+            None => {
+                let error = RpcError::new(INVALID_REQUEST, "missing method");
+                return (400, error_response(id, error));
+            }
+        };
+        let params = match request.get("params") {
+            Some(Value::Array(params)) => params.clone(),
+            None | Some(Value::Null) => vec![],
+            Some(_) => {
+                let error = RpcError::new(INVALID_PARAMS, "params must be an array");
+                return (400, error_response(id, error));
+            }
+        };
+
+        let required = match required_permission(method) {
+            Some(required) => required,
+            None => {
+                let error =
+                    RpcError::new(METHOD_NOT_FOUND, format!("method '{}' not found", method));
+                return (200, error_response(id, error));
+            }
+        };
+        match self.authorize(authorization) {
+            Some(permission) if permission >= required => {}
+            Some(_) => {
+                let error = RpcError::new(
+                    UNAUTHORIZED,
+                    format!("missing permission to invoke '{}'", method),
+                );
+                return (403, error_response(id, error));
+            }
+            None => {
+                let error = RpcError::new(UNAUTHORIZED, "unauthorized");
+                return (401, error_response(id, error));
+            }
+        }
+
+        match self.call(method, &params) {
+            Ok(result) => (200, json!({"jsonrpc": "2.0", "id": id, "result": result})),
+            Err(error) => (200, error_response(id, error)),
+        }
+    }
+
+    fn call(&self, method: &str, params: &[Value]) -> Result<Value, RpcError> {
+        let mut wallet = self.wallet.lock().expect("wallet lock poisoned");
+
+        match method {
+            "Filecoin.WalletNew" => {
+                let wallet_type = match param_str(params, 0)? {
+                    "secp256k1" => WalletType::Secp256k1,
+                    "bls" => WalletType::Bls,
+                    t => {
+                        return Err(RpcError::new(
+                            HANDLER_ERROR,
+                            format!("unsupported key type: {}", t),
+                        ))
+                    }
+                };
+                let account = FlairAccount::generate(wallet_type)?;
+                let address = account.display();
+                self.persist(&mut wallet, account)?;
+                Ok(json!(address))
+            }
+            "Filecoin.WalletHas" => {
+                let address = param_address(params, 0)?;
+                Ok(json!(wallet.has(&address)))
+            }
+            "Filecoin.WalletList" => {
+                let addresses: Vec<String> =
+                    wallet.list()?.into_iter().map(|a| a.to_string()).collect();
+                Ok(json!(addresses))
+            }
+            "Filecoin.WalletSign" => {
+                let address = param_address(params, 0)?;
+                let data = base64::decode(param_str(params, 1)?)
+                    .map_err(|e| RpcError::new(INVALID_PARAMS, e))?;
+                let meta: MsgMeta = match params.get(2) {
+                    Some(meta) if !meta.is_null() => {
+                        serde_json::from_value::<MsgMetaJson>(meta.clone())
+                            .map_err(|e| RpcError::new(INVALID_PARAMS, e))?
+                            .into()
+                    }
+                    _ => MsgMeta::unknown(),
+                };
+
+                let account = wallet
+                    .get(&address)
+                    .ok_or_else(|| RpcError::new(HANDLER_ERROR, "key not found"))?;
+                check_meta(&address, &data, &meta)?;
+                let approval = match &self.policy {
+                    Some(policy) => Some(policy.authorize(&address, &data, &meta)?),
+                    None => None,
+                };
+                let signature = account.sign_bytes(&data, &meta).inspect_err(|_| {
+                    if let (Some(policy), Some(approval)) = (&self.policy, approval) {
+                        policy.release(approval);
+                    }
+                })?;
+                Ok(json!(SignatureJson::from(&signature)))
+            }
+            "Filecoin.WalletExport" => {
+                let address = param_address(params, 0)?;
+                let account = wallet
+                    .get(&address)
+                    .ok_or_else(|| RpcError::new(HANDLER_ERROR, "key not found"))?;
+                let private = account
+                    .private
+                    .as_ref()
+                    .ok_or_else(|| RpcError::new(HANDLER_ERROR, "key not found"))?;
+                Ok(serde_json::to_value(private).map_err(anyhow::Error::from)?)
+            }
+            "Filecoin.WalletImport" => {
+                let key_info = params
+                    .first()
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, "missing key info"))?;
+                let key_info = Zeroizing::new(key_info.to_string());
+                let account = parse_private_key_string(&key_info)?;
+                let address = account.display();
+                self.persist(&mut wallet, account)?;
+                Ok(json!(address))
+            }
+            "Filecoin.WalletDelete" => {
+                let address = param_address(params, 0)?;
+                if let Some(keystore) = &self.keystore {
+                    keystore.delete(&address)?;
+                }
+                wallet.remove(&address);
+                Ok(Value::Null)
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("method '{}' not found", method),
+            )),
+        }
+    }
+
+    /// keys are written to the keystore before being usable
+    fn persist(&self, wallet: &mut FlairWallet, account: FlairAccount) -> anyhow::Result<()> {
+        if let Some(keystore) = &self.keystore {
+            keystore.put(&account)?;
+        }
+        wallet.add(account);
+        Ok(())
+    }
+
+    /// Serve JSON-RPC over HTTP on `addr` (e.g. "127.0.0.1:1777"), at `/rpc/v0`
+    #[cfg(feature = "server")]
+    pub fn serve(&self, addr: &str) -> anyhow::Result<()> {
+        let server = tiny_http::Server::http(addr).map_err(|e| anyhow::anyhow!("{}", e))?;
+        self.run(&server);
+        Ok(())
+    }
+
+    // A broken connection only fails its own request
+    #[cfg(feature = "server")]
+    fn run(&self, server: &tiny_http::Server) {
+        for request in server.incoming_requests() {
+            let url = request.url().to_string();
+            if let Err(e) = self.respond(request) {
+                eprintln!("failed to answer request to {}: {}", url, e);
+            }
+        }
+    }
+
+    #[cfg(feature = "server")]
+    fn respond(&self, mut request: tiny_http::Request) -> std::io::Result<()> {
+        use std::io::Read;
+
+        if request.method() != &tiny_http::Method::Post || !request.url().starts_with("/rpc/v0") {
+            return request.respond(tiny_http::Response::empty(404));
+        }
+        if request.body_length().unwrap_or(0) as u64 > MAX_BODY {
+            return request.respond(tiny_http::Response::empty(413));
+        }
+
+        let authorization = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Authorization"))
+            .map(|h| h.value.as_str().to_string());
+        let mut body = vec![];
+        request
+            .as_reader()
+            .take(MAX_BODY + 1)
+            .read_to_end(&mut body)?;
+        if body.len() as u64 > MAX_BODY {
+            return request.respond(tiny_http::Response::empty(413));
+        }
+
+        let (status, response) = self.handle(authorization.as_deref(), &body);
+        let response = tiny_http::Response::from_string(response.to_string())
+            .with_status_code(status)
+            .with_header(
+                tiny_http::Header::from_bytes("Content-Type", "application/json")
+                    .expect("valid header"),
+            );
+        request.respond(response)
+    }
+}
+
+fn required_permission(method: &str) -> Option<Permission> {
+    match method {
+        "Filecoin.WalletNew" | "Filecoin.WalletHas" | "Filecoin.WalletList" => {
+            Some(Permission::Write)
+        }
+        "Filecoin.WalletSign" => Some(Permission::Sign),
+        "Filecoin.WalletExport" | "Filecoin.WalletImport" | "Filecoin.WalletDelete" => {
+            Some(Permission::Admin)
+        }
+        _ => None,
+    }
+}
+
+/// the bytes must be what the meta describes, and messages must come from the signing address
+fn check_meta(address: &FlairAddress, data: &[u8], meta: &MsgMeta) -> Result<(), RpcError> {
+    meta.check(data)?;
+    if meta.msg_type == MsgType::ChainMsg {
+        let message = meta.message()?;
+        if message.from.to_bytes() != address.to_bytes() {
+            return Err(RpcError::new(
+                HANDLER_ERROR,
+                format!(
+                    "message from {} can't be signed by {}",
+                    message.from, address
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn param_str(params: &[Value], index: usize) -> Result<&str, RpcError> {
+    params.get(index).and_then(|p| p.as_str()).ok_or_else(|| {
+        RpcError::new(
+            INVALID_PARAMS,
+            format!("expected a string parameter at {}", index),
+        )
+    })
+}
+
+fn param_address(params: &[Value], index: usize) -> Result<FlairAddress, RpcError> {
+    FlairAddress::from_str(param_str(params, index)?).map_err(|e| RpcError::new(INVALID_PARAMS, e))
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": error.code, "message": error.message},
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use fvm_ipld_encoding::Cbor;
+    use fvm_shared::address::Address;
+
+    use super::*;
+    use crate::{
+        signer::remote::{parse_response, RemoteSigner, RpcTransport},
+        MessageBuilder,
+    };
+
+    fn server() -> WalletServer {
+        let mut server = WalletServer::new(FlairWallet::new());
+        server.add_token("write-token", Permission::Write);
+        server.add_token("sign-token", Permission::Sign);
+        server.add_token("admin-token", Permission::Admin);
+        server
+    }
+
+    fn call(server: &WalletServer, token: &str, method: &str, params: Value) -> (u16, Value) {
+        let body = json!({"jsonrpc": "2.0", "id": 7, "method": method, "params": params});
+        let authorization = format!("Bearer {}", token);
+        server.handle(Some(&authorization), body.to_string().as_bytes())
+    }
+
+    /// Lotus talking to the server
+    struct Client<'a>(&'a WalletServer, &'a str);
+
+    impl RpcTransport for Client<'_> {
+        fn call(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+            let (_, response) = call(self.0, self.1, method, params);
+            parse_response(method, response)
+        }
+    }
+
+    #[test]
+    fn test_wallet_api() {
+        let server = server();
+        let (status, response) = call(&server, "write-token", "Filecoin.WalletNew", json!(["bls"]));
+        assert_eq!(status, 200);
+        assert_eq!(response["id"], 7);
+        let bls = response["result"].as_str().unwrap().to_string();
+        assert!(bls.starts_with("f3"));
+
+        let secp = Client(&server, "write-token")
+            .call("Filecoin.WalletNew", json!(["secp256k1"]))
+            .unwrap();
+        let list = Client(&server, "write-token")
+            .call("Filecoin.WalletList", json!([]))
+            .unwrap();
+        assert_eq!(list, json!([bls, secp]));
+
+        // Lotus may use testnet addresses
+        let testnet = bls.replacen('f', "t", 1);
+        let has = Client(&server, "write-token")
+            .call("Filecoin.WalletHas", json!([testnet]))
+            .unwrap();
+        assert_eq!(has, json!(true));
+
+        // export / delete / import round trip
+        let admin = Client(&server, "admin-token");
+        let key_info = admin.call("Filecoin.WalletExport", json!([bls])).unwrap();
+        assert_eq!(key_info["Type"], "bls");
+        admin.call("Filecoin.WalletDelete", json!([bls])).unwrap();
+        assert_eq!(
+            admin.call("Filecoin.WalletHas", json!([bls])).unwrap(),
+            json!(false)
+        );
+        assert_eq!(
+            admin
+                .call("Filecoin.WalletImport", json!([key_info]))
+                .unwrap(),
+            json!(bls)
+        );
+
+        let (_, response) = call(&server, "admin-token", "Filecoin.ChainHead", json!([]));
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_wallet_auth() {
+        let server = server();
+        let body =
+            json!({"jsonrpc": "2.0", "id": 1, "method": "Filecoin.WalletList", "params": []});
+        let (status, _) = server.handle(None, body.to_string().as_bytes());
+        assert_eq!(status, 401);
+        let (status, _) = call(&server, "wrong-token", "Filecoin.WalletList", json!([]));
+        assert_eq!(status, 401);
+
+        let (status, response) = call(
+            &server,
+            "sign-token",
+            "Filecoin.WalletNew",
+            json!(["secp256k1"]),
+        );
+        assert_eq!(status, 200);
+        let address = response["result"].clone();
+
+        let (status, response) = call(
+            &server,
+            "sign-token",
+            "Filecoin.WalletExport",
+            json!([address]),
+        );
+        assert_eq!(status, 403);
+        assert!(response.get("result").is_none());
+        let (status, _) = call(
+            &server,
+            "write-token",
+            "Filecoin.WalletSign",
+            json!([address, "", null]),
+        );
+        assert_eq!(status, 403);
+
+        let (status, _) = server.handle(Some("Bearer admin-token"), b"{not json");
+        assert_eq!(status, 400);
+    }
+
+    #[test]
+    fn test_wallet_sign() {
+        let server = server();
+        let address = Client(&server, "write-token")
+            .call("Filecoin.WalletNew", json!(["secp256k1"]))
+            .unwrap();
+        let from = Address::from_str(address.as_str().unwrap()).unwrap();
+
+        // the remote signer is what Lotus does with --wallet-api
+        let signer = RemoteSigner::new(Client(&server, "sign-token"), from.into()).unwrap();
+        let signed = MessageBuilder::new(from, Address::new_id(1000))
+            .nonce(3)
+            .sign(&signer)
+            .unwrap();
+        assert_eq!(signed.signature.bytes().len(), 65);
+
+        // CID of another message
+        let other = MessageBuilder::new(from, Address::new_id(1001)).build();
+        let meta = MsgMetaJson::from(&MsgMeta::chain_msg(signed.message.marshal_cbor().unwrap()));
+        let (_, response) = call(
+            &server,
+            "sign-token",
+            "Filecoin.WalletSign",
+            json!([
+                address,
+                base64::encode(other.cid().unwrap().to_bytes()),
+                meta
+            ]),
+        );
+        assert!(response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("doesn't match"));
+
+        // message from another address
+        let foreign = MessageBuilder::new(Address::new_id(5), Address::new_id(1000)).build();
+        let meta = MsgMetaJson::from(&MsgMeta::chain_msg(foreign.marshal_cbor().unwrap()));
+        let (_, response) = call(
+            &server,
+            "sign-token",
+            "Filecoin.WalletSign",
+            json!([
+                address,
+                base64::encode(foreign.cid().unwrap().to_bytes()),
+                meta
+            ]),
+        );
+        assert!(response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("can't be signed"));
+    }
+
+    #[test]
+    fn test_wallet_policy() {
+        let mut server = server();
+        let policy = serde_json::from_value(json!({"max_value": "1000"})).unwrap();
+        server.set_policy(Arc::new(PolicyEngine::new(&policy).unwrap()));
+        let address = Client(&server, "write-token")
+            .call("Filecoin.WalletNew", json!(["bls"]))
+            .unwrap();
+        let from = Address::from_str(address.as_str().unwrap()).unwrap();
+
+        let signer = RemoteSigner::new(Client(&server, "sign-token"), from.into()).unwrap();
+        let send = |value: u64| {
+            MessageBuilder::new(from, Address::new_id(1000))
+                .value(fvm_shared::econ::TokenAmount::from_atto(value))
+                .sign(&signer)
+        };
+        assert!(send(1000).is_ok());
+        let err = send(1001).unwrap_err();
+        assert!(err.to_string().contains("per message cap"));
+        assert!(signer.sign_bytes(b"data", &MsgMeta::unknown()).is_err());
+    }
+
+    #[test]
+    fn test_wallet_keystore() {
+        let dir = std::env::temp_dir().join(format!("fil-wallet-server-{}", std::process::id()));
+        let mut server = WalletServer::with_keystore(Keystore::open(&dir).unwrap()).unwrap();
+        server.add_token("admin-token", Permission::Admin);
+        let address = Client(&server, "admin-token")
+            .call("Filecoin.WalletNew", json!(["bls"]))
+            .unwrap();
+
+        let mut restarted = WalletServer::with_keystore(Keystore::open(&dir).unwrap()).unwrap();
+        restarted.add_token("admin-token", Permission::Admin);
+        let client = Client(&restarted, "admin-token");
+        assert_eq!(
+            client.call("Filecoin.WalletList", json!([])).unwrap(),
+            json!([address])
+        );
+
+        client
+            .call("Filecoin.WalletDelete", json!([address]))
+            .unwrap();
+        assert!(Keystore::open(&dir)
+            .unwrap()
+            .load()
+            .unwrap()
+            .list()
+            .unwrap()
+            .is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn test_serve_survives_bad_requests() {
+        use std::io::{Read, Write};
+        use std::net::{Shutdown, TcpStream};
+
+        let http = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let addr = http.server_addr().to_ip().unwrap();
+        let server = Arc::new(server());
+        let serving = server.clone();
+        std::thread::spawn(move || serving.run(&http));
+
+        let send = |head: &str, body: &[u8], close: bool| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(head.as_bytes()).unwrap();
+            stream.write_all(body).unwrap();
+            if close {
+                stream.shutdown(Shutdown::Both).unwrap();
+                return String::new();
+            }
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let post = |headers: &str| {
+            format!(
+                "POST /rpc/v0 HTTP/1.1\r\nHost: wallet\r\nConnection: close\r\nAuthorization: Bearer write-token\r\n{}\r\n",
+                headers
+            )
+        };
+
+        // oversized bodies, announced or streamed
+        let response = send(&post("Content-Length: 2000000\r\n"), b"", false);
+        assert!(response.starts_with("HTTP/1.1 413"));
+        let mut chunked = format!("{:x}\r\n", MAX_BODY + 1).into_bytes();
+        chunked.extend(vec![b' '; MAX_BODY as usize + 1]);
+        chunked.extend(b"\r\n0\r\n\r\n");
+        let response = send(&post("Transfer-Encoding: chunked\r\n"), &chunked, false);
+        assert!(response.starts_with("HTTP/1.1 413"));
+
+        // a client going away mid-request
+        send(&post("Content-Length: 100\r\n"), b"{\"jsonrpc\"", true);
+
+        let body =
+            json!({"jsonrpc": "2.0", "id": 1, "method": "Filecoin.WalletList", "params": []})
+                .to_string();
+        let response = send(
+            &post(&format!("Content-Length: {}\r\n", body.len())),
+            body.as_bytes(),
+            false,
+        );
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with(r#""result":[]}"#));
+    }
+}
@@ -0,0 +1,95 @@
+use fvm_shared::{address::Address, crypto::signature::Signature};
+
+use super::{secp_recoverable_signature, MsgMeta, Signer};
+use crate::{
+    models::account::{blake2b_256, FlairAddress},
+    types::WalletType,
+};
+
+/// Session on a PKCS#11 token holding a secp256k1 key pair
+/// (CKK_EC with the secp256k1 curve parameters).
+pub trait Pkcs11Session {
+    /// uncompressed SEC1 public key (CKA_EC_POINT without the DER wrapping)
+    fn public_key(&self) -> anyhow::Result<Vec<u8>>;
+
+    /// raw 64 byte r || s signature of a 32 byte digest (CKM_ECDSA)
+    fn sign_ecdsa(&self, digest: &[u8; 32]) -> anyhow::Result<Vec<u8>>;
+}
+
+/// Signer keeping the secp256k1 key inside an HSM, the key never leaves the device.
+pub struct HsmSigner<S: Pkcs11Session> {
+    session: S,
+    public_key: libsecp256k1::PublicKey,
+    address: FlairAddress,
+}
+
+impl<S: Pkcs11Session> HsmSigner<S> {
+    pub fn new(session: S) -> anyhow::Result<Self> {
+        let public_key = libsecp256k1::PublicKey::parse_slice(&session.public_key()?, None)
+            .map_err(|e| anyhow::anyhow!("invalid HSM public key: {:?}", e))?;
+        let address = Address::new_secp256k1(&public_key.serialize())?.into();
+        Ok(Self {
+            session,
+            public_key,
+            address,
+        })
+    }
+}
+
+impl<S: Pkcs11Session> Signer for HsmSigner<S> {
+    fn address(&self) -> FlairAddress {
+        self.address
+    }
+
+    fn wallet_type(&self) -> WalletType {
+        WalletType::Secp256k1
+    }
+
+    fn sign_bytes(&self, data: &[u8], _meta: &MsgMeta) -> anyhow::Result<Signature> {
+        let digest = blake2b_256(data);
+        let raw = self.session.sign_ecdsa(&digest)?;
+        let signature = secp_recoverable_signature(&digest, &raw, &self.public_key)?;
+        Ok(Signature::new_secp256k1(signature))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::account::FlairAccount;
+
+    /// Software token, returns high-S signatures like many HSMs do
+    struct SoftToken(libsecp256k1::SecretKey);
+
+    impl Pkcs11Session for SoftToken {
+        fn public_key(&self) -> anyhow::Result<Vec<u8>> {
+            Ok(libsecp256k1::PublicKey::from_secret_key(&self.0)
+                .serialize()
+                .to_vec())
+        }
+
+        fn sign_ecdsa(&self, digest: &[u8; 32]) -> anyhow::Result<Vec<u8>> {
+            let (sig, _) = libsecp256k1::sign(&libsecp256k1::Message::parse(digest), &self.0);
+            let mut s = sig.s;
+            s = -s;
+            let high_s = libsecp256k1::Signature { r: sig.r, s };
+            Ok(high_s.serialize().to_vec())
+        }
+    }
+
+    #[test]
+    fn test_hsm_signer() {
+        let key = [9u8; 32];
+        let account =
+            FlairAccount::from_private(WalletType::Secp256k1, key.to_vec().into()).unwrap();
+        let signer =
+            HsmSigner::new(SoftToken(libsecp256k1::SecretKey::parse(&key).unwrap())).unwrap();
+        assert_eq!(signer.address(), account.get_address());
+
+        let signature = signer.sign_bytes(b"message", &MsgMeta::unknown()).unwrap();
+        assert_eq!(
+            signature,
+            account.sign_bytes(b"message", &MsgMeta::unknown()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,396 @@
+use fvm_shared::{address::Address, crypto::signature::Signature};
+
+use super::{secp_recoverable_signature, MsgMeta, MsgType, Signer};
+use crate::{
+    models::account::{blake2b_256, FlairAddress},
+    types::WalletType,
+};
+
+// Filecoin Ledger app (https://github.com/Zondax/ledger-filecoin) protocol constants
+const CLA: u8 = 0x06;
+const INS_GET_VERSION: u8 = 0x00;
+const INS_GET_ADDR_SECP256K1: u8 = 0x01;
+const INS_SIGN_SECP256K1: u8 = 0x02;
+
+const PAYLOAD_INIT: u8 = 0x00;
+const PAYLOAD_ADD: u8 = 0x01;
+const PAYLOAD_LAST: u8 = 0x02;
+
+const CHUNK_SIZE: usize = 250;
+const PUBLIC_KEY_LEN: usize = 65;
+const SIGNATURE_LEN: usize = 65;
+
+const HARDENED: u32 = 0x8000_0000;
+const FIL_COIN_TYPE: u32 = 461;
+
+/// Success status word
+pub const APDU_OK: u16 = 0x9000;
+
+/// Command sent to the device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApduCommand {
+    pub cla: u8,
+    pub ins: u8,
+    pub p1: u8,
+    pub p2: u8,
+    pub data: Vec<u8>,
+}
+
+impl ApduCommand {
+    /// wire format: CLA INS P1 P2 Lc DATA
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = vec![self.cla, self.ins, self.p1, self.p2, self.data.len() as u8];
+        out.extend_from_slice(&self.data);
+        out
+    }
+}
+
+/// Answer of the device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApduAnswer {
+    pub data: Vec<u8>,
+    pub retcode: u16,
+}
+
+impl ApduAnswer {
+    /// parse wire format: DATA SW1 SW2
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.len() < 2 {
+            return Err(anyhow::anyhow!("APDU answer too short"));
+        }
+        let (data, status) = bytes.split_at(bytes.len() - 2);
+        Ok(Self {
+            data: data.to_vec(),
+            retcode: u16::from_be_bytes([status[0], status[1]]),
+        })
+    }
+}
+
+/// Transport to a Ledger device (USB HID, Bluetooth, Speculos emulator, ...)
+pub trait ApduTransport {
+    fn exchange(&self, command: &ApduCommand) -> anyhow::Result<ApduAnswer>;
+}
+
+/// Signer backed by the Filecoin app of a Ledger device, only secp256k1 keys are supported.
+pub struct LedgerSigner<T: ApduTransport> {
+    transport: T,
+    path: [u32; 5],
+    public_key: Vec<u8>,
+    address: FlairAddress,
+}
+
+impl<T: ApduTransport> LedgerSigner<T> {
+    /// connect to the key at m/44'/461'/account'/0/index
+    pub fn new(transport: T, account: u32, index: u32) -> anyhow::Result<Self> {
+        let path = [
+            44 | HARDENED,
+            FIL_COIN_TYPE | HARDENED,
+            account | HARDENED,
+            0,
+            index,
+        ];
+        let (public_key, address) = get_address(&transport, &path, false)?;
+        Ok(Self {
+            transport,
+            path,
+            public_key,
+            address,
+        })
+    }
+
+    /// app version (major, minor, patch)
+    pub fn version(&self) -> anyhow::Result<(u8, u8, u8)> {
+        let answer = exchange(&self.transport, INS_GET_VERSION, 0, vec![])?;
+        if answer.len() < 4 {
+            return Err(anyhow::anyhow!("invalid version answer"));
+        }
+        Ok((answer[1], answer[2], answer[3]))
+    }
+
+    /// display the address on the device for the user to compare
+    pub fn show_address(&self) -> anyhow::Result<FlairAddress> {
+        let (_, address) = get_address(&self.transport, &self.path, true)?;
+        Ok(address)
+    }
+
+    /// uncompressed secp256k1 public key
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+}
+
+impl<T: ApduTransport> Signer for LedgerSigner<T> {
+    fn address(&self) -> FlairAddress {
+        self.address
+    }
+
+    fn wallet_type(&self) -> WalletType {
+        WalletType::Secp256k1
+    }
+
+    /// The app signs the CBOR encoded message it displays, so only chain messages are supported.
+    fn sign_bytes(&self, data: &[u8], meta: &MsgMeta) -> anyhow::Result<Signature> {
+        if meta.msg_type != MsgType::ChainMsg {
+            return Err(anyhow::anyhow!(
+                "Ledger can only sign chain messages, got {:?}",
+                meta.msg_type
+            ));
+        }
+        meta.check(data)?;
+
+        exchange(
+            &self.transport,
+            INS_SIGN_SECP256K1,
+            PAYLOAD_INIT,
+            serialize_path(&self.path),
+        )?;
+        let chunks: Vec<&[u8]> = meta.extra.chunks(CHUNK_SIZE).collect();
+        let mut answer = vec![];
+        for (i, chunk) in chunks.iter().enumerate() {
+            let p1 = if i + 1 == chunks.len() {
+                PAYLOAD_LAST
+            } else {
+                PAYLOAD_ADD
+            };
+            answer = exchange(&self.transport, INS_SIGN_SECP256K1, p1, chunk.to_vec())?;
+        }
+
+        // answer: compact signature (r || s || v) followed by the DER signature
+        if answer.len() < SIGNATURE_LEN {
+            return Err(anyhow::anyhow!("invalid signature answer"));
+        }
+
+        // don't trust the device blindly, the signature must be from our key
+        let public_key = libsecp256k1::PublicKey::parse_slice(&self.public_key, None)
+            .map_err(|e| anyhow::anyhow!("invalid public key: {:?}", e))?;
+        let digest = blake2b_256(data);
+        let signature =
+            secp_recoverable_signature(&digest, &answer[..SIGNATURE_LEN - 1], &public_key)?;
+        Ok(Signature::new_secp256k1(signature))
+    }
+}
+
+fn serialize_path(path: &[u32; 5]) -> Vec<u8> {
+    path.iter().flat_map(|p| p.to_le_bytes()).collect()
+}
+
+fn exchange<T: ApduTransport>(
+    transport: &T,
+    ins: u8,
+    p1: u8,
+    data: Vec<u8>,
+) -> anyhow::Result<Vec<u8>> {
+    let command = ApduCommand {
+        cla: CLA,
+        ins,
+        p1,
+        p2: 0,
+        data,
+    };
+    let answer = transport.exchange(&command)?;
+    if answer.retcode != APDU_OK {
+        return Err(anyhow::anyhow!(
+            "Ledger returned error 0x{:04x}",
+            answer.retcode
+        ));
+    }
+    Ok(answer.data)
+}
+
+fn get_address<T: ApduTransport>(
+    transport: &T,
+    path: &[u32; 5],
+    confirm: bool,
+) -> anyhow::Result<(Vec<u8>, FlairAddress)> {
+    let answer = exchange(
+        transport,
+        INS_GET_ADDR_SECP256K1,
+        confirm as u8,
+        serialize_path(path),
+    )?;
+
+    // answer: public key (65) | address length (1) | address bytes | string length (1) | address string
+    let invalid = || anyhow::anyhow!("invalid address answer");
+    let public_key = answer.get(..PUBLIC_KEY_LEN).ok_or_else(invalid)?.to_vec();
+    let addr_len = *answer.get(PUBLIC_KEY_LEN).ok_or_else(invalid)? as usize;
+    let addr_bytes = answer
+        .get(PUBLIC_KEY_LEN + 1..PUBLIC_KEY_LEN + 1 + addr_len)
+        .ok_or_else(invalid)?;
+    let address = Address::from_bytes(addr_bytes)?;
+
+    // don't trust the device blindly
+    if address != Address::new_secp256k1(&public_key)? {
+        return Err(anyhow::anyhow!("address doesn't match the public key"));
+    }
+    Ok((public_key, address.into()))
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+
+    use fvm_ipld_encoding::Cbor;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::message::Message;
+    use libsecp256k1::{Message as SecpMessage, PublicKey, SecretKey};
+
+    use super::*;
+
+    /// Software implementation of the Filecoin app
+    struct MockLedger {
+        secret: SecretKey,
+        signing_secret: SecretKey,
+        payload: RefCell<Vec<u8>>,
+        commands: RefCell<Vec<ApduCommand>>,
+    }
+
+    impl MockLedger {
+        fn new() -> Self {
+            Self {
+                secret: SecretKey::parse(&[3u8; 32]).unwrap(),
+                signing_secret: SecretKey::parse(&[3u8; 32]).unwrap(),
+                payload: RefCell::new(vec![]),
+                commands: RefCell::new(vec![]),
+            }
+        }
+    }
+
+    impl ApduTransport for &MockLedger {
+        fn exchange(&self, command: &ApduCommand) -> anyhow::Result<ApduAnswer> {
+            // round trip through the wire format
+            let wire = command.serialize();
+            assert_eq!(wire[4] as usize, command.data.len());
+            self.commands.borrow_mut().push(command.clone());
+
+            let ok = |data: Vec<u8>| {
+                let mut bytes = data;
+                bytes.extend_from_slice(&APDU_OK.to_be_bytes());
+                ApduAnswer::from_bytes(&bytes)
+            };
+
+            match (command.cla, command.ins) {
+                (CLA, INS_GET_VERSION) => ok(vec![0, 0, 22, 4]),
+                (CLA, INS_GET_ADDR_SECP256K1) => {
+                    let public = PublicKey::from_secret_key(&self.secret).serialize();
+                    let address = Address::new_secp256k1(&public).unwrap();
+                    let mut data = public.to_vec();
+                    data.push(address.to_bytes().len() as u8);
+                    data.extend(address.to_bytes());
+                    let text = address.to_string();
+                    data.push(text.len() as u8);
+                    data.extend(text.as_bytes());
+                    ok(data)
+                }
+                (CLA, INS_SIGN_SECP256K1) => match command.p1 {
+                    PAYLOAD_INIT => {
+                        assert_eq!(command.data.len(), 20);
+                        self.payload.borrow_mut().clear();
+                        ok(vec![])
+                    }
+                    PAYLOAD_ADD => {
+                        self.payload.borrow_mut().extend(&command.data);
+                        ok(vec![])
+                    }
+                    _ => {
+                        self.payload.borrow_mut().extend(&command.data);
+                        let message: Message =
+                            fvm_ipld_encoding::from_slice(&self.payload.borrow()).unwrap();
+                        let cid = message.cid().unwrap();
+                        let digest = blake2b_256(&cid.to_bytes());
+                        let (sig, v) =
+                            libsecp256k1::sign(&SecpMessage::parse(&digest), &self.signing_secret);
+                        let mut data = sig.serialize().to_vec();
+                        data.push(v.serialize());
+                        data.extend(sig.serialize_der().as_ref());
+                        ok(data)
+                    }
+                },
+                _ => Ok(ApduAnswer {
+                    data: vec![],
+                    retcode: 0x6e00,
+                }),
+            }
+        }
+    }
+
+    fn message(from: Address, params: Vec<u8>) -> Message {
+        Message {
+            version: 0,
+            from,
+            to: Address::new_id(1000),
+            sequence: 1,
+            value: TokenAmount::from_atto(1000),
+            method_num: 0,
+            params: params.into(),
+            gas_limit: 1_000_000,
+            gas_fee_cap: TokenAmount::from_atto(100),
+            gas_premium: TokenAmount::from_atto(100),
+        }
+    }
+
+    #[test]
+    fn test_ledger_sign() {
+        let device = MockLedger::new();
+        let signer = LedgerSigner::new(&device, 0, 0).unwrap();
+        assert_eq!(signer.version().unwrap(), (0, 22, 4));
+        assert_eq!(signer.show_address().unwrap(), signer.address());
+
+        // large params need several chunks
+        let msg = message(signer.address().try_into().unwrap(), vec![7u8; 600]);
+        let cid = msg.cid().unwrap();
+        let meta = MsgMeta::chain_msg(msg.marshal_cbor().unwrap());
+        let signature = signer.sign_bytes(&cid.to_bytes(), &meta).unwrap();
+        assert_eq!(signature.bytes().len(), 65);
+
+        let digest = blake2b_256(&cid.to_bytes());
+        let recovered = libsecp256k1::recover(
+            &SecpMessage::parse(&digest),
+            &libsecp256k1::Signature::parse_standard_slice(&signature.bytes()[..64]).unwrap(),
+            &libsecp256k1::RecoveryId::parse(signature.bytes()[64]).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(&recovered.serialize()[..], signer.public_key());
+
+        let p1s: Vec<u8> = device
+            .commands
+            .borrow()
+            .iter()
+            .filter(|c| c.ins == INS_SIGN_SECP256K1)
+            .map(|c| c.p1)
+            .collect();
+        assert_eq!(
+            p1s,
+            vec![PAYLOAD_INIT, PAYLOAD_ADD, PAYLOAD_ADD, PAYLOAD_LAST]
+        );
+    }
+
+    #[test]
+    fn test_ledger_rejects_mismatched_payload() {
+        let device = MockLedger::new();
+        let signer = LedgerSigner::new(&device, 0, 0).unwrap();
+
+        let msg = message(signer.address().try_into().unwrap(), vec![]);
+        let other = message(signer.address().try_into().unwrap(), vec![1]);
+        let meta = MsgMeta::chain_msg(other.marshal_cbor().unwrap());
+        assert!(signer
+            .sign_bytes(&msg.cid().unwrap().to_bytes(), &meta)
+            .is_err());
+        assert!(signer
+            .sign_bytes(&msg.cid().unwrap().to_bytes(), &MsgMeta::unknown())
+            .is_err());
+    }
+
+    #[test]
+    fn test_ledger_rejects_foreign_signature() {
+        let mut device = MockLedger::new();
+        device.signing_secret = SecretKey::parse(&[4u8; 32]).unwrap();
+        let signer = LedgerSigner::new(&device, 0, 0).unwrap();
+
+        let msg = message(signer.address().try_into().unwrap(), vec![]);
+        let meta = MsgMeta::chain_msg(msg.marshal_cbor().unwrap());
+        let err = signer
+            .sign_bytes(&msg.cid().unwrap().to_bytes(), &meta)
+            .unwrap_err();
+        assert!(err.to_string().contains("doesn't match the public key"));
+    }
+}
@@ -0,0 +1,298 @@
+pub mod hsm;
+pub mod ledger;
+pub mod remote;
+
+use bls_signatures::{
+    PrivateKey as BlsPrivate, PublicKey as BlsPublic, Serialize as BlsSerialize,
+    Signature as BlsSignature,
+};
+use fvm_ipld_encoding::Cbor;
+use fvm_shared::{
+    address::{Address, Protocol},
+    crypto::signature::{Signature, SignatureType},
+    message::Message,
+};
+use libsecp256k1::{Message as SecpMessage, SecretKey as SecpPrivate};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::account::{blake2b_256, FlairAccount, FlairAddress},
+    types::WalletType,
+};
+
+/// What is being signed, following the Lotus wallet API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MsgType {
+    #[serde(rename = "unknown")]
+    Unknown,
+    /// message CID, `MsgMeta::extra` holds the CBOR encoded message
+    #[serde(rename = "message")]
+    ChainMsg,
+    /// CBOR encoded block header
+    #[serde(rename = "block")]
+    Block,
+    /// CBOR encoded storage deal proposal
+    #[serde(rename = "dealproposal")]
+    DealProposal,
+    #[serde(rename = "network_response")]
+    NetworkResponse,
+    #[serde(rename = "provider_deal_state")]
+    ProviderDealState,
+}
+
+/// Signing context passed along with the bytes to sign
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MsgMeta {
+    pub msg_type: MsgType,
+    pub extra: Vec<u8>,
+}
+
+impl MsgMeta {
+    pub fn unknown() -> Self {
+        Self {
+            msg_type: MsgType::Unknown,
+            extra: vec![],
+        }
+    }
+
+    /// signing a message CID, with the CBOR encoded message it belongs to
+    pub fn chain_msg(message_cbor: Vec<u8>) -> Self {
+        Self {
+            msg_type: MsgType::ChainMsg,
+            extra: message_cbor,
+        }
+    }
+
+    /// decode the message carried by a chain message meta
+    pub fn message(&self) -> anyhow::Result<Message> {
+        if self.msg_type != MsgType::ChainMsg {
+            return Err(anyhow::anyhow!(
+                "{:?} doesn't carry a message",
+                self.msg_type
+            ));
+        }
+        fvm_ipld_encoding::from_slice(&self.extra)
+            .map_err(|e| anyhow::anyhow!("invalid message: {}", e))
+    }
+
+    /// check the bytes to sign match what the meta describes, so that a
+    /// signer can't be tricked into signing something other than it was shown
+    pub fn check(&self, data: &[u8]) -> anyhow::Result<()> {
+        match self.msg_type {
+            MsgType::ChainMsg if self.message()?.cid()?.to_bytes() != data => {
+                return Err(anyhow::anyhow!("message doesn't match the CID to sign"));
+            }
+            MsgType::DealProposal if !self.extra.is_empty() && self.extra != data => {
+                return Err(anyhow::anyhow!(
+                    "deal proposal doesn't match the bytes to sign"
+                ));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Anything able to produce Filecoin signatures for an address: local accounts,
+/// hardware wallets, remote wallets or HSMs.
+pub trait Signer {
+    /// address the signatures belong to
+    fn address(&self) -> FlairAddress;
+
+    /// key type of the address
+    fn wallet_type(&self) -> WalletType;
+
+    /// sign bytes (a message CID for chain messages), secp256k1 signers hash them with blake2b-256 first
+    fn sign_bytes(&self, data: &[u8], meta: &MsgMeta) -> anyhow::Result<Signature>;
+}
+
+impl Signer for FlairAccount {
+    fn address(&self) -> FlairAddress {
+        self.get_address()
+    }
+
+    fn wallet_type(&self) -> WalletType {
+        self.get_type()
+    }
+
+    fn sign_bytes(&self, data: &[u8], _meta: &MsgMeta) -> anyhow::Result<Signature> {
+        let private_key = match &self.private {
+            Some(private_key) => private_key.as_bytes(),
+            None => return Err(anyhow::anyhow!("Not Authourized Account")),
+        };
+
+        match self.get_type() {
+            WalletType::Bls => {
+                let priv_key = BlsPrivate::from_bytes(private_key)?;
+                let sig = priv_key.sign(data).as_bytes();
+                Ok(Signature::new_bls(sig))
+            }
+            WalletType::Secp256k1 => {
+                let priv_key = SecpPrivate::parse_slice(private_key)?;
+                let msg_hash = blake2b_256(data);
+                let message = SecpMessage::parse(&msg_hash);
+                let (sig, recovery_id) = libsecp256k1::sign(&message, &priv_key);
+                let mut new_bytes = [0; 65];
+                new_bytes[..64].copy_from_slice(&sig.serialize());
+                new_bytes[64] = recovery_id.serialize();
+                Ok(Signature::new_secp256k1(new_bytes.to_vec()))
+            }
+        }
+    }
+}
+
+/// Check a signature of `data` made by `address`, secp256k1 signatures
+/// are checked by recovering the public key.
+pub fn verify(address: &Address, data: &[u8], signature: &Signature) -> anyhow::Result<()> {
+    match (address.protocol(), signature.signature_type()) {
+        (Protocol::BLS, SignatureType::BLS) => {
+            let public_key = BlsPublic::from_bytes(&address.payload_bytes())?;
+            let sig = BlsSignature::from_bytes(signature.bytes())?;
+            if !bls_signatures::verify_messages(&sig, &[data], &[public_key]) {
+                return Err(anyhow::anyhow!("invalid signature"));
+            }
+        }
+        (Protocol::Secp256k1, SignatureType::Secp256k1) => {
+            let bytes = signature.bytes();
+            if bytes.len() != 65 {
+                return Err(anyhow::anyhow!("invalid signature length: {}", bytes.len()));
+            }
+            let sig = libsecp256k1::Signature::parse_standard_slice(&bytes[..64])
+                .map_err(|e| anyhow::anyhow!("invalid signature: {:?}", e))?;
+            let recovery_id = libsecp256k1::RecoveryId::parse(bytes[64])
+                .map_err(|e| anyhow::anyhow!("invalid signature: {:?}", e))?;
+            let message = SecpMessage::parse(&blake2b_256(data));
+            let public_key = libsecp256k1::recover(&message, &sig, &recovery_id)
+                .map_err(|_| anyhow::anyhow!("invalid signature"))?;
+            let signer = Address::new_secp256k1(&public_key.serialize())?;
+            if signer.payload() != address.payload() {
+                return Err(anyhow::anyhow!("invalid signature"));
+            }
+        }
+        (protocol, sig_type) => {
+            return Err(anyhow::anyhow!(
+                "can't verify a {:?} signature for a {:?} address",
+                sig_type,
+                protocol
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Build a recoverable 65 byte secp256k1 signature (r || s || v) from a raw
+/// 64 byte ECDSA signature of `digest`, as produced by devices that don't
+/// return the recovery id.
+pub(crate) fn secp_recoverable_signature(
+    digest: &[u8; 32],
+    raw: &[u8],
+    public_key: &libsecp256k1::PublicKey,
+) -> anyhow::Result<Vec<u8>> {
+    let mut sig = libsecp256k1::Signature::parse_standard_slice(raw)
+        .map_err(|e| anyhow::anyhow!("invalid ECDSA signature: {:?}", e))?;
+    sig.normalize_s();
+
+    let message = SecpMessage::parse(digest);
+    for v in 0..4u8 {
+        let recovery_id = libsecp256k1::RecoveryId::parse(v).expect("recovery id < 4");
+        match libsecp256k1::recover(&message, &sig, &recovery_id) {
+            Ok(recovered) if &recovered == public_key => {
+                let mut out = sig.serialize().to_vec();
+                out.push(v);
+                return Ok(out);
+            }
+            _ => continue,
+        }
+    }
+    Err(anyhow::anyhow!("signature doesn't match the public key"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_account_signer() {
+        let in_bls = "7b2254797065223a22626c73222c22507269766174654b6579223a2270657341657756666d382f6f7a574c736b6f767a7464677a62566d73677657695a70506f346d53367269493d227d";
+        let account = FlairAccount::import(in_bls).unwrap();
+        let cid: cid::Cid = "bafy2bzacec6m3lsogelnttnn4ck7dr35zpyuynqaliiqycx4zraqmqmjebc36"
+            .parse()
+            .unwrap();
+
+        let signer: &dyn Signer = &account;
+        assert_eq!(signer.wallet_type(), WalletType::Bls);
+        let signature = signer
+            .sign_bytes(&cid.to_bytes(), &MsgMeta::unknown())
+            .unwrap();
+        assert_eq!(
+            base64::encode(signature.bytes()),
+            "iKKZhRQkg2qQ8fWWIMFFHjJSAWaOgIkvVSAJ4aZ0pL2fPeMOsSzOoqqfE9mj/qtCDAfeDWCgvVzTwVvSNNt5jL+LPwSSGinfHtO01urO0vuOq2EUCVcgH57ftzf5SAJT"
+        );
+
+        let watch_only = FlairAccount::from_address(WalletType::Bls, account.get_address());
+        assert!(watch_only
+            .sign_bytes(&cid.to_bytes(), &MsgMeta::unknown())
+            .is_err());
+    }
+
+    #[test]
+    fn test_msg_meta_check() {
+        let message = crate::MessageBuilder::new(
+            fvm_shared::address::Address::new_id(1),
+            fvm_shared::address::Address::new_id(2),
+        )
+        .build();
+        let cid = message.cid().unwrap().to_bytes();
+        let meta = MsgMeta::chain_msg(message.marshal_cbor().unwrap());
+        assert_eq!(meta.message().unwrap(), message);
+        assert!(meta.check(&cid).is_ok());
+        assert!(meta.check(b"something else").is_err());
+        assert!(MsgMeta::chain_msg(vec![]).check(&cid).is_err());
+        assert!(MsgMeta::unknown().check(b"anything").is_ok());
+
+        let proposal = MsgMeta {
+            msg_type: MsgType::DealProposal,
+            extra: b"proposal".to_vec(),
+        };
+        assert!(proposal.check(b"proposal").is_ok());
+        assert!(proposal.check(b"other").is_err());
+    }
+
+    #[test]
+    fn test_verify() {
+        for wallet_type in [WalletType::Secp256k1, WalletType::Bls] {
+            let account = FlairAccount::generate(wallet_type).unwrap();
+            let address: Address = account.get_address().try_into().unwrap();
+            let signature = account.sign_bytes(b"data", &MsgMeta::unknown()).unwrap();
+            assert!(verify(&address, b"data", &signature).is_ok());
+            assert!(verify(&address, b"other", &signature).is_err());
+
+            let other = FlairAccount::generate(wallet_type).unwrap();
+            assert!(verify(
+                &other.get_address().try_into().unwrap(),
+                b"data",
+                &signature
+            )
+            .is_err());
+        }
+        let secp = FlairAccount::generate(WalletType::Secp256k1).unwrap();
+        let signature = secp.sign_bytes(b"data", &MsgMeta::unknown()).unwrap();
+        assert!(verify(&Address::new_id(1), b"data", &signature).is_err());
+    }
+
+    #[test]
+    fn test_secp_recoverable_signature() {
+        let secret = SecpPrivate::parse(&[7u8; 32]).unwrap();
+        let public = libsecp256k1::PublicKey::from_secret_key(&secret);
+        let digest = blake2b_256(b"hello");
+        let (sig, recovery_id) = libsecp256k1::sign(&SecpMessage::parse(&digest), &secret);
+
+        let out = secp_recoverable_signature(&digest, &sig.serialize(), &public).unwrap();
+        assert_eq!(&out[..64], &sig.serialize()[..]);
+        assert_eq!(out[64], recovery_id.serialize());
+
+        let other =
+            libsecp256k1::PublicKey::from_secret_key(&SecpPrivate::parse(&[8u8; 32]).unwrap());
+        assert!(secp_recoverable_signature(&digest, &sig.serialize(), &other).is_err());
+    }
+}
@@ -0,0 +1,310 @@
+use fvm_shared::crypto::signature::{Signature, SignatureType};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{MsgMeta, Signer};
+use crate::{
+    models::{account::FlairAddress, address::AddressProtocol},
+    types::WalletType,
+};
+
+/// JSON-RPC 2.0 transport to a Lotus compatible API
+pub trait RpcTransport {
+    /// call `method` (e.g. "Filecoin.WalletSign") and return the `result` field
+    fn call(&self, method: &str, params: Value) -> anyhow::Result<Value>;
+}
+
+/// JSON-RPC over HTTP(S) POST, authenticated with a Lotus API token
+#[cfg(feature = "rpc")]
+pub struct HttpTransport {
+    endpoint: String,
+    token: Option<String>,
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "rpc")]
+impl HttpTransport {
+    /// `endpoint` like http://127.0.0.1:1234/rpc/v0
+    pub fn new(endpoint: &str, token: Option<String>) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            token,
+            agent: ureq::AgentBuilder::new()
+                .timeout(std::time::Duration::from_secs(60))
+                .build(),
+        }
+    }
+}
+
+#[cfg(feature = "rpc")]
+impl RpcTransport for HttpTransport {
+    fn call(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        let mut request = self.agent.post(&self.endpoint);
+        if let Some(token) = &self.token {
+            request = request.set("Authorization", &format!("Bearer {}", token));
+        }
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        let response: Value = match request.send_json(body) {
+            Ok(response) => response.into_json()?,
+            // JSON-RPC errors may come with a non-200 status
+            Err(ureq::Error::Status(code, response)) => response
+                .into_json()
+                .map_err(|_| anyhow::anyhow!("{} failed with HTTP status {}", method, code))?,
+            Err(e) => return Err(e.into()),
+        };
+        parse_response(method, response)
+    }
+}
+
+/// Extract the result of a JSON-RPC response
+pub fn parse_response(method: &str, mut response: Value) -> anyhow::Result<Value> {
+    if let Some(error) = response.get("error").filter(|e| !e.is_null()) {
+        let message = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown error");
+        return Err(anyhow::anyhow!("{} failed: {}", method, message));
+    }
+    response
+        .get_mut("result")
+        .map(Value::take)
+        .ok_or_else(|| anyhow::anyhow!("{} returned no result", method))
+}
+
+/// Lotus JSON form of a signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureJson {
+    #[serde(rename = "Type")]
+    pub sig_type: u8,
+    #[serde(rename = "Data")]
+    pub data: String,
+}
+
+impl From<&Signature> for SignatureJson {
+    fn from(signature: &Signature) -> Self {
+        Self {
+            sig_type: signature.signature_type() as u8,
+            data: base64::encode(signature.bytes()),
+        }
+    }
+}
+
+impl TryFrom<SignatureJson> for Signature {
+    type Error = anyhow::Error;
+
+    fn try_from(signature: SignatureJson) -> anyhow::Result<Self> {
+        let bytes = base64::decode(signature.data)?;
+        match signature.sig_type {
+            t if t == SignatureType::Secp256k1 as u8 => Ok(Signature::new_secp256k1(bytes)),
+            t if t == SignatureType::BLS as u8 => Ok(Signature::new_bls(bytes)),
+            t => Err(anyhow::anyhow!("unknown signature type {}", t)),
+        }
+    }
+}
+
+/// Lotus JSON form of MsgMeta
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MsgMetaJson {
+    #[serde(rename = "Type")]
+    pub msg_type: super::MsgType,
+    #[serde(rename = "Extra", default, with = "base64_opt")]
+    pub extra: Vec<u8>,
+}
+
+impl From<&MsgMeta> for MsgMetaJson {
+    fn from(meta: &MsgMeta) -> Self {
+        Self {
+            msg_type: meta.msg_type,
+            extra: meta.extra.clone(),
+        }
+    }
+}
+
+impl From<MsgMetaJson> for MsgMeta {
+    fn from(meta: MsgMetaJson) -> Self {
+        Self {
+            msg_type: meta.msg_type,
+            extra: meta.extra,
+        }
+    }
+}
+
+// Go encodes empty byte slices as null
+mod base64_opt {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if bytes.is_empty() {
+            serializer.serialize_none()
+        } else {
+            serializer.serialize_str(&base64::encode(bytes))
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded: Option<String> = Option::deserialize(deserializer)?;
+        match encoded {
+            Some(encoded) => base64::decode(encoded).map_err(serde::de::Error::custom),
+            None => Ok(vec![]),
+        }
+    }
+}
+
+/// Signer delegating to a remote wallet implementing the Lotus `WalletSign` API,
+/// such as a Lotus node or `lotus-wallet`.
+pub struct RemoteSigner<T: RpcTransport> {
+    transport: T,
+    address: FlairAddress,
+    wallet_type: WalletType,
+}
+
+impl<T: RpcTransport> RemoteSigner<T> {
+    /// signer for a secp256k1 (f1) or bls (f3) address held by the remote wallet
+    pub fn new(transport: T, address: FlairAddress) -> anyhow::Result<Self> {
+        let wallet_type = match address.protocol() {
+            AddressProtocol::Secp256k1 => WalletType::Secp256k1,
+            AddressProtocol::Bls => WalletType::Bls,
+            p => return Err(anyhow::anyhow!("{:?} addresses can't sign", p)),
+        };
+
+        let has = transport.call("Filecoin.WalletHas", json!([address.to_string()]))?;
+        if has != Value::Bool(true) {
+            return Err(anyhow::anyhow!(
+                "remote wallet doesn't hold the key for {}",
+                address
+            ));
+        }
+
+        Ok(Self {
+            transport,
+            address,
+            wallet_type,
+        })
+    }
+}
+
+impl<T: RpcTransport> Signer for RemoteSigner<T> {
+    fn address(&self) -> FlairAddress {
+        self.address
+    }
+
+    fn wallet_type(&self) -> WalletType {
+        self.wallet_type
+    }
+
+    fn sign_bytes(&self, data: &[u8], meta: &MsgMeta) -> anyhow::Result<Signature> {
+        let params = json!([
+            self.address.to_string(),
+            base64::encode(data),
+            MsgMetaJson::from(meta),
+        ]);
+        let result = self.transport.call("Filecoin.WalletSign", params)?;
+        let signature: Signature = serde_json::from_value::<SignatureJson>(result)?.try_into()?;
+
+        let expected = match self.wallet_type {
+            WalletType::Secp256k1 => SignatureType::Secp256k1,
+            WalletType::Bls => SignatureType::BLS,
+        };
+        if signature.signature_type() != expected {
+            return Err(anyhow::anyhow!(
+                "remote wallet returned a {:?} signature",
+                signature.signature_type()
+            ));
+        }
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+
+    use fvm_shared::address::Address;
+
+    use super::*;
+    use crate::models::account::FlairAccount;
+    use crate::signer::MsgType;
+
+    /// Remote wallet holding one local account
+    struct MockWallet {
+        account: FlairAccount,
+        calls: RefCell<Vec<(String, Value)>>,
+    }
+
+    impl RpcTransport for &MockWallet {
+        fn call(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+            self.calls
+                .borrow_mut()
+                .push((method.to_string(), params.clone()));
+            let response = match method {
+                "Filecoin.WalletHas" => json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": params[0] == self.account.display(),
+                }),
+                "Filecoin.WalletSign" => {
+                    let data = base64::decode(params[1].as_str().unwrap()).unwrap();
+                    let meta: MsgMetaJson = serde_json::from_value(params[2].clone()).unwrap();
+                    let signature = self.account.sign_bytes(&data, &meta.into()).unwrap();
+                    json!({"jsonrpc": "2.0", "id": 1, "result": SignatureJson::from(&signature)})
+                }
+                _ => {
+                    json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32601, "message": "method not found"}})
+                }
+            };
+            parse_response(method, response)
+        }
+    }
+
+    #[test]
+    fn test_remote_sign() {
+        let in_bls = "7b2254797065223a22626c73222c22507269766174654b6579223a2270657341657756666d382f6f7a574c736b6f767a7464677a62566d73677657695a70506f346d53367269493d227d";
+        let wallet = MockWallet {
+            account: FlairAccount::import(in_bls).unwrap(),
+            calls: RefCell::new(vec![]),
+        };
+
+        let signer = RemoteSigner::new(&wallet, wallet.account.get_address()).unwrap();
+        assert_eq!(signer.wallet_type(), WalletType::Bls);
+
+        let meta = MsgMeta {
+            msg_type: MsgType::ChainMsg,
+            extra: vec![1, 2, 3],
+        };
+        let signature = signer.sign_bytes(b"data", &meta).unwrap();
+        let local = wallet.account.sign_bytes(b"data", &meta).unwrap();
+        assert_eq!(signature, local);
+
+        let calls = wallet.calls.borrow();
+        assert_eq!(calls[1].0, "Filecoin.WalletSign");
+        assert_eq!(calls[1].1[2], json!({"Type": "message", "Extra": "AQID"}));
+    }
+
+    #[test]
+    fn test_remote_unknown_address() {
+        let in_bls = "7b2254797065223a22626c73222c22507269766174654b6579223a2270657341657756666d382f6f7a574c736b6f767a7464677a62566d73677657695a70506f346d53367269493d227d";
+        let wallet = MockWallet {
+            account: FlairAccount::import(in_bls).unwrap(),
+            calls: RefCell::new(vec![]),
+        };
+        let other = FlairAccount::generate(WalletType::Secp256k1).unwrap();
+        assert!(RemoteSigner::new(&wallet, other.get_address()).is_err());
+        assert!(RemoteSigner::new(&wallet, Address::new_id(5).into()).is_err());
+    }
+
+    #[test]
+    fn test_parse_response_error() {
+        let err = parse_response(
+            "Filecoin.WalletSign",
+            json!({"jsonrpc": "2.0", "id": 1, "error": {"code": 1, "message": "key not found"}}),
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Filecoin.WalletSign failed: key not found");
+    }
+}
@@ -0,0 +1,718 @@
+use std::{
+    fmt,
+    io::{Read, Write},
+};
+
+use cid::Cid;
+use fil_actor_states::{
+    collections::{make_map_with_root_and_bitwidth, Keyer, Map},
+    decode_state, map_get,
+    singletons::{INIT_ACTOR_ID, STORAGE_MARKET_ACTOR_ID, SYSTEM_ACTOR_ID},
+};
+use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
+use fvm_ipld_encoding::{tuple::*, CborStore};
+use fvm_shared::{address::Address, clock::ChainEpoch, econ::TokenAmount, ActorID};
+use serde::{
+    de::{self, DeserializeOwned, IgnoredAny, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+use crate::{
+    car::{load_car, write_car},
+    decode::ActorType,
+    manifest::Manifest,
+};
+
+// Actor states read straight from a state tree, so balances and locked funds
+// of our addresses can be checked from a snapshot without a node. Layouts are
+// the ones of actors v9, fields we don't use are skipped.
+
+// the market actor's escrow and locked tables, the market isn't vendored
+const BALANCE_TABLE_BITWIDTH: u32 = 6;
+
+/// Root of a versioned state tree
+#[derive(Debug, Clone, Deserialize_tuple)]
+struct StateRoot {
+    version: u64,
+    actors: Cid,
+    _info: Cid,
+}
+
+/// The parts of a block header leading to its state
+#[derive(Deserialize_tuple)]
+struct BlockHeader {
+    _miner: IgnoredAny,
+    _ticket: IgnoredAny,
+    _election_proof: IgnoredAny,
+    _beacon_entries: IgnoredAny,
+    _win_post_proof: IgnoredAny,
+    _parents: IgnoredAny,
+    _parent_weight: IgnoredAny,
+    height: ChainEpoch,
+    parent_state_root: Cid,
+    _parent_message_receipts: IgnoredAny,
+    _messages: IgnoredAny,
+    _bls_aggregate: IgnoredAny,
+    _timestamp: IgnoredAny,
+    _block_sig: IgnoredAny,
+    _fork_signaling: IgnoredAny,
+    _parent_base_fee: IgnoredAny,
+}
+
+/// An entry of the state tree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple)]
+pub struct ActorState {
+    pub code: Cid,
+    pub head: Cid,
+    pub sequence: u64,
+    pub balance: TokenAmount,
+}
+
+/// Newer trees append the delegated address, which is skipped
+impl<'de> Deserialize<'de> for ActorState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ActorVisitor;
+
+        impl<'de> Visitor<'de> for ActorVisitor {
+            type Value = ActorState;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an actor state tuple")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<ActorState, A::Error> {
+                let missing = |i| de::Error::invalid_length(i, &self);
+                let state = ActorState {
+                    code: seq.next_element()?.ok_or_else(|| missing(0))?,
+                    head: seq.next_element()?.ok_or_else(|| missing(1))?,
+                    sequence: seq.next_element()?.ok_or_else(|| missing(2))?,
+                    balance: seq.next_element()?.ok_or_else(|| missing(3))?,
+                };
+                while seq.next_element::<IgnoredAny>()?.is_some() {}
+                Ok(state)
+            }
+        }
+
+        deserializer.deserialize_seq(ActorVisitor)
+    }
+}
+
+#[derive(Deserialize_tuple)]
+struct SystemState {
+    builtin_actors: Cid,
+}
+
+#[derive(Deserialize_tuple)]
+struct InitState {
+    address_map: Cid,
+    _next_id: u64,
+    _network_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize_tuple)]
+pub struct AccountState {
+    pub address: Address,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize_tuple)]
+pub struct MultisigState {
+    pub signers: Vec<Address>,
+    pub num_approvals_threshold: u64,
+    pub next_tx_id: i64,
+    pub initial_balance: TokenAmount,
+    pub start_epoch: ChainEpoch,
+    pub unlock_duration: ChainEpoch,
+    pub pending_txs: Cid,
+}
+
+impl MultisigState {
+    /// Balance still vesting at `epoch`, rounded up like the actor does
+    pub fn amount_locked(&self, epoch: ChainEpoch) -> TokenAmount {
+        let elapsed = epoch - self.start_epoch;
+        if elapsed >= self.unlock_duration {
+            return TokenAmount::from_atto(0);
+        }
+        if elapsed <= 0 {
+            return self.initial_balance.clone();
+        }
+        let remaining = self.unlock_duration - elapsed;
+        let numerator = self.initial_balance.atto() * remaining;
+        let denominator = self.unlock_duration;
+        TokenAmount::from_atto((numerator + denominator - 1) / denominator)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize_tuple)]
+pub struct MinerState {
+    pub info: Cid,
+    pub pre_commit_deposits: TokenAmount,
+    /// vesting block rewards
+    pub locked_funds: TokenAmount,
+    _vesting_funds: IgnoredAny,
+    pub fee_debt: TokenAmount,
+    pub initial_pledge: TokenAmount,
+    _pre_committed_sectors: IgnoredAny,
+    _pre_committed_sectors_cleanup: IgnoredAny,
+    _allocated_sectors: IgnoredAny,
+    _sectors: IgnoredAny,
+    _proving_period_start: IgnoredAny,
+    _current_deadline: IgnoredAny,
+    _deadlines: IgnoredAny,
+    _early_terminations: IgnoredAny,
+    _deadline_cron_active: IgnoredAny,
+}
+
+impl MinerState {
+    /// Funds the miner can't withdraw, fee debt aside
+    pub fn locked(&self) -> TokenAmount {
+        &self.locked_funds + &self.pre_commit_deposits + &self.initial_pledge
+    }
+}
+
+#[derive(Debug, Clone, Deserialize_tuple)]
+pub struct MinerInfo {
+    pub owner: Address,
+    pub worker: Address,
+    pub control_addresses: Vec<Address>,
+    _pending_worker_key: IgnoredAny,
+    _peer_id: IgnoredAny,
+    _multi_address: IgnoredAny,
+    _window_post_proof_type: IgnoredAny,
+    _sector_size: IgnoredAny,
+    _window_post_partition_sectors: IgnoredAny,
+    _consensus_fault_elapsed: IgnoredAny,
+    _pending_owner_address: IgnoredAny,
+    pub beneficiary: Address,
+    _beneficiary_term: IgnoredAny,
+    _pending_beneficiary_term: IgnoredAny,
+}
+
+#[derive(Deserialize_tuple)]
+struct MarketState {
+    _proposals: IgnoredAny,
+    _states: IgnoredAny,
+    _pending_proposals: IgnoredAny,
+    escrow_table: Cid,
+    locked_table: Cid,
+    _next_id: IgnoredAny,
+    _deal_ops_by_epoch: IgnoredAny,
+    _last_cron: IgnoredAny,
+    _total_client_locked_collateral: IgnoredAny,
+    _total_provider_locked_collateral: IgnoredAny,
+    _total_client_storage_fee: IgnoredAny,
+    _pending_deal_allocation_ids: IgnoredAny,
+}
+
+/// What an address holds at an epoch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Holding {
+    pub address: Address,
+    /// None when the address isn't on chain
+    pub id: Option<ActorID>,
+    pub actor: Option<ActorType>,
+    pub balance: TokenAmount,
+    /// vesting multisig funds, or the locked rewards, deposits and pledge
+    /// of a miner
+    pub locked: TokenAmount,
+    pub available: TokenAmount,
+    pub market_escrow: TokenAmount,
+    pub market_locked: TokenAmount,
+}
+
+/// A state tree in a blockstore
+pub struct StateTree<BS> {
+    store: BS,
+    actors: Cid,
+    manifest: Manifest,
+}
+
+impl<BS: Blockstore> StateTree<BS> {
+    pub fn new(store: BS, root: &Cid) -> anyhow::Result<Self> {
+        let root: StateRoot = store
+            .get_cbor(root)?
+            .ok_or_else(|| anyhow::anyhow!("state root {} not found", root))?;
+        if root.version == 0 {
+            return Err(anyhow::anyhow!("unversioned state trees are not supported"));
+        }
+        let mut tree = Self {
+            store,
+            actors: root.actors,
+            manifest: Manifest::default(),
+        };
+        tree.manifest = tree.load_manifest()?;
+        Ok(tree)
+    }
+
+    pub fn store(&self) -> &BS {
+        &self.store
+    }
+
+    /// Builtin actor code CIDs, from the system actor
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    fn load_manifest(&self) -> anyhow::Result<Manifest> {
+        let system = self
+            .actor_by_id(SYSTEM_ACTOR_ID)?
+            .ok_or_else(|| anyhow::anyhow!("no system actor in the state"))?;
+        let system: SystemState = self.get(&system.head)?;
+        let entries: Vec<(String, Cid)> = self.get(&system.builtin_actors)?;
+        Ok(Manifest::new(entries.into_iter().collect()))
+    }
+
+    fn get<T: DeserializeOwned>(&self, cid: &Cid) -> anyhow::Result<T> {
+        decode_state(&self.store, cid)
+    }
+
+    fn actor_by_id(&self, id: ActorID) -> anyhow::Result<Option<ActorState>> {
+        map_get(&self.store, &self.actors, &Address::new_id(id))
+    }
+
+    /// ID of `address`, through the address map of the init actor
+    pub fn lookup_id(&self, address: &Address) -> anyhow::Result<Option<ActorID>> {
+        if let Ok(id) = address.id() {
+            return Ok(Some(id));
+        }
+        let init = self
+            .actor_by_id(INIT_ACTOR_ID)?
+            .ok_or_else(|| anyhow::anyhow!("no init actor in the state"))?;
+        let init: InitState = self.get(&init.head)?;
+        map_get(&self.store, &init.address_map, address)
+    }
+
+    pub fn get_actor(&self, address: &Address) -> anyhow::Result<Option<ActorState>> {
+        match self.lookup_id(address)? {
+            Some(id) => self.actor_by_id(id),
+            None => Ok(None),
+        }
+    }
+
+    pub fn actor_type(&self, actor: &ActorState) -> Option<ActorType> {
+        self.manifest.actor_type(&actor.code)
+    }
+
+    /// CAR file of the state of `address` and everything it links to
+    pub fn export_actor_state<W: Write>(&self, address: &Address, writer: W) -> anyhow::Result<W> {
+        let actor = self
+            .get_actor(address)?
+            .ok_or_else(|| anyhow::anyhow!("actor {} not found", address))?;
+        write_car(&self.store, &[actor.head], writer)
+    }
+
+    /// State of `actor`, decoded as `S`
+    pub fn actor_state<S: DeserializeOwned>(&self, actor: &ActorState) -> anyhow::Result<S> {
+        self.get(&actor.head)
+    }
+
+    pub fn miner_info(&self, state: &MinerState) -> anyhow::Result<MinerInfo> {
+        self.get(&state.info)
+    }
+
+    /// Escrow and locked balance of the actor `id` in the storage market
+    pub fn market_balance(&self, id: ActorID) -> anyhow::Result<(TokenAmount, TokenAmount)> {
+        let market = self
+            .actor_by_id(STORAGE_MARKET_ACTOR_ID)?
+            .ok_or_else(|| anyhow::anyhow!("no storage market actor in the state"))?;
+        let market: MarketState = self.get(&market.head)?;
+        let key = Address::new_id(id).key();
+        let balance = |table: &Cid| -> anyhow::Result<TokenAmount> {
+            let table: Map<_, TokenAmount> =
+                make_map_with_root_and_bitwidth(table, &self.store, BALANCE_TABLE_BITWIDTH)?;
+            Ok(table
+                .get(&key)?
+                .cloned()
+                .unwrap_or_else(|| TokenAmount::from_atto(0)))
+        };
+        Ok((
+            balance(&market.escrow_table)?,
+            balance(&market.locked_table)?,
+        ))
+    }
+
+    /// Balances of `address` at `epoch`, the epoch only matters to vesting
+    pub fn holding(&self, address: &Address, epoch: ChainEpoch) -> anyhow::Result<Holding> {
+        let zero = || TokenAmount::from_atto(0);
+        let mut holding = Holding {
+            address: *address,
+            id: None,
+            actor: None,
+            balance: zero(),
+            locked: zero(),
+            available: zero(),
+            market_escrow: zero(),
+            market_locked: zero(),
+        };
+        let id = match self.lookup_id(address)? {
+            Some(id) => id,
+            None => return Ok(holding),
+        };
+        let actor = match self.actor_by_id(id)? {
+            Some(actor) => actor,
+            None => return Ok(holding),
+        };
+        holding.id = Some(id);
+        holding.actor = self.actor_type(&actor);
+        holding.balance = actor.balance.clone();
+        holding.available = match holding.actor {
+            Some(ActorType::Multisig) => {
+                let state: MultisigState = self.actor_state(&actor)?;
+                holding.locked = state.amount_locked(epoch);
+                &actor.balance - &holding.locked
+            }
+            Some(ActorType::Miner) => {
+                let state: MinerState = self.actor_state(&actor)?;
+                holding.locked = state.locked();
+                &actor.balance - &holding.locked - &state.fee_debt
+            }
+            _ => actor.balance.clone(),
+        };
+        let (escrow, locked) = self.market_balance(id)?;
+        holding.market_escrow = escrow;
+        holding.market_locked = locked;
+        Ok(holding)
+    }
+}
+
+/// A state tree loaded from a CAR file, with the epoch of the tipset when the
+/// file is a chain snapshot
+pub struct Snapshot {
+    pub state: StateTree<MemoryBlockstore>,
+    pub epoch: Option<ChainEpoch>,
+}
+
+impl Snapshot {
+    /// Load a chain snapshot, whose first root is a block header, or a state
+    /// export whose root is the state root
+    pub fn load<R: Read>(reader: R) -> anyhow::Result<Self> {
+        let store = MemoryBlockstore::new();
+        let roots =
+            load_car(&store, reader).map_err(|e| anyhow::anyhow!("invalid CAR file: {}", e))?;
+        let root = *roots
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("CAR file without a root"))?;
+        let (root, epoch) = match store.get_cbor::<BlockHeader>(&root) {
+            Ok(Some(header)) => (header.parent_state_root, Some(header.height)),
+            _ => (root, None),
+        };
+        Ok(Self {
+            state: StateTree::new(store, &root)?,
+            epoch,
+        })
+    }
+
+    /// Holdings of `addresses`, at the snapshot's epoch unless given
+    pub fn holdings(
+        &self,
+        addresses: &[Address],
+        epoch: Option<ChainEpoch>,
+    ) -> anyhow::Result<Vec<Holding>> {
+        let epoch = epoch
+            .or(self.epoch)
+            .ok_or_else(|| anyhow::anyhow!("the epoch of a state export must be given"))?;
+        addresses
+            .iter()
+            .map(|address| self.state.holding(address, epoch))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+
+    use cid::multihash::{Code, Multihash};
+    use serde::Serialize;
+
+    use fil_actor_states::collections::{make_empty_map, HAMT_BIT_WIDTH};
+
+    use super::*;
+    use crate::car::{CarReader, CarWriter};
+
+    /// Keeps the CIDs it is given, in order, to write them out
+    #[derive(Default)]
+    struct Recorder {
+        blocks: MemoryBlockstore,
+        cids: RefCell<Vec<Cid>>,
+    }
+
+    impl Blockstore for Recorder {
+        fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+            self.blocks.get(k)
+        }
+
+        fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+            if !self.cids.borrow().contains(k) {
+                self.cids.borrow_mut().push(*k);
+            }
+            self.blocks.put_keyed(k, block)
+        }
+    }
+
+    fn code(name: &str) -> Cid {
+        Cid::new_v1(0x55, Multihash::wrap(0, name.as_bytes()).unwrap())
+    }
+
+    fn fil(n: i64) -> TokenAmount {
+        TokenAmount::from_whole(n)
+    }
+
+    fn put<S: Serialize>(store: &Recorder, obj: &S) -> Cid {
+        store.put_cbor(obj, Code::Blake2b256).unwrap()
+    }
+
+    /// CARv1 bytes of `blocks` under `root`
+    fn car(store: &Recorder, root: &Cid, blocks: &[Cid]) -> Vec<u8> {
+        let mut car = CarWriter::new(vec![], &[*root]).unwrap();
+        for cid in blocks {
+            car.write_block(cid, &store.get(cid).unwrap().unwrap())
+                .unwrap();
+        }
+        car.finish().unwrap()
+    }
+
+    /// A tree with an account, a vesting multisig, a miner and market
+    /// balances, and every block CID put in it
+    fn state(store: &Recorder) -> (Cid, Address, Address, Address) {
+        let manifest: Vec<(String, Cid)> = [
+            "system",
+            "init",
+            "account",
+            "multisig",
+            "storageminer",
+            "storagemarket",
+        ]
+        .iter()
+        .map(|name| (name.to_string(), code(name)))
+        .collect();
+        let system = put(store, &(put(store, &manifest),));
+
+        let key = Address::new_bls(&[1; 48]).unwrap();
+        let msig = Address::new_actor(b"msig");
+        let mut address_map = make_empty_map::<_, ActorID>(store, HAMT_BIT_WIDTH);
+        address_map.set(key.key(), 100).unwrap();
+        address_map.set(msig.key(), 101).unwrap();
+        let init = put(
+            store,
+            &(address_map.flush().unwrap(), 103u64, "testnet".to_string()),
+        );
+
+        let account = put(store, &(key,));
+        let empty = put(store, &Vec::<u8>::new());
+        let multisig = put(
+            store,
+            &(
+                vec![Address::new_id(100)],
+                1u64,
+                0i64,
+                fil(100),
+                1000i64,
+                100i64,
+                empty,
+            ),
+        );
+        let info = put(
+            store,
+            &(
+                Address::new_id(100),
+                Address::new_id(100),
+                Vec::<Address>::new(),
+                (),
+                (),
+                (),
+                0,
+                0,
+                0,
+                0,
+                (),
+                Address::new_id(100),
+                (),
+                (),
+            ),
+        );
+        let miner = put(
+            store,
+            &(
+                info,
+                fil(1),
+                fil(2),
+                empty,
+                fil(1),
+                fil(3),
+                empty,
+                empty,
+                empty,
+                empty,
+                0,
+                0,
+                empty,
+                (),
+                false,
+            ),
+        );
+
+        let table = |amount: TokenAmount| {
+            let mut table = make_empty_map(store, BALANCE_TABLE_BITWIDTH);
+            table.set(Address::new_id(100).key(), amount).unwrap();
+            table.flush().unwrap()
+        };
+        let (escrow, locked_table) = (table(fil(5)), table(fil(2)));
+        // the v9 market state
+        let market = put(
+            store,
+            &(
+                empty,
+                empty,
+                empty,
+                escrow,
+                locked_table,
+                0,
+                empty,
+                0,
+                fil(2),
+                fil(0),
+                fil(0),
+                empty,
+            ),
+        );
+
+        let mut actors = make_empty_map(store, HAMT_BIT_WIDTH);
+        for (id, name, head, balance) in [
+            (SYSTEM_ACTOR_ID, "system", system, fil(0)),
+            (INIT_ACTOR_ID, "init", init, fil(0)),
+            (STORAGE_MARKET_ACTOR_ID, "storagemarket", market, fil(7)),
+            (100, "account", account, fil(10)),
+            (101, "multisig", multisig, fil(100)),
+            (102, "storageminer", miner, fil(10)),
+        ] {
+            actors
+                .set(
+                    Address::new_id(id).key(),
+                    ActorState {
+                        code: code(name),
+                        head,
+                        sequence: 0,
+                        balance,
+                    },
+                )
+                .unwrap();
+        }
+        let root = put(store, &(4u64, actors.flush().unwrap(), empty));
+        (root, key, msig, Address::new_id(102))
+    }
+
+    #[test]
+    fn test_state_tree() {
+        let store = Recorder::default();
+        let (root, key, msig, miner) = state(&store);
+        let tree = StateTree::new(&store, &root).unwrap();
+        assert_eq!(tree.lookup_id(&key).unwrap(), Some(100));
+        assert_eq!(tree.lookup_id(&Address::new_id(7)).unwrap(), Some(7));
+        assert_eq!(
+            tree.lookup_id(&Address::new_actor(b"unknown")).unwrap(),
+            None
+        );
+
+        let account = tree.holding(&key, 1050).unwrap();
+        assert_eq!(account.id, Some(100));
+        assert_eq!(account.actor, Some(ActorType::Account));
+        assert_eq!(account.available, fil(10));
+        assert_eq!(account.market_escrow, fil(5));
+        assert_eq!(account.market_locked, fil(2));
+        let state: AccountState = tree
+            .actor_state(&tree.get_actor(&key).unwrap().unwrap())
+            .unwrap();
+        assert_eq!(state.address, key);
+
+        // half of the unlock duration elapsed
+        let multisig = tree.holding(&msig, 1050).unwrap();
+        assert_eq!(multisig.actor, Some(ActorType::Multisig));
+        assert_eq!(multisig.locked, fil(50));
+        assert_eq!(multisig.available, fil(50));
+        assert_eq!(tree.holding(&msig, 900).unwrap().locked, fil(100));
+        assert_eq!(tree.holding(&msig, 1100).unwrap().locked, fil(0));
+
+        let holding = tree.holding(&miner, 0).unwrap();
+        assert_eq!(holding.actor, Some(ActorType::Miner));
+        assert_eq!(holding.locked, fil(6));
+        assert_eq!(holding.available, fil(3));
+        let state: MinerState = tree
+            .actor_state(&tree.get_actor(&miner).unwrap().unwrap())
+            .unwrap();
+        assert_eq!(tree.miner_info(&state).unwrap().owner, Address::new_id(100));
+
+        let unknown = tree.holding(&Address::new_actor(b"unknown"), 0).unwrap();
+        assert_eq!(unknown.id, None);
+        assert_eq!(unknown.balance, fil(0));
+    }
+
+    #[test]
+    fn test_amount_locked() {
+        let state = MultisigState {
+            signers: vec![],
+            num_approvals_threshold: 1,
+            next_tx_id: 0,
+            initial_balance: TokenAmount::from_atto(10),
+            start_epoch: 0,
+            unlock_duration: 3,
+            pending_txs: code("empty"),
+        };
+        // 10 * 2 / 3 rounds up
+        assert_eq!(state.amount_locked(1), TokenAmount::from_atto(7));
+        assert_eq!(state.amount_locked(3), TokenAmount::from_atto(0));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let store = Recorder::default();
+        let (root, key, _, _) = state(&store);
+        let export = car(&store, &root, &store.cids.borrow());
+        let snapshot = Snapshot::load(&export[..]).unwrap();
+        assert_eq!(snapshot.epoch, None);
+        assert!(snapshot.holdings(&[key], None).is_err());
+        let holdings = snapshot.holdings(&[key], Some(10)).unwrap();
+        assert_eq!(holdings[0].balance, fil(10));
+
+        // a chain snapshot, rooted at a block header
+        let header = put(
+            &store,
+            &(
+                (),
+                (),
+                (),
+                (),
+                (),
+                (),
+                (),
+                1234i64,
+                root,
+                (),
+                (),
+                (),
+                (),
+                (),
+                (),
+                (),
+            ),
+        );
+        let blocks = store.cids.borrow().clone();
+        let snapshot = Snapshot::load(&car(&store, &header, &blocks)[..]).unwrap();
+        assert_eq!(snapshot.epoch, Some(1234));
+        assert_eq!(snapshot.holdings(&[key], None).unwrap()[0].id, Some(100));
+
+        assert!(Snapshot::load(&b"not a car"[..]).is_err());
+
+        let tree = StateTree::new(&store, &root).unwrap();
+        let export = tree.export_actor_state(&key, vec![]).unwrap();
+        let account = tree.get_actor(&key).unwrap().unwrap();
+        let blocks = CarReader::new(&export[..])
+            .unwrap()
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].0, account.head);
+        assert!(tree
+            .export_actor_state(&Address::new_id(999), vec![])
+            .is_err());
+    }
+}
@@ -1,41 +1,62 @@
+pub use fil_actor_states::ChainType;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
-use structopt::StructOpt;
 
 // any error type implementing Display is acceptable.
 type ParseError = &'static str;
 
-/// chain network type: mainnet, calibnet
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
-pub enum ChainType {
+/// how the seed is derived from a secret phrase
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(tag = "Type")]
+pub enum SeedDerivation {
+    /// BIP39 seed: PBKDF2-HMAC-SHA512 with 2048 rounds
     #[default]
-    Mainnet,
-    Calibnet,
+    Bip39,
+    /// scrypt with N = 2^log_n, the memory-hard alternative for high-value accounts
+    Scrypt {
+        #[serde(rename = "LogN")]
+        log_n: u8,
+        #[serde(rename = "R")]
+        r: u32,
+        #[serde(rename = "P")]
+        p: u32,
+    },
 }
-impl FromStr for ChainType {
-    type Err = ParseError;
-    fn from_str(chain: &str) -> Result<Self, Self::Err> {
-        match chain.to_lowercase().as_str() {
-            "mainnet" => Ok(ChainType::Mainnet),
-            "calibnet" => Ok(ChainType::Calibnet),
-            _ => Err("Chain type not supported"),
+
+impl SeedDerivation {
+    /// scrypt with the parameters crypto-wallet-gen uses, higher than the ones proposed in BIP38
+    pub fn scrypt_default() -> Self {
+        SeedDerivation::Scrypt {
+            log_n: 21,
+            r: 8,
+            p: 8,
         }
     }
 }
 
-//StructOpt
-#[derive(Debug, Default, StructOpt, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
 pub enum WalletType {
     #[default]
     Secp256k1,
     Bls,
 }
 
-impl ToString for WalletType {
-    fn to_string(&self) -> String {
+impl FromStr for WalletType {
+    type Err = ParseError;
+    fn from_str(wallet_type: &str) -> Result<Self, Self::Err> {
+        match wallet_type.to_lowercase().as_str() {
+            "secp256k1" => Ok(WalletType::Secp256k1),
+            "bls" => Ok(WalletType::Bls),
+            _ => Err("Wallet type not supported"),
+        }
+    }
+}
+
+impl std::fmt::Display for WalletType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            WalletType::Bls => "bls".to_string(),
-            WalletType::Secp256k1 => "secp256k1".to_string(),
+            WalletType::Bls => write!(f, "bls"),
+            WalletType::Secp256k1 => write!(f, "secp256k1"),
         }
     }
 }
@@ -0,0 +1,324 @@
+use cid::Cid;
+use fvm_ipld_encoding::{tuple::*, RawBytes};
+use fvm_shared::{
+    address::Address,
+    bigint::{bigint_ser, BigInt},
+    clock::ChainEpoch,
+    commcid::cid_to_data_commitment_v1,
+    econ::TokenAmount,
+    message::Message,
+    piece::PaddedPieceSize,
+    sector::StoragePower,
+    MethodNum,
+};
+
+use crate::{
+    actors::{Policy, VERIFIED_REGISTRY_ACTOR_ADDR},
+    datacap,
+    message::MessageBuilder,
+};
+
+// verified registry actor methods and params, as of actors v9
+
+pub const METHOD_ADD_VERIFIER: MethodNum = 2;
+pub const METHOD_REMOVE_VERIFIER: MethodNum = 3;
+pub const METHOD_ADD_VERIFIED_CLIENT: MethodNum = 4;
+
+pub type ClaimID = u64;
+
+/// AddVerifier and AddVerifiedClient, `allowance` in bytes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct VerifierParams {
+    pub address: Address,
+    #[serde(with = "bigint_ser")]
+    pub allowance: StoragePower,
+}
+
+/// Message from a notary granting `allowance` bytes of DataCap to `client`
+pub fn add_verified_client_message(
+    policy: &Policy,
+    from: Address,
+    client: Address,
+    allowance: StoragePower,
+) -> anyhow::Result<Message> {
+    if allowance < policy.minimum_verified_allocation_size {
+        return Err(anyhow::anyhow!(
+            "allowance of {} bytes is below the minimum of {}",
+            allowance,
+            policy.minimum_verified_allocation_size
+        ));
+    }
+    let params = VerifierParams {
+        address: client,
+        allowance,
+    };
+    Ok(MessageBuilder::new(from, VERIFIED_REGISTRY_ACTOR_ADDR)
+        .method(METHOD_ADD_VERIFIED_CLIENT)
+        .params(RawBytes::serialize(params)?)
+        .build())
+}
+
+/// Data a client allocates DataCap to, for `provider` to claim by sealing it
+/// before `expiration` and keep for a term within `term_min..=term_max`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct AllocationRequest {
+    pub provider: Address,
+    pub data: Cid,
+    pub size: PaddedPieceSize,
+    pub term_min: ChainEpoch,
+    pub term_max: ChainEpoch,
+    pub expiration: ChainEpoch,
+}
+
+/// Longer term for a claim of `provider`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct ClaimExtensionRequest {
+    pub provider: Address,
+    pub claim: ClaimID,
+    pub term_max: ChainEpoch,
+}
+
+/// Operator data of a DataCap transfer to the verified registry
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+pub struct AllocationRequests {
+    pub allocations: Vec<AllocationRequest>,
+    pub extensions: Vec<ClaimExtensionRequest>,
+}
+
+impl AllocationRequest {
+    /// The checks of the verified registry, `current_epoch` being the epoch
+    /// the transfer would land at
+    pub fn validate(&self, policy: &Policy, current_epoch: ChainEpoch) -> anyhow::Result<()> {
+        cid_to_data_commitment_v1(&self.data)
+            .map_err(|e| anyhow::anyhow!("invalid piece CID {}: {}", self.data, e))?;
+        self.size
+            .validate()
+            .map_err(|e| anyhow::anyhow!("invalid piece size {}: {}", self.size.0, e))?;
+        if BigInt::from(self.size.0) < policy.minimum_verified_allocation_size {
+            return Err(anyhow::anyhow!(
+                "allocation of {} bytes is below the minimum of {}",
+                self.size.0,
+                policy.minimum_verified_allocation_size
+            ));
+        }
+        if self.term_min < policy.minimum_verified_allocation_term {
+            return Err(anyhow::anyhow!(
+                "minimum term {} is below the policy minimum of {}",
+                self.term_min,
+                policy.minimum_verified_allocation_term
+            ));
+        }
+        if self.term_max > policy.maximum_verified_allocation_term {
+            return Err(anyhow::anyhow!(
+                "maximum term {} is above the policy maximum of {}",
+                self.term_max,
+                policy.maximum_verified_allocation_term
+            ));
+        }
+        if self.term_min > self.term_max {
+            return Err(anyhow::anyhow!(
+                "minimum term {} is above the maximum term {}",
+                self.term_min,
+                self.term_max
+            ));
+        }
+        if self.expiration < current_epoch {
+            return Err(anyhow::anyhow!(
+                "allocation expires at {}, before the current epoch {}",
+                self.expiration,
+                current_epoch
+            ));
+        }
+        let max_expiration = current_epoch + policy.maximum_verified_allocation_expiration;
+        if self.expiration > max_expiration {
+            return Err(anyhow::anyhow!(
+                "allocation expires at {}, after the policy maximum of {}",
+                self.expiration,
+                max_expiration
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl AllocationRequests {
+    /// DataCap the transfer has to carry, the sum of the allocation sizes.
+    /// Extensions are paid for in DataCap as well, by the size of their
+    /// claim, which only the chain state knows.
+    pub fn datacap(&self) -> TokenAmount {
+        datacap::bytes_to_datacap(self.allocations.iter().map(|a| a.size.0).sum())
+    }
+
+    pub fn validate(&self, policy: &Policy, current_epoch: ChainEpoch) -> anyhow::Result<()> {
+        if self.allocations.is_empty() && self.extensions.is_empty() {
+            return Err(anyhow::anyhow!("no allocation nor extension requested"));
+        }
+        for (i, allocation) in self.allocations.iter().enumerate() {
+            allocation
+                .validate(policy, current_epoch)
+                .map_err(|e| anyhow::anyhow!("allocation {}: {}", i, e))?;
+        }
+        for (i, extension) in self.extensions.iter().enumerate() {
+            if extension.term_max > policy.maximum_verified_allocation_term {
+                return Err(anyhow::anyhow!(
+                    "extension {}: maximum term {} is above the policy maximum of {}",
+                    i,
+                    extension.term_max,
+                    policy.maximum_verified_allocation_term
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// DataCap transfer from a client to the verified registry creating
+/// allocations, for requests without extensions
+pub fn allocate_message(
+    policy: &Policy,
+    from: Address,
+    requests: &AllocationRequests,
+    current_epoch: ChainEpoch,
+) -> anyhow::Result<Message> {
+    requests.validate(policy, current_epoch)?;
+    if !requests.extensions.is_empty() {
+        return Err(anyhow::anyhow!(
+            "extensions need the size of their claims, use allocate_message_with_amount"
+        ));
+    }
+    allocate_message_with_amount(from, requests, requests.datacap())
+}
+
+/// DataCap transfer to the verified registry carrying `amount`, which has to
+/// match what the requests cost. Requests are not validated.
+pub fn allocate_message_with_amount(
+    from: Address,
+    requests: &AllocationRequests,
+    amount: TokenAmount,
+) -> anyhow::Result<Message> {
+    datacap::transfer_message(
+        from,
+        VERIFIED_REGISTRY_ACTOR_ADDR,
+        amount,
+        RawBytes::serialize(requests)?,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use fvm_shared::commcid::data_commitment_v1_to_cid;
+
+    use super::*;
+    use crate::actors::{DATACAP_TOKEN_ACTOR_ADDR, EPOCHS_IN_DAY};
+
+    fn request(policy: &Policy) -> AllocationRequest {
+        AllocationRequest {
+            provider: Address::new_id(1000),
+            data: data_commitment_v1_to_cid(&[3; 32]).unwrap(),
+            size: PaddedPieceSize(1 << 20),
+            term_min: policy.minimum_verified_allocation_term,
+            term_max: policy.maximum_verified_allocation_term,
+            expiration: 100 + 30 * EPOCHS_IN_DAY,
+        }
+    }
+
+    #[test]
+    fn test_add_verified_client() {
+        let policy = Policy::mainnet();
+        let message = add_verified_client_message(
+            &policy,
+            Address::new_id(1000),
+            Address::new_id(1001),
+            BigInt::from(1 << 30),
+        )
+        .unwrap();
+        assert_eq!(message.to, VERIFIED_REGISTRY_ACTOR_ADDR);
+        assert_eq!(message.method_num, METHOD_ADD_VERIFIED_CLIENT);
+        let params: VerifierParams = message.params.deserialize().unwrap();
+        assert_eq!(params.allowance, BigInt::from(1 << 30));
+        assert!(add_verified_client_message(
+            &policy,
+            Address::new_id(1000),
+            Address::new_id(1001),
+            BigInt::from(1024),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_allocation_requests() {
+        let policy = Policy::mainnet();
+        let requests = AllocationRequests {
+            allocations: vec![request(&policy), request(&policy)],
+            extensions: vec![],
+        };
+        let message = allocate_message(&policy, Address::new_id(1001), &requests, 100).unwrap();
+        assert_eq!(message.to, DATACAP_TOKEN_ACTOR_ADDR);
+        assert_eq!(message.method_num, datacap::METHOD_TRANSFER_EXPORTED);
+        let params: datacap::TransferParams = message.params.deserialize().unwrap();
+        assert_eq!(params.to, VERIFIED_REGISTRY_ACTOR_ADDR);
+        assert_eq!(params.amount, datacap::bytes_to_datacap(2 << 20));
+        assert_eq!(
+            params
+                .operator_data
+                .deserialize::<AllocationRequests>()
+                .unwrap(),
+            requests
+        );
+
+        let base = request(&policy);
+        let invalid = [
+            AllocationRequest {
+                size: PaddedPieceSize(1 << 19),
+                ..base.clone()
+            },
+            AllocationRequest {
+                size: PaddedPieceSize(3 << 20),
+                ..base.clone()
+            },
+            AllocationRequest {
+                term_min: policy.minimum_verified_allocation_term - 1,
+                ..base.clone()
+            },
+            AllocationRequest {
+                term_max: policy.maximum_verified_allocation_term + 1,
+                ..base.clone()
+            },
+            AllocationRequest {
+                term_min: policy.minimum_verified_allocation_term + 10,
+                term_max: policy.minimum_verified_allocation_term,
+                ..base.clone()
+            },
+            AllocationRequest {
+                expiration: 99,
+                ..base.clone()
+            },
+            AllocationRequest {
+                expiration: 101 + policy.maximum_verified_allocation_expiration,
+                ..base.clone()
+            },
+        ];
+        for allocation in invalid {
+            let requests = AllocationRequests {
+                allocations: vec![allocation],
+                extensions: vec![],
+            };
+            assert!(allocate_message(&policy, Address::new_id(1001), &requests, 100).is_err());
+        }
+        assert!(AllocationRequests::default()
+            .validate(&policy, 100)
+            .is_err());
+
+        // extensions cost the size of their claim
+        let extension = AllocationRequests {
+            allocations: vec![],
+            extensions: vec![ClaimExtensionRequest {
+                provider: Address::new_id(1000),
+                claim: 4,
+                term_max: policy.maximum_verified_allocation_term,
+            }],
+        };
+        extension.validate(&policy, 100).unwrap();
+        assert!(allocate_message(&policy, Address::new_id(1001), &extension, 100).is_err());
+    }
+}